@@ -5,24 +5,31 @@ use serenity::model::prelude::*;
 
 use crate::bot::helpers::channels::GuildChannelDefinition;
 use crate::bot::helpers::channels::GuildChannelDefinitionBuilder;
+use crate::bot::helpers::HelperResult;
 use crate::bot::Bot;
+use crate::models::Team;
 
-static STAFF_CATEGORY_NAME: &str = "ICTSC2025 Staff";
-
-// Discordの使い方を案内するread-onlyなチャンネル
-static HELP_CHANNEL_NAME: &str = "help";
-
-// 協議開始等のアナウンスを流すread-onlyなチャンネル
-static ANNOUNCE_CHANNEL_NAME: &str = "announce";
-
-// プラチナスポンサーの方々からのメッセージを流すread-onlyなチャンネル
-static PLATINUM_SPONSORS_NAME: &str = "platinum-sponsors";
+impl Bot {
+    // チームやstaff向けのテキストチャンネル名を返す（例: team1-text）
+    pub fn text_channel_name(&self, prefix: &str) -> String {
+        format!("{}-{}", prefix, self.channels.text_suffix)
+    }
 
-// 参加者が自由に読み書きできるチャンネル
-static RANDOM_CHANNEL_NAME: &str = "random";
+    // チームやstaff向けのボイスチャンネル名を返す（例: team1-voice）
+    pub fn voice_channel_name(&self, prefix: &str) -> String {
+        format!("{}-{}", prefix, self.channels.voice_suffix)
+    }
 
-static TEXT_CHANNEL_NAME_SUFFIX: &str = "text";
-static VOICE_CHANNEL_NAME_SUFFIX: &str = "voice";
+    // チームのテキストチャンネル（例: team1-text）を探す。
+    pub async fn find_team_text_channel(&self, team: &Team) -> HelperResult<Option<GuildChannel>> {
+        let name = self.text_channel_name(&team.id);
+        Ok(self
+            .get_channels(&[ChannelType::Text])
+            .await?
+            .into_iter()
+            .find(|channel| channel.name == name))
+    }
+}
 
 impl Bot {
     #[tracing::instrument(skip_all)]
@@ -31,13 +38,46 @@ impl Bot {
 
         self.update_role_cache().await?;
 
+        let categories = self.define_categories()?;
+        self._sync_channels(&[ChannelType::Category], categories)
+            .await?;
+
+        tracing::info!("sync channels");
+
+        let category_map: HashMap<_, _> = self
+            .get_channels(&[ChannelType::Category])
+            .await?
+            .into_iter()
+            .map(|category| (category.name.clone(), category.id))
+            .collect();
+
+        let channels = self.define_channels(&category_map).await?;
+        self._sync_channels(&[ChannelType::Text, ChannelType::Voice], channels)
+            .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn delete_channels(&self) -> Result<()> {
+        tracing::info!("delete all channels");
+        for (channel_id, channel) in self.guild_id.channels(&self.discord_client).await? {
+            tracing::debug!(?channel, "delete channel");
+            channel_id.delete(&self.discord_client).await?;
+        }
+        Ok(())
+    }
+
+    // 競技用ギルドに存在すべきカテゴリの定義を返す。
+    pub fn define_categories(&self) -> Result<Vec<GuildChannelDefinition>> {
         let mut categories = Vec::new();
 
         // Define staff category
         categories.push(
             GuildChannelDefinitionBuilder::default()
-                .name(STAFF_CATEGORY_NAME.to_string())
+                .name(self.staff.category_name())
                 .kind(ChannelType::Category)
+                .previous_names(self.staff.previous_category_names())
                 .build()?,
         );
 
@@ -51,69 +91,71 @@ impl Bot {
             );
         }
 
-        self._sync_channels(&[ChannelType::Category], categories)
-            .await?;
+        // Define categories for public channels
+        for public_channel in &self.channels.public {
+            let category = match &public_channel.category {
+                Some(category) => category,
+                None => continue,
+            };
 
-        tracing::info!("sync channels");
+            if categories.iter().any(|c| &c.name == category) {
+                continue;
+            }
 
-        let category_map: HashMap<_, _> = self
-            .get_channels(&[ChannelType::Category])
-            .await?
-            .into_iter()
-            .map(|category| (category.name.clone(), category.id))
-            .collect();
+            categories.push(
+                GuildChannelDefinitionBuilder::default()
+                    .name(category.clone())
+                    .kind(ChannelType::Category)
+                    .build()?,
+            );
+        }
+
+        Ok(categories)
+    }
 
+    // 競技用ギルドに存在すべきテキスト・ボイスチャンネルの定義を返す。
+    // category_mapには、カテゴリ名からカテゴリのChannelIdへの対応を渡す。
+    // 実行前に、self.update_role_cache()を呼び出して、ロールキャッシュを更新しておく必要がある。
+    pub async fn define_channels(
+        &self,
+        category_map: &HashMap<String, ChannelId>,
+    ) -> Result<Vec<GuildChannelDefinition>> {
         let mut channels = Vec::new();
 
         // Define public channels
-        let permissions_for_help_channel =
-            self.get_permission_overwrites_for_help_channel().await?;
-        channels.push(
-            GuildChannelDefinitionBuilder::default()
-                .name(HELP_CHANNEL_NAME.to_string())
-                .kind(ChannelType::Text)
-                .permissions(permissions_for_help_channel)
-                .build()?,
-        );
-
-        let permissions_for_announce_channel = self
-            .get_permission_overwrites_for_announce_channel()
-            .await?;
-        channels.push(
-            GuildChannelDefinitionBuilder::default()
-                .name(ANNOUNCE_CHANNEL_NAME.to_string())
-                .kind(ChannelType::Text)
-                .permissions(permissions_for_announce_channel.clone())
-                .build()?,
-        );
-
-        channels.push(
-            GuildChannelDefinitionBuilder::default()
-                .name(PLATINUM_SPONSORS_NAME.to_string())
-                .kind(ChannelType::Text)
-                .permissions(permissions_for_announce_channel)
-                .build()?,
-        );
-
-        let permissions_for_random_channel =
-            self.get_permission_overwrites_for_random_channel().await?;
+        for public_channel in &self.channels.public {
+            let category_id = match &public_channel.category {
+                Some(category) => Some(*category_map.get(category).ok_or(anyhow::anyhow!(
+                    "failed to get category {} for {}",
+                    category,
+                    public_channel.name
+                ))?),
+                None => None,
+            };
+
+            let permissions = self
+                .get_permission_overwrites_for_public_channel(public_channel.policy)
+                .await?;
 
-        channels.push(
-            GuildChannelDefinitionBuilder::default()
-                .name(RANDOM_CHANNEL_NAME.to_string())
-                .kind(ChannelType::Text)
-                .permissions(permissions_for_random_channel.clone())
-                .build()?,
-        );
+            channels.push(
+                GuildChannelDefinitionBuilder::default()
+                    .name(public_channel.name.clone())
+                    .kind(ChannelType::Text)
+                    .topic(public_channel.topic.clone())
+                    .category(category_id)
+                    .permissions(permissions)
+                    .build()?,
+            );
+        }
 
         // Define staff channels
         let staff_category_id = *category_map
-            .get(STAFF_CATEGORY_NAME)
+            .get(&self.staff.category_name())
             .ok_or(anyhow::anyhow!("failed to get staff category"))?;
 
         channels.push(
             GuildChannelDefinitionBuilder::default()
-                .name(format!("staff-{}", TEXT_CHANNEL_NAME_SUFFIX))
+                .name(self.text_channel_name("staff"))
                 .kind(ChannelType::Text)
                 .category(Some(staff_category_id))
                 .build()?,
@@ -122,7 +164,7 @@ impl Bot {
         if self.create_voice_channels {
             channels.push(
                 GuildChannelDefinitionBuilder::default()
-                    .name(format!("staff-{}", VOICE_CHANNEL_NAME_SUFFIX))
+                    .name(self.voice_channel_name("staff"))
                     .kind(ChannelType::Voice)
                     .category(Some(staff_category_id))
                     .build()?,
@@ -141,7 +183,7 @@ impl Bot {
 
             channels.push(
                 GuildChannelDefinitionBuilder::default()
-                    .name(format!("{}-{}", team.id, TEXT_CHANNEL_NAME_SUFFIX))
+                    .name(self.text_channel_name(&team.id))
                     .kind(ChannelType::Text)
                     .category(Some(team_category_id))
                     .permissions(permissions_for_team_channel.clone())
@@ -151,7 +193,7 @@ impl Bot {
             if self.create_voice_channels {
                 channels.push(
                     GuildChannelDefinitionBuilder::default()
-                        .name(format!("{}-{}", team.id, VOICE_CHANNEL_NAME_SUFFIX))
+                        .name(self.voice_channel_name(&team.id))
                         .kind(ChannelType::Voice)
                         .category(Some(team_category_id))
                         .permissions(permissions_for_team_channel.clone())
@@ -160,102 +202,159 @@ impl Bot {
             }
         }
 
-        self._sync_channels(&[ChannelType::Text, ChannelType::Voice], channels)
-            .await?;
-
-        Ok(())
+        Ok(channels)
     }
+}
 
-    #[tracing::instrument(skip_all)]
-    pub async fn delete_channels(&self) -> Result<()> {
-        tracing::info!("delete all channels");
-        for (channel_id, channel) in self.guild_id.channels(&self.discord_client).await? {
-            tracing::debug!(?channel, "delete channel");
-            channel_id.delete(&self.discord_client).await?;
-        }
-        Ok(())
-    }
+// チャンネルの同期時に実行される操作
+#[derive(Clone, Debug)]
+pub enum ChannelSyncAction {
+    Create(GuildChannelDefinition),
+    Update(GuildChannel, GuildChannelDefinition),
+    Delete(GuildChannel),
 }
 
 impl Bot {
     async fn _sync_channels<K, T>(&self, kinds: K, definitions: T) -> Result<()>
+    where
+        K: AsRef<[ChannelType]>,
+        T: AsRef<[GuildChannelDefinition]>,
+    {
+        let actions = self.plan_channels(kinds, definitions).await?;
+
+        for action in actions {
+            match action {
+                ChannelSyncAction::Create(definition) => {
+                    tracing::debug!(?definition, "create channel");
+                    self.create_channel(&definition).await?;
+                },
+                ChannelSyncAction::Update(mut channel, definition) => {
+                    tracing::debug!(
+                        ?channel,
+                        ?definition,
+                        "channel is created but not synced, update channel"
+                    );
+                    self.edit_channel(&mut channel, &definition).await?;
+                },
+                ChannelSyncAction::Delete(mut channel) => {
+                    tracing::debug!(?channel, "delete channel");
+                    self.delete_channel(&mut channel).await?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    // 与えられたGuildChannelDefinitionのリストと現在のチャンネルを比較し、同期に必要な操作を列挙する。
+    // このメソッド自体はギルドに対して何も変更を加えない。
+    pub async fn plan_channels<K, T>(
+        &self,
+        kinds: K,
+        definitions: T,
+    ) -> Result<Vec<ChannelSyncAction>>
     where
         K: AsRef<[ChannelType]>,
         T: AsRef<[GuildChannelDefinition]>,
     {
         tracing::debug!("fetch current channels");
-        let mut channels = self.get_channels(kinds).await?;
+        let channels = self.get_channels(kinds).await?;
 
-        tracing::debug!("sync defined channels");
-        for definition in definitions.as_ref() {
-            tracing::debug!(?definition, "sync channels");
+        Ok(plan_channel_actions(channels, definitions.as_ref()))
+    }
+}
 
-            let matched_channels: Vec<_> = channels
-                .iter_mut()
-                .filter(|c| c.name == definition.name && c.parent_id == definition.category)
-                .collect();
+// 与えられたGuildChannelDefinitionのリストと現在のチャンネルを比較し、同期に必要な操作を列挙する。
+pub fn plan_channel_actions(
+    channels: Vec<GuildChannel>,
+    definitions: &[GuildChannelDefinition],
+) -> Vec<ChannelSyncAction> {
+    let mut actions = Vec::new();
+    let mut renamed_channel_ids = Vec::new();
+
+    tracing::debug!("plan defined channels");
+    for definition in definitions {
+        tracing::debug!(?definition, "plan channels");
+
+        let matched_channels: Vec<_> = channels
+            .iter()
+            .filter(|c| c.name == definition.name && c.parent_id == definition.category)
+            .collect();
 
-            if matched_channels.len() == 1 {
-                let channel = matched_channels.into_iter().nth(0).unwrap();
-                if self.check_channel_synced(channel, definition) {
-                    tracing::debug!(
-                        channel_id = ?channel.id,
-                        channel_name = ?channel.name,
-                        "target channel is created and synced, skip"
-                    );
-                    continue;
-                }
+        if matched_channels.len() == 1 {
+            let channel = matched_channels[0];
+            if check_channel_synced(channel, definition) {
                 tracing::debug!(
-                    ?channel,
-                    ?definition,
-                    "channel is created but not synced, update channel"
+                    channel_id = ?channel.id,
+                    channel_name = ?channel.name,
+                    "target channel is created and synced, skip"
                 );
-                self.edit_channel(channel, &definition).await?;
                 continue;
             }
-
-            if matched_channels.len() != 0 {
-                tracing::debug!(
-                    ?matched_channels,
-                    "several matched channels are found, delete them"
-                );
-                for channel in matched_channels {
-                    self.delete_channel(channel).await?;
-                }
-            }
-
-            tracing::debug!(?definition, "create channel");
-            self.create_channel(&definition).await?;
+            actions.push(ChannelSyncAction::Update(
+                channel.clone(),
+                definition.clone(),
+            ));
+            continue;
         }
 
-        tracing::debug!("delete not-defined channels");
-        for channel in channels.iter_mut() {
-            let found = definitions
-                .as_ref()
+        if !matched_channels.is_empty() {
+            tracing::debug!(
+                ?matched_channels,
+                "several matched channels are found, delete them"
+            );
+            for channel in matched_channels {
+                actions.push(ChannelSyncAction::Delete(channel.clone()));
+            }
+        } else {
+            // 以前の名前のチャンネルが存在する場合、配下のチャンネルやメッセージを維持するため、名前を変更して使い回す。
+            let previous_channels: Vec<_> = channels
                 .iter()
-                .find(|d| d.name == channel.name)
-                .is_some();
+                .filter(|c| {
+                    definition.previous_names.contains(&c.name)
+                        && c.kind == definition.kind
+                        && c.parent_id == definition.category
+                })
+                .collect();
 
-            if !found {
-                tracing::debug!(?channel, "delete category");
-                self.delete_channel(channel).await?;
+            if previous_channels.len() == 1 {
+                let channel = previous_channels[0];
+                tracing::debug!(
+                    ?channel,
+                    ?definition,
+                    "channel with previous name is found, rename it"
+                );
+                renamed_channel_ids.push(channel.id);
+                actions.push(ChannelSyncAction::Update(
+                    channel.clone(),
+                    definition.clone(),
+                ));
+                continue;
             }
         }
 
-        Ok(())
+        actions.push(ChannelSyncAction::Create(definition.clone()));
     }
 
-    fn check_channel_synced(
-        &self,
-        channel: &GuildChannel,
-        definition: &GuildChannelDefinition,
-    ) -> bool {
-        channel.kind == definition.kind
-            && channel.name == definition.name
-            && channel.parent_id == definition.category
-            && channel.topic == definition.topic
-            // Discordはpermission_overwritesを順不同で返すため、順序を無視して比較する
-            && channel.permission_overwrites.iter().all(|overwrite| definition.permissions.contains(overwrite))
-            && definition.permissions.iter().all(|permission| channel.permission_overwrites.contains(permission))
+    tracing::debug!("plan not-defined channels");
+    for channel in channels {
+        let found = definitions.iter().any(|d| d.name == channel.name);
+
+        if !found && !renamed_channel_ids.contains(&channel.id) {
+            tracing::debug!(?channel, "channel is not defined, delete it");
+            actions.push(ChannelSyncAction::Delete(channel));
+        }
     }
+
+    actions
+}
+
+pub fn check_channel_synced(channel: &GuildChannel, definition: &GuildChannelDefinition) -> bool {
+    channel.kind == definition.kind
+        && channel.name == definition.name
+        && channel.parent_id == definition.category
+        && channel.topic == definition.topic
+        // Discordはpermission_overwritesを順不同で返すため、順序を無視して比較する
+        && channel.permission_overwrites.iter().all(|overwrite| definition.permissions.contains(overwrite))
+        && definition.permissions.iter().all(|permission| channel.permission_overwrites.contains(permission))
 }
@@ -1,8 +1,12 @@
 use anyhow::Result;
+use chrono::Utc;
 use serenity::all::CreateCommand;
 use serenity::all::EditInteractionResponse;
+use serenity::all::EditMessage;
 use serenity::model::prelude::*;
 
+use super::ask::create_question_ticket_buttons;
+use super::ask::create_question_ticket_embed;
 use crate::bot::helpers::HelperError;
 use crate::bot::Bot;
 
@@ -54,6 +58,10 @@ impl Bot {
             return Err(ArchiveCommandError::ChannelNotThreadError);
         }
 
+        // スレッドをアーカイブすると編集できなくなるため、先にチケットを終了する。
+        self.close_question_ticket(&guild_channel).await;
+        self.export_question_transcript(&guild_channel).await;
+
         self.archive_thread(&mut guild_channel).await?;
 
         self.edit_response(
@@ -64,4 +72,42 @@ impl Bot {
 
         Ok(())
     }
+
+    // /askで作成されたスレッドであれば、チケットを終了して対応状況の表示を更新する。
+    // チケットの更新に失敗しても、スレッドの終了は続行する。
+    async fn close_question_ticket(&self, thread: &GuildChannel) {
+        let ticket_lock = self.question_ticket_lock.lock().await;
+        let mut ticket = match self.storage.get_question_ticket(thread.id.get()).await {
+            Ok(Some(ticket)) => ticket,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::error!(?err, "failed to get question ticket");
+                return;
+            },
+        };
+
+        ticket.close(Utc::now());
+        if let Err(err) = self.storage.save_question_ticket(&ticket).await {
+            tracing::error!(?err, "failed to save question ticket");
+        }
+        drop(ticket_lock);
+
+        if let Some(message_id) = ticket.message_id {
+            let result = thread
+                .id
+                .edit_message(
+                    &self.discord_client,
+                    MessageId::new(message_id),
+                    EditMessage::new()
+                        .embed(create_question_ticket_embed(&ticket))
+                        .components(create_question_ticket_buttons(&ticket)),
+                )
+                .await;
+            if let Err(err) = result {
+                tracing::error!(?err, "failed to update question ticket message");
+            }
+        }
+
+        self.question_dashboard.refresh().await;
+    }
 }
@@ -1,20 +1,67 @@
 use anyhow::Result;
+use chrono::Utc;
+use serenity::all::CreateActionRow;
+use serenity::all::CreateAutocompleteResponse;
+use serenity::all::CreateButton;
 use serenity::all::CreateCommand;
 use serenity::all::CreateCommandOption;
+use serenity::all::CreateEmbed;
 use serenity::all::CreateInteractionResponseMessage;
 use serenity::all::CreateMessage;
 use serenity::all::EditInteractionResponse;
 use serenity::model::prelude::*;
 
+use super::add_problem_choices;
 use crate::bot::helpers::HelperError;
-use crate::bot::roles;
 use crate::bot::Bot;
+use crate::models::search_problems;
+use crate::models::Problem;
+use crate::services::storage::QuestionRecord;
+use crate::services::storage::QuestionStatus;
+use crate::services::storage::QuestionTicket;
+
+const CUSTOM_ID_QUESTION_CLAIM: &str = "question_claim";
+const CUSTOM_ID_QUESTION_RESOLVE: &str = "question_resolve";
+
+// custom_idが質問スレッドのチケットのボタンのものか？
+pub fn is_question_ticket_custom_id(custom_id: &str) -> bool {
+    custom_id == CUSTOM_ID_QUESTION_CLAIM || custom_id == CUSTOM_ID_QUESTION_RESOLVE
+}
+
+// チケットの対応状況を表示するEmbed
+pub fn create_question_ticket_embed(ticket: &QuestionTicket) -> CreateEmbed {
+    let assignee = match ticket.assignee_id {
+        Some(assignee_id) => Mention::from(UserId::new(assignee_id)).to_string(),
+        None => String::from("なし"),
+    };
+    CreateEmbed::new()
+        .title("対応状況")
+        .field("状態", ticket.status.label(), true)
+        .field("担当", assignee, true)
+}
+
+// staffがチケットを操作するためのボタン。終了したチケットは操作できない。
+pub fn create_question_ticket_buttons(ticket: &QuestionTicket) -> Vec<CreateActionRow> {
+    let closed = ticket.status == QuestionStatus::Closed;
+    let claim = CreateButton::new(CUSTOM_ID_QUESTION_CLAIM)
+        .label("担当する")
+        .style(ButtonStyle::Primary)
+        .disabled(closed);
+    let resolve = CreateButton::new(CUSTOM_ID_QUESTION_RESOLVE)
+        .label("回答済みにする")
+        .style(ButtonStyle::Success)
+        .disabled(closed || ticket.status == QuestionStatus::Answered);
+    vec![CreateActionRow::Buttons(vec![claim, resolve])]
+}
 
 #[derive(Debug, thiserror::Error)]
 enum AskCommandError {
     #[error("質問のタイトルは50文字以内でなければなりません。「問題〇〇の初期条件について」など、簡潔にまとめて再度お試しください。")]
     TitleTooLongError,
 
+    #[error("問題コード `{0}` に対応する問題はありません。問題コードを再度お確かめください。")]
+    InvalidProblemCodeError(String),
+
     #[error("このコマンドはテキストチャンネル以外から呼び出すことはできません。")]
     InvalidChannelTypeError,
 
@@ -39,11 +86,36 @@ impl Bot {
                 )
                 .required(true),
             )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "problem_code",
+                    "質問に関係する問題の問題コード",
+                )
+                .set_autocomplete(true),
+            )
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn handle_ask_autocomplete(&self, interaction: &CommandInteraction) -> Result<()> {
+        let focused = match interaction.data.autocomplete() {
+            Some(focused) => focused,
+            None => return Ok(()),
+        };
+
+        let mut response = CreateAutocompleteResponse::new();
+        if focused.name == "problem_code" {
+            response =
+                add_problem_choices(response, search_problems(&self.problems, focused.value));
+        }
+
+        self.respond_autocomplete(interaction, response).await?;
+        Ok(())
     }
 
     #[tracing::instrument(skip_all)]
     pub async fn handle_ask_command(&self, interaction: &CommandInteraction) -> Result<()> {
-        let (guild_channel, title) = match self.validate_ask_command(interaction).await {
+        let (guild_channel, title, problem) = match self.validate_ask_command(interaction).await {
             Ok(v) => v,
             Err(err) => {
                 self.respond(
@@ -61,7 +133,7 @@ impl Bot {
         self.defer_response(interaction).await?;
 
         if let Err(err) = self
-            .do_ask_command(interaction, &guild_channel, title)
+            .do_ask_command(interaction, &guild_channel, title, problem)
             .await
         {
             tracing::error!(?err, "failed to do ask command");
@@ -78,7 +150,7 @@ impl Bot {
     async fn validate_ask_command<'t>(
         &self,
         interaction: &'t CommandInteraction,
-    ) -> AskCommandResult<(GuildChannel, &'t str)> {
+    ) -> AskCommandResult<(GuildChannel, &'t str, Option<&Problem>)> {
         let channel = self.get_channel(interaction.channel_id).await?;
 
         let guild_channel = match channel {
@@ -101,7 +173,14 @@ impl Bot {
             return Err(AskCommandError::TitleTooLongError);
         }
 
-        Ok((guild_channel, title))
+        let problem = match self.get_option_as_str(&interaction.data.options, "problem_code") {
+            Some(problem_code) => Some(self.find_problem(problem_code).ok_or_else(|| {
+                AskCommandError::InvalidProblemCodeError(problem_code.to_string())
+            })?),
+            None => None,
+        };
+
+        Ok((guild_channel, title, problem))
     }
 
     async fn do_ask_command(
@@ -109,16 +188,18 @@ impl Bot {
         interaction: &CommandInteraction,
         guild_channel: &GuildChannel,
         title: &str,
+        problem: Option<&Problem>,
     ) -> AskCommandResult<()> {
         let sender = &interaction.user;
         let sender_mention = Mention::from(sender.id).to_string();
 
-        let staff_mentions: Vec<_> = self
-            .find_roles_by_name_cached(roles::STAFF_ROLE_NAME)
-            .await?
-            .iter()
-            .map(|role| Mention::from(role.id).to_string())
-            .collect();
+        // 問題が指定されていれば、スレッド名に問題コードを付ける（例: [ABC] 初期条件について）
+        let title = match problem {
+            Some(problem) => format!("[{}] {}", problem.code, title),
+            None => title.to_string(),
+        };
+
+        let mentions = self.find_question_mentions(problem).await?;
 
         self.edit_response(
             interaction,
@@ -130,20 +211,135 @@ impl Bot {
         let message = self.get_response(interaction).await?;
 
         let channel = self
-            .create_public_thread(guild_channel, &message, title)
+            .create_public_thread(guild_channel, &message, &title)
             .await?;
 
+        let record = QuestionRecord {
+            created_at: Utc::now(),
+            team_id: self
+                .teams
+                .iter()
+                .find(|team| guild_channel.name == self.text_channel_name(&team.id))
+                .map(|team| team.id.clone()),
+            user_id: sender.id.get(),
+            user_name: sender.name.clone(),
+            title: title.clone(),
+            channel_id: guild_channel.id.get(),
+            thread_id: channel.id.get(),
+        };
+        if let Err(err) = self.storage.record_question(&record).await {
+            tracing::error!(?err, "failed to record question");
+        }
+
         // TODO: 直接メッセージを送信するな！！！
-        channel
+        let mut ticket = QuestionTicket::open(channel.id.get(), None, Utc::now());
+        let message = channel
             .send_message(
                 &self.discord_client,
-                CreateMessage::new().content(format!(
-                    "{} 質問スレッドを開始します。",
-                    staff_mentions.join(" ")
-                )),
+                CreateMessage::new()
+                    .content(format!("{} 質問スレッドを開始します。", mentions.join(" ")))
+                    .embed(create_question_ticket_embed(&ticket))
+                    .components(create_question_ticket_buttons(&ticket)),
             )
             .await?;
 
+        ticket.message_id = Some(message.id.get());
+        if let Err(err) = self.storage.save_question_ticket(&ticket).await {
+            tracing::error!(?err, "failed to save question ticket");
+        }
+        self.question_dashboard.refresh().await;
+
+        Ok(())
+    }
+}
+
+impl Bot {
+    // 質問スレッドの開始時にメンションする相手を返す。
+    // 問題の担当者が設定されていれば担当者を、それ以外の場合はstaffロールをメンションする。
+    async fn find_question_mentions(
+        &self,
+        problem: Option<&Problem>,
+    ) -> AskCommandResult<Vec<String>> {
+        if let Some(problem) = problem.filter(|problem| !problem.owners.is_empty()) {
+            return Ok(problem
+                .owners
+                .iter()
+                .map(|owner| Mention::from(UserId::new(*owner)).to_string())
+                .collect());
+        }
+
+        Ok(self
+            .find_roles_by_name_cached(&self.staff.role_name())
+            .await?
+            .iter()
+            .map(|role| Mention::from(role.id).to_string())
+            .collect())
+    }
+}
+
+impl Bot {
+    // 質問スレッドの「担当する」「回答済みにする」ボタンを処理する。
+    // チケットはスレッドのIDで保存しているため、botの再起動後も処理できる。
+    #[tracing::instrument(skip_all)]
+    pub async fn handle_question_ticket_button(
+        &self,
+        interaction: &ComponentInteraction,
+    ) -> Result<()> {
+        let is_staff = match &interaction.member {
+            Some(member) => self.is_staff_member(member).await?,
+            None => false,
+        };
+        if !is_staff {
+            self.respond(
+                interaction,
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .content("このボタンはstaffのみ押すことができます。"),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let thread_id = interaction.channel_id.get();
+        let ticket_lock = self.question_ticket_lock.lock().await;
+        let mut ticket = self
+            .storage
+            .get_question_ticket(thread_id)
+            .await?
+            // チケットが保存されていない場合は、ボタンが押されたメッセージを対応状況の表示先とする。
+            .unwrap_or_else(|| {
+                QuestionTicket::open(thread_id, Some(interaction.message.id.get()), Utc::now())
+            });
+
+        let user = &interaction.user;
+        let result = match interaction.data.custom_id.as_str() {
+            CUSTOM_ID_QUESTION_CLAIM => ticket.claim(user.id.get(), &user.name, Utc::now()),
+            CUSTOM_ID_QUESTION_RESOLVE => ticket.resolve(user.id.get(), &user.name, Utc::now()),
+            custom_id => return Err(anyhow::anyhow!("unknown component: {}", custom_id)),
+        };
+        if result.is_err() {
+            self.respond(
+                interaction,
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .content("この質問スレッドは既に終了しています。"),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        self.storage.save_question_ticket(&ticket).await?;
+        drop(ticket_lock);
+
+        self.update_message(
+            interaction,
+            CreateInteractionResponseMessage::new()
+                .embed(create_question_ticket_embed(&ticket))
+                .components(create_question_ticket_buttons(&ticket)),
+        )
+        .await?;
+        self.question_dashboard.refresh().await;
+
         Ok(())
     }
 }
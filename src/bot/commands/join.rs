@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 
 use anyhow::Result;
+use chrono::Utc;
 use serenity::all::CommandInteraction;
 use serenity::all::CommandOptionType;
 use serenity::all::CreateCommand;
@@ -9,8 +10,8 @@ use serenity::all::CreateInteractionResponseMessage;
 use serenity::all::EditInteractionResponse;
 
 use crate::bot::helpers::HelperError;
-use crate::bot::roles;
 use crate::bot::Bot;
+use crate::services::storage::JoinRecord;
 
 #[derive(Debug, thiserror::Error)]
 enum JoinCommandError<'a> {
@@ -56,7 +57,7 @@ impl Bot {
         tracing::trace!("send acknowledgement");
         self.defer_response(interaction).await?;
 
-        if let Err(err) = self.do_join_command(interaction, role_name).await {
+        if let Err(err) = self.do_join_command(interaction, &role_name).await {
             tracing::error!(?err, "failed to do join command");
             self.edit_response(
                 interaction,
@@ -71,7 +72,7 @@ impl Bot {
     fn validate_join_command<'t>(
         &self,
         interaction: &'t CommandInteraction,
-    ) -> JoinCommandResult<'t, &str> {
+    ) -> JoinCommandResult<'t, String> {
         // joinコマンドはGlobalCommandなので、どこからでも呼び出すことは可能である。
         // だが、間違ってrandomチャンネル等で呼び出されてしまうことを防ぐため、DM以外からの呼び出しはエラーとする。
         if interaction.guild_id.is_some() {
@@ -86,16 +87,16 @@ impl Bot {
             .ok_or(JoinCommandError::InvalidTeamCodeError(team_code))
     }
 
-    fn find_role_name_by_team_code(&self, team_code: &str) -> Option<&str> {
+    fn find_role_name_by_team_code(&self, team_code: &str) -> Option<String> {
         // インフラパスが指定された場合、staff権限を付与する。
-        if team_code == self.infra_password {
-            return Some(roles::STAFF_ROLE_NAME);
+        if team_code == self.staff.password {
+            return Some(self.staff.role_name());
         }
 
         // チームに割り当てられたチームコードの場合、チーム権限を付与する。
         for team in &self.teams {
             if team.team_code == team_code {
-                return Some(&team.role_name);
+                return Some(team.role_name.clone());
             }
         }
 
@@ -139,6 +140,21 @@ impl Bot {
         self.revoke_roles(&mut sender_member, role_ids_revoked)
             .await?;
 
+        let record = JoinRecord {
+            joined_at: Utc::now(),
+            user_id: sender.id.get(),
+            user_name: sender.name.clone(),
+            team_id: self
+                .teams
+                .iter()
+                .find(|team| team.role_name == role_name)
+                .map(|team| team.id.clone()),
+            role_name: role_name.to_string(),
+        };
+        if let Err(err) = self.storage.record_join(&record).await {
+            tracing::error!(?err, "failed to record join");
+        }
+
         self.edit_response(
             interaction,
             EditInteractionResponse::new()
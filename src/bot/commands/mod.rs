@@ -7,9 +7,49 @@ mod redeploy;
 use std::collections::HashMap;
 
 use anyhow::Result;
-use serenity::client::Context;
+use serenity::all::ComponentInteraction;
+use serenity::all::CreateAutocompleteResponse;
+use serenity::all::CreateCommand;
+use serenity::all::CreateInteractionResponseMessage;
+use serenity::all::ModalInteraction;
 
+use self::ask::is_question_ticket_custom_id;
 use crate::bot::*;
+use crate::models::default_command_access;
+use crate::models::normalize_problem_code;
+use crate::services::redeploy::confirmation::is_confirmation_custom_id;
+
+// 問題コードの候補を追加する
+fn add_problem_choices<'a, I>(
+    mut response: CreateAutocompleteResponse,
+    problems: I,
+) -> CreateAutocompleteResponse
+where
+    I: IntoIterator<Item = &'a Problem>,
+{
+    // Discordの制約上、候補は25件までしか返せない。
+    for problem in problems.into_iter().take(25) {
+        // 候補の表示名は100文字までに制限されている。
+        let name: String = format!("{}: {}", problem.code, problem.name)
+            .chars()
+            .take(100)
+            .collect();
+        response = response.add_string_choice(name, &problem.code);
+    }
+    response
+}
+
+impl Bot {
+    // 問題コードに対応する問題を返す
+    fn find_problem(&self, problem_code: &str) -> Option<&Problem> {
+        // スコアサーバーとの互換性のため、ここで半角の大文字に正規化する
+        let normalized_problem_code = normalize_problem_code(problem_code);
+
+        self.problems
+            .iter()
+            .find(|problem| problem.code == normalized_problem_code)
+    }
+}
 
 impl Bot {
     pub async fn sync_global_application_commands(&self) -> Result<()> {
@@ -40,6 +80,7 @@ impl Bot {
                 continue;
             }
             tracing::debug!(command = ?name, "Syncing command");
+            let builder = self.restrict_command(&name, builder);
             Command::create_global_command(&self.discord_client, builder).await?;
         }
 
@@ -78,6 +119,9 @@ impl Bot {
                 continue;
             }
             tracing::debug!(command = ?name, "Syncing command");
+            let builder = self
+                .restrict_command(&name, builder)
+                .contexts(vec![InteractionContext::Guild]);
             self.guild_id
                 .create_command(&self.discord_client, builder)
                 .await?;
@@ -86,6 +130,25 @@ impl Bot {
         Ok(())
     }
 
+    pub fn get_command_access(&self, name: &str) -> CommandAccess {
+        self.command_access
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| default_command_access(name))
+    }
+
+    // アクセスポリシーに応じて、Discordのクライアント上でコマンドを表示するユーザを絞り込む。
+    // 参加者向けのコマンドは、チームチャンネルでのみUSE_APPLICATION_COMMANDSが許可されているため、ここでは絞り込まない。
+    // いずれの場合も、実際の権限チェックはhandle_application_commandで行う。
+    fn restrict_command(&self, name: &str, builder: CreateCommand) -> CreateCommand {
+        match self.get_command_access(name) {
+            CommandAccess::Everyone | CommandAccess::TeamMember => builder,
+            CommandAccess::Staff => {
+                builder.default_member_permissions(self.get_permissions_for_staff_command())
+            },
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn delete_commands(&self) -> Result<()> {
         tracing::info!("delete global application commands");
@@ -123,19 +186,38 @@ impl Bot {
         user_id = ?interaction.user.id,
         user_name = ?interaction.user.name,
     ))]
-    pub async fn handle_application_command(
-        &self,
-        ctx: &Context,
-        interaction: &CommandInteraction,
-    ) {
+    pub async fn handle_application_command(&self, interaction: &CommandInteraction) {
         let name = interaction.data.name.as_str();
 
+        match self.check_command_access(interaction).await {
+            Ok(true) => {},
+            Ok(false) => {
+                tracing::info!(command = ?name, "user is not allowed to run command");
+                let result = self
+                    .respond(
+                        interaction,
+                        CreateInteractionResponseMessage::new()
+                            .ephemeral(true)
+                            .content("このコマンドを実行する権限がありません。"),
+                    )
+                    .await;
+                if let Err(err) = result {
+                    tracing::error!(?err, "failed to respond to unauthorized command");
+                }
+                return;
+            },
+            Err(err) => {
+                tracing::error!(?err, "failed to check command access");
+                return;
+            },
+        }
+
         let result = match name {
             "archive" => self.handle_archive_command(interaction).await,
             "ask" => self.handle_ask_command(interaction).await,
             "join" => self.handle_join_command(interaction).await,
             "ping" => self.handle_ping_command(interaction).await,
-            "redeploy" => self.handle_redeploy_command(ctx, interaction).await,
+            "redeploy" => self.handle_redeploy_command(interaction).await,
             _ => Err(anyhow::anyhow!("unknown command: {}", name)),
         };
 
@@ -144,3 +226,110 @@ impl Bot {
         };
     }
 }
+
+impl Bot {
+    // コマンドの呼び出し元が、コマンドのアクセスポリシーを満たしているかを確認する。
+    async fn check_command_access(&self, interaction: &CommandInteraction) -> Result<bool> {
+        let access = self.get_command_access(&interaction.data.name);
+        if access == CommandAccess::Everyone {
+            return Ok(true);
+        }
+
+        // DMから呼び出された場合はメンバー情報が含まれないため、ギルドから取得する。
+        let member = match &interaction.member {
+            Some(member) => member.as_ref().clone(),
+            None => match self.get_member(&interaction.user).await {
+                Ok(member) => member,
+                Err(_) => return Ok(false),
+            },
+        };
+
+        let mut is_staff = false;
+        let mut is_team_member = false;
+        for role_id in member.roles {
+            let role = match self.find_roles_by_id_cached(role_id).await? {
+                Some(role) => role,
+                None => continue,
+            };
+
+            is_staff |= self.is_staff_role(&role);
+            is_team_member |= self.is_team_role(&role);
+        }
+
+        Ok(access.allows(is_staff, is_team_member))
+    }
+}
+
+impl Bot {
+    #[tracing::instrument(skip_all, fields(
+        id = ?interaction.id,
+        guild_id = ?interaction.guild_id,
+        channel_id = ?interaction.channel_id,
+        user_id = ?interaction.user.id,
+        user_name = ?interaction.user.name,
+    ))]
+    pub async fn handle_autocomplete(&self, interaction: &CommandInteraction) {
+        let name = interaction.data.name.as_str();
+
+        let result = match name {
+            "ask" => self.handle_ask_autocomplete(interaction).await,
+            "redeploy" => self.handle_redeploy_autocomplete(interaction).await,
+            _ => Err(anyhow::anyhow!(
+                "unknown command for autocomplete: {}",
+                name
+            )),
+        };
+
+        if let Err(err) = result {
+            tracing::error!(?err, "failed to handle autocomplete");
+        };
+    }
+}
+
+impl Bot {
+    #[tracing::instrument(skip_all, fields(
+        id = ?interaction.id,
+        guild_id = ?interaction.guild_id,
+        channel_id = ?interaction.channel_id,
+        user_id = ?interaction.user.id,
+        user_name = ?interaction.user.name,
+        custom_id = ?interaction.data.custom_id,
+    ))]
+    pub async fn handle_component(&self, interaction: &ComponentInteraction) {
+        let custom_id = interaction.data.custom_id.as_str();
+
+        let result = if is_confirmation_custom_id(custom_id) {
+            self.handle_redeploy_confirmation(interaction).await
+        } else if is_question_ticket_custom_id(custom_id) {
+            self.handle_question_ticket_button(interaction).await
+        } else {
+            Err(anyhow::anyhow!("unknown component: {}", custom_id))
+        };
+
+        if let Err(err) = result {
+            tracing::error!(?err, "failed to handle component");
+        };
+    }
+
+    #[tracing::instrument(skip_all, fields(
+        id = ?interaction.id,
+        guild_id = ?interaction.guild_id,
+        channel_id = ?interaction.channel_id,
+        user_id = ?interaction.user.id,
+        user_name = ?interaction.user.name,
+        custom_id = ?interaction.data.custom_id,
+    ))]
+    pub async fn handle_modal(&self, interaction: &ModalInteraction) {
+        let custom_id = interaction.data.custom_id.as_str();
+
+        let result = if is_confirmation_custom_id(custom_id) {
+            self.handle_redeploy_reason(interaction).await
+        } else {
+            Err(anyhow::anyhow!("unknown modal: {}", custom_id))
+        };
+
+        if let Err(err) = result {
+            tracing::error!(?err, "failed to handle modal");
+        };
+    }
+}
@@ -1,47 +1,106 @@
-use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Result;
+use chrono::Utc;
+use serenity::all::ActionRowComponent;
 use serenity::all::ButtonStyle;
 use serenity::all::CommandDataOption;
 use serenity::all::CommandDataOptionValue;
 use serenity::all::CommandInteraction;
 use serenity::all::CommandOptionType;
-use serenity::all::ComponentInteractionDataKind;
+use serenity::all::ComponentInteraction;
 use serenity::all::CreateActionRow;
+use serenity::all::CreateAutocompleteResponse;
 use serenity::all::CreateButton;
 use serenity::all::CreateCommand;
 use serenity::all::CreateCommandOption;
 use serenity::all::CreateEmbed;
+use serenity::all::CreateInputText;
 use serenity::all::CreateInteractionResponseMessage;
+use serenity::all::CreateModal;
 use serenity::all::EditInteractionResponse;
+use serenity::all::EditMessage;
+use serenity::all::InputTextStyle;
+use serenity::all::ModalInteraction;
 use serenity::model::user::User;
-use serenity::prelude::*;
 
+use super::add_problem_choices;
 use crate::bot::helpers::HelperError;
+use crate::bot::redeploy_watcher::WatchedRedeployJob;
 use crate::bot::Bot;
+use crate::models::find_team;
+use crate::models::search_problems;
+use crate::models::search_teams;
 use crate::models::Problem;
 use crate::models::Team;
+use crate::services::redeploy::confirmation::RedeployConfirmation;
+use crate::services::redeploy::confirmation::RedeployConfirmationAction;
+use crate::services::redeploy::confirmation::RedeployConfirmationError;
 use crate::services::redeploy::RedeployError;
+use crate::services::redeploy::RedeployRequestEvent;
+use crate::services::redeploy::RedeployRequester;
+use crate::services::redeploy::RedeployStatus;
 use crate::services::redeploy::RedeployTarget;
+use crate::services::storage::RedeployLimitResetRecord;
+use crate::services::storage::RedeployRecord;
+use crate::services::storage::StorageError;
 
 const CUSTOM_ID_REDEPLOY_CONFIRM: &str = "redeploy_confirm";
 const CUSTOM_ID_REDEPLOY_CANCELED: &str = "redeploy_canceled";
 
+// 再展開の理由を入力するモーダルの入力欄のcustom_id
+const CUSTOM_ID_REDEPLOY_REASON: &str = "redeploy_reason";
+
+const REDEPLOY_REASON_MAX_LENGTH: u16 = 500;
+
+// 確認メッセージに再展開の理由を埋め込むEmbedのタイトル
+const REDEPLOY_REASON_EMBED_TITLE: &str = "再展開の理由";
+
 #[derive(Debug, thiserror::Error)]
-enum RedeployCommandError<'a> {
+enum RedeployCommandError {
     #[error("問題コード `{0}` に対応する問題はありません。問題コードを再度お確かめください。")]
-    InvalidProblemCodeError(&'a str),
+    InvalidProblemCodeError(String),
+
+    #[error("問題 `{0}` は再展開できません。")]
+    NotRedeployableError(String),
 
     #[error("問題 `{0}` の再展開は実行中です。再展開が完了してから再度お試しください。")]
     AnotherJobInQueue(String),
 
-    // /redeployコマンドの使用者のチームが解決できない時に発生するエラー
-    #[error("予期しないエラーが発生しました。運営にお問い合わせください。")]
-    UnexpectedSenderTeamsError,
+    // 再展開中か、クールダウン中かを確認できないため、再展開を受け付けない。
+    #[error("問題 `{0}` の再展開状況を取得できませんでした。しばらくしてから再度お試しください。")]
+    StatusUnknownError(String),
+
+    #[error(
+        "問題 `{0}` は再展開が完了してから一定時間、再展開できません。あと{1}お待ちください。"
+    )]
+    CooldownError(String, String),
+
+    #[error("問題 `{0}` の再展開回数の上限（{1}回）に達しました。追加の再展開が必要な場合は運営にお問い合わせください。")]
+    QuotaExceededError(String, u32),
+
+    #[error("`team` オプションはstaffのみ指定できます。")]
+    TeamOptionNotAllowedError,
+
+    #[error("このサブコマンドはstaffのみ実行できます。")]
+    StaffOnlySubcommandError,
+
+    #[error("チーム `{0}` は存在しません。チームIDを再度お確かめください。")]
+    InvalidTeamIdError(String),
+
+    // /redeployコマンドの使用者がどのチームのロールも持っていない時に発生するエラー
+    #[error("チームに参加していません。/join でチームに参加してから再度お試しください。")]
+    SenderTeamNotFoundError,
 
     // redeploy serviceからエラーが帰ってきた時に発生するエラー
     #[error("予期しないエラーが発生しました。運営にお問い合わせください。")]
-    RedeployServiceError(#[from] RedeployError),
+    RedeployServiceError(#[source] RedeployError),
+
+    #[error("再展開システムが一時的に利用できません。しばらくしてから再度お試しください。")]
+    RedeployUnavailableError,
+
+    #[error("予期しないエラーが発生しました。運営にお問い合わせください。")]
+    StorageError(#[from] StorageError),
 
     #[error("予期しないエラーが発生しました。運営にお問い合わせください。")]
     InconsistentCommandDefinitionError,
@@ -50,18 +109,48 @@ enum RedeployCommandError<'a> {
     HelperError(#[from] HelperError),
 }
 
-type RedeployCommandResult<'t, T> = std::result::Result<T, RedeployCommandError<'t>>;
+impl From<RedeployError> for RedeployCommandError {
+    fn from(err: RedeployError) -> Self {
+        match err {
+            RedeployError::Unavailable => RedeployCommandError::RedeployUnavailableError,
+            err => RedeployCommandError::RedeployServiceError(err),
+        }
+    }
+}
+
+type RedeployCommandResult<T> = std::result::Result<T, RedeployCommandError>;
+
+// staffがチームの代わりにコマンドを実行するためのオプション
+fn create_team_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "team",
+        "対象のチームID（staffのみ指定可能）",
+    )
+    .set_autocomplete(true)
+}
+
+// 残り時間を「1分30秒」のような形式で表示する
+fn format_remaining(remaining: chrono::Duration) -> String {
+    let secs = remaining.num_seconds().max(1);
+    match (secs / 60, secs % 60) {
+        (0, secs) => format!("{}秒", secs),
+        (mins, 0) => format!("{}分", mins),
+        (mins, secs) => format!("{}分{}秒", mins, secs),
+    }
+}
 
-fn create_buttons(disabled: bool) -> Vec<CreateActionRow> {
+// 押された後の確認ボタン
+fn create_disabled_buttons() -> Vec<CreateActionRow> {
     let ok = CreateButton::new(CUSTOM_ID_REDEPLOY_CONFIRM)
         .label("OK")
         .style(ButtonStyle::Primary)
-        .disabled(disabled);
+        .disabled(true);
 
     let cancel = CreateButton::new(CUSTOM_ID_REDEPLOY_CANCELED)
         .label("キャンセル")
         .style(ButtonStyle::Secondary)
-        .disabled(disabled);
+        .disabled(true);
 
     vec![CreateActionRow::Buttons(vec![ok, cancel])]
 }
@@ -82,23 +171,91 @@ impl Bot {
                         "problem_code",
                         "問題コード",
                     )
-                    .required(true),
+                    .required(true)
+                    .set_autocomplete(true),
+                )
+                .add_sub_option(create_team_option()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "status",
+                    "現在の再展開状況を表示します。",
+                )
+                .add_sub_option(create_team_option()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "reset",
+                    "チームの再展開の回数制限をリセットします。（staffのみ）",
+                )
+                .add_sub_option(create_team_option().required(true))
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "problem_code",
+                        "問題コード（省略した場合は全ての問題）",
+                    )
+                    .set_autocomplete(true),
                 ),
             )
-            .add_option(CreateCommandOption::new(
-                CommandOptionType::SubCommand,
-                "status",
-                "現在の再展開状況を表示します。",
-            ))
     }
 
     #[tracing::instrument(skip_all)]
-    pub async fn handle_redeploy_command(
+    pub async fn handle_redeploy_autocomplete(
         &self,
-        ctx: &Context,
         interaction: &CommandInteraction,
     ) -> Result<()> {
-        if let Err(err) = self._handle_redeploy_command(ctx, interaction).await {
+        let focused = match interaction.data.autocomplete() {
+            Some(focused) => focused,
+            None => return Ok(()),
+        };
+
+        let mut response = CreateAutocompleteResponse::new();
+
+        if focused.name == "problem_code" {
+            // startサブコマンドでは、再展開できない問題を候補に含めない。
+            let is_start = interaction
+                .data
+                .options
+                .first()
+                .is_some_and(|subcommand| subcommand.name == "start");
+
+            response = add_problem_choices(
+                response,
+                search_problems(&self.problems, focused.value)
+                    .into_iter()
+                    .filter(|problem| !is_start || problem.is_redeployable()),
+            );
+        }
+
+        if focused.name == "team" {
+            // teamオプションはstaffのみが指定できるため、staff以外には候補を表示しない。
+            let is_staff = match &interaction.member {
+                Some(member) => self.is_staff_member(member).await?,
+                None => false,
+            };
+
+            if is_staff {
+                // Discordの制約上、候補は25件までしか返せない。
+                for team in search_teams(&self.teams, focused.value)
+                    .into_iter()
+                    .take(25)
+                {
+                    response = response
+                        .add_string_choice(format!("{}: {}", team.id, team.role_name), &team.id);
+                }
+            }
+        }
+
+        self.respond_autocomplete(interaction, response).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn handle_redeploy_command(&self, interaction: &CommandInteraction) -> Result<()> {
+        if let Err(err) = self._handle_redeploy_command(interaction).await {
             tracing::error!(?err, "failed to handle redeploy command");
             self.edit_response(
                 interaction,
@@ -112,7 +269,6 @@ impl Bot {
 
     async fn _handle_redeploy_command(
         &self,
-        ctx: &Context,
         interaction: &CommandInteraction,
     ) -> RedeployCommandResult<()> {
         let subcommand = interaction
@@ -124,10 +280,17 @@ impl Bot {
         match &subcommand.value {
             CommandDataOptionValue::SubCommand(options) => match subcommand.name.as_str() {
                 "start" => {
-                    self.handle_redeploy_start_subcommand(ctx, interaction, options)
+                    self.handle_redeploy_start_subcommand(interaction, options)
+                        .await?
+                },
+                "status" => {
+                    self.handle_redeploy_status_subcommand(interaction, options)
+                        .await?
+                },
+                "reset" => {
+                    self.handle_redeploy_reset_subcommand(interaction, options)
                         .await?
                 },
-                "status" => self.handle_redeploy_status_subcommand(interaction).await?,
                 _ => return Err(RedeployCommandError::InconsistentCommandDefinitionError),
             },
             _ => return Err(RedeployCommandError::InconsistentCommandDefinitionError),
@@ -137,23 +300,41 @@ impl Bot {
     }
 
     async fn get_team_for(&self, user: &User) -> RedeployCommandResult<Team> {
-        let member = self.get_member(&user).await?;
+        let member = self.get_member(user).await?;
 
         for role_id in member.roles {
-            let role = self.find_roles_by_id_cached(role_id).await.unwrap();
-            match role {
-                Some(role) => {
-                    for team in &self.teams {
-                        if role.name == team.role_name {
-                            return Ok(team.clone());
-                        }
-                    }
-                },
-                None => (),
+            if let Some(role) = self.find_roles_by_id_cached(role_id).await? {
+                if let Some(team) = self.teams.iter().find(|team| role.name == team.role_name) {
+                    return Ok(team.clone());
+                }
             }
         }
 
-        Err(RedeployCommandError::UnexpectedSenderTeamsError)
+        Err(RedeployCommandError::SenderTeamNotFoundError)
+    }
+
+    // コマンドの対象となるチームを解決する。
+    // staffがteamオプションを指定した場合はそのチームを、それ以外の場合は呼び出し元のチームを返す。
+    // 2つ目の返り値は、staffがチームの代わりに実行しているかを表す。
+    async fn resolve_redeploy_team(
+        &self,
+        interaction: &CommandInteraction,
+        options: &[CommandDataOption],
+    ) -> RedeployCommandResult<(Team, bool)> {
+        let team_id = match self.get_option_as_str(options, "team") {
+            Some(team_id) => team_id,
+            None => return Ok((self.get_team_for(&interaction.user).await?, false)),
+        };
+
+        let member = self.get_member(&interaction.user).await?;
+        if !self.is_staff_member(&member).await? {
+            return Err(RedeployCommandError::TeamOptionNotAllowedError);
+        }
+
+        let team = find_team(&self.teams, team_id)
+            .ok_or_else(|| RedeployCommandError::InvalidTeamIdError(team_id.to_string()))?;
+
+        Ok((team.clone(), true))
     }
 }
 
@@ -161,12 +342,21 @@ impl Bot {
     #[tracing::instrument(skip_all)]
     async fn handle_redeploy_start_subcommand(
         &self,
-        ctx: &Context,
         interaction: &CommandInteraction,
         options: &[CommandDataOption],
     ) -> RedeployCommandResult<()> {
-        let problem = match self.validate_redeploy_start_subcommand(options) {
-            Ok(problem) => problem,
+        // 理由を入力させてから断ることがないよう、再展開できるかはモーダルを表示する前に確認する。
+        let checked = async {
+            let problem = self.validate_redeploy_start_subcommand(options)?;
+            let (sender_team, triggered_by_staff) =
+                self.resolve_redeploy_team(interaction, options).await?;
+            self.check_redeploy_available(&sender_team, problem, triggered_by_staff)
+                .await?;
+            Ok::<_, RedeployCommandError>((problem, sender_team, triggered_by_staff))
+        }
+        .await;
+        let (problem, sender_team, triggered_by_staff) = match checked {
+            Ok(checked) => checked,
             Err(err) => {
                 self.respond(
                     interaction,
@@ -179,51 +369,163 @@ impl Bot {
             },
         };
 
-        self.defer_response(interaction).await?;
-
-        if let Err(err) = self
-            .do_redeploy_start_subcommand(ctx, interaction, problem)
-            .await
-        {
-            tracing::error!(?err, "failed to do redeploy start subcommand");
-            return Err(err);
-        }
+        // 確認の前に、再展開の理由を尋ねる。
+        // botが再起動しても送信を受け付けられるよう、再展開の内容はモーダルのcustom_idに署名付きで埋め込み、
+        // 送信はinteraction_createで受け取る。
+        let expires_at =
+            Utc::now() + chrono::Duration::seconds(self.redeploy_confirmation.ttl_secs as i64);
+        let request = RedeployConfirmation::new(
+            &sender_team.id,
+            &problem.code,
+            triggered_by_staff,
+            interaction.user.id.get(),
+            expires_at,
+        )
+        .with_action(RedeployConfirmationAction::Reason);
+        let modal = CreateModal::new(request.encode(self.confirmation_secret()), "再展開の理由")
+            .components(vec![CreateActionRow::InputText(
+                CreateInputText::new(
+                    InputTextStyle::Paragraph,
+                    "理由（任意）",
+                    CUSTOM_ID_REDEPLOY_REASON,
+                )
+                .placeholder("例: 設定を誤ってネットワークに繋がらなくなったため")
+                .max_length(REDEPLOY_REASON_MAX_LENGTH)
+                .required(false),
+            )]);
+        self.show_modal(interaction, modal).await?;
 
         Ok(())
     }
 
-    fn validate_redeploy_start_subcommand<'t>(
+    fn validate_redeploy_start_subcommand(
         &self,
-        options: &'t [CommandDataOption],
-    ) -> RedeployCommandResult<'t, &Problem> {
+        options: &[CommandDataOption],
+    ) -> RedeployCommandResult<&Problem> {
         let problem_code = self.get_option_as_str(options, "problem_code").unwrap();
+        let problem = self.find_problem(problem_code).ok_or_else(|| {
+            RedeployCommandError::InvalidProblemCodeError(problem_code.to_string())
+        })?;
+        if !problem.is_redeployable() {
+            return Err(RedeployCommandError::NotRedeployableError(
+                problem.name.clone(),
+            ));
+        }
+        Ok(problem)
+    }
 
-        // スコアサーバーとの互換性のため、ここで大文字に正規化する
-        let normalized_problem_code = problem_code.to_uppercase();
+    async fn do_redeploy_start_subcommand(
+        &self,
+        response: &ModalInteraction,
+        sender_team: &Team,
+        triggered_by_staff: bool,
+        problem: &Problem,
+        reason: Option<String>,
+    ) -> RedeployCommandResult<()> {
+        let sender = &response.user;
 
-        let problem = self
-            .problems
-            .iter()
-            .find(|problem| problem.code == normalized_problem_code);
+        // モーダルの入力中に状況が変わっている場合があるため、残り回数は改めて確認する。
+        let remaining_redeploys = self
+            .check_redeploy_available(sender_team, problem, triggered_by_staff)
+            .await?;
+
+        let mut confirmation = format!(
+            "チーム `{}` の問題 `{}` を再展開しますか？",
+            sender_team.role_name, problem.name
+        );
+        if let Some(confirmation_message) = &problem.confirmation_message {
+            confirmation.push_str(&format!("\n\n⚠️ {}\n", confirmation_message));
+        }
+        if let Some(remaining_redeploys) = remaining_redeploys {
+            confirmation.push_str(&format!(
+                "\n（この再展開を実行すると、残りの再展開回数は{}回です）",
+                remaining_redeploys
+            ));
+        }
+        if triggered_by_staff {
+            confirmation.push_str("\n（staffによるチームの代理実行です）");
+        }
+
+        let expires_at =
+            Utc::now() + chrono::Duration::seconds(self.redeploy_confirmation.ttl_secs as i64);
+        let request = RedeployConfirmation::new(
+            &sender_team.id,
+            &problem.code,
+            triggered_by_staff,
+            sender.id.get(),
+            expires_at,
+        );
 
-        problem.ok_or(RedeployCommandError::InvalidProblemCodeError(problem_code))
+        // 理由はcustom_idに収まらないため、確認メッセージに埋め込んでおき、ボタンが押された時に読み取る。
+        let mut message = EditInteractionResponse::new()
+            .content(confirmation)
+            .components(self.create_confirmation_buttons(&request));
+        if let Some(reason) = &reason {
+            message = message.embed(
+                CreateEmbed::new()
+                    .title(REDEPLOY_REASON_EMBED_TITLE)
+                    .description(reason),
+            );
+        }
+
+        self.edit_response(response, message).await?;
+        Ok(())
     }
 
-    async fn do_redeploy_start_subcommand(
+    fn confirmation_secret(&self) -> &str {
+        self.redeploy_confirmation
+            .secret
+            .as_deref()
+            .unwrap_or(&self.token)
+    }
+
+    fn create_confirmation_buttons(&self, request: &RedeployConfirmation) -> Vec<CreateActionRow> {
+        let secret = self.confirmation_secret();
+        let ok = CreateButton::new(
+            request
+                .with_action(RedeployConfirmationAction::Confirm)
+                .encode(secret),
+        )
+        .label("OK")
+        .style(ButtonStyle::Primary);
+
+        let cancel = CreateButton::new(
+            request
+                .with_action(RedeployConfirmationAction::Cancel)
+                .encode(secret),
+        )
+        .label("キャンセル")
+        .style(ButtonStyle::Secondary);
+
+        vec![CreateActionRow::Buttons(vec![ok, cancel])]
+    }
+
+    // 問題が再展開中でないこと、回数制限に達していないことを確認する。
+    // 回数の上限が設定されていれば、今回の再展開後の残り回数を返す。
+    async fn check_redeploy_available(
         &self,
-        ctx: &Context,
-        interaction: &CommandInteraction,
+        team: &Team,
         problem: &Problem,
-    ) -> RedeployCommandResult<()> {
-        let sender = &interaction.user;
-        let sender_team = self.get_team_for(sender).await?;
+        triggered_by_staff: bool,
+    ) -> RedeployCommandResult<Option<u32>> {
+        let redeploy_status = self.redeploy_service.get_status(&team.id).await?;
+
+        // 状態不明の問題は、再展開中でないこともクールダウンが明けていることも確認できない。
+        let status_unknown = redeploy_status
+            .iter()
+            .any(|status| status.problem_code == problem.code && status.is_unknown);
+        if status_unknown {
+            return Err(RedeployCommandError::StatusUnknownError(
+                problem.name.clone(),
+            ));
+        }
 
-        let redeploy_status = self.redeploy_service.get_status(&sender_team.id).await?;
         let redeploy_job_exists = redeploy_status.iter().any(|status| {
-            // リクエストされた問題が既に再展開中か？
+            // リクエストされた問題が既に再展開中（または順番待ち）か？
             status.problem_code == problem.code
-                && status.last_redeploy_started_at.is_some()
-                && status.last_redeploy_completed_at.is_none()
+                && (status.is_redeploying
+                    || status.last_redeploy_started_at.is_some()
+                        && status.last_redeploy_completed_at.is_none())
         });
 
         if redeploy_job_exists {
@@ -232,83 +534,373 @@ impl Bot {
             ));
         }
 
-        self.edit_response(
-            interaction,
-            EditInteractionResponse::new()
-                .content(format!(
-                    "チーム `{}` の問題 `{}` を再展開しますか？",
-                    sender_team.role_name, problem.name
-                ))
-                .components(create_buttons(false)),
-        )
-        .await?;
-
-        let message = self.get_response(interaction).await?;
-
-        let component_interaction = message
-            .await_component_interaction(ctx)
-            .author_id(sender.id)
-            .filter(
-                |component_interaction| match &component_interaction.data.kind {
-                    ComponentInteractionDataKind::Button => {
-                        component_interaction.data.custom_id == CUSTOM_ID_REDEPLOY_CONFIRM
-                            || component_interaction.data.custom_id == CUSTOM_ID_REDEPLOY_CANCELED
-                    },
-                    _ => false,
+        // staffによる代理実行は回数制限の対象外とする。
+        if triggered_by_staff {
+            return Ok(None);
+        }
+        self.check_redeploy_limits(team, problem, &redeploy_status)
+            .await
+    }
+}
+
+impl Bot {
+    // 再展開の理由を入力するモーダルの送信を処理し、確認メッセージを表示する。
+    // 確認ボタンと同様に、モーダルのcustom_idに再展開の内容が署名付きで埋め込まれている。
+    #[tracing::instrument(skip_all)]
+    pub async fn handle_redeploy_reason(&self, interaction: &ModalInteraction) -> Result<()> {
+        let request = match RedeployConfirmation::decode(
+            &interaction.data.custom_id,
+            self.confirmation_secret(),
+            Utc::now(),
+        ) {
+            Ok(request) if request.action == RedeployConfirmationAction::Reason => request,
+            Ok(_) => {
+                tracing::warn!("unexpected redeploy reason action");
+                self.respond(
+                    interaction,
+                    CreateInteractionResponseMessage::new()
+                        .ephemeral(true)
+                        .content(
+                            "このフォームは無効です。再度、再作成リクエストを投稿してください。",
+                        ),
+                )
+                .await?;
+                return Ok(());
+            },
+            Err(RedeployConfirmationError::Expired) => {
+                self.respond(
+                    interaction,
+                    CreateInteractionResponseMessage::new()
+                        .ephemeral(true)
+                        .content(
+                            "タイムアウトしました。再度、再作成リクエストを投稿してください。",
+                        ),
+                )
+                .await?;
+                return Ok(());
+            },
+            Err(err) => {
+                tracing::warn!(?err, "invalid redeploy reason");
+                self.respond(
+                    interaction,
+                    CreateInteractionResponseMessage::new()
+                        .ephemeral(true)
+                        .content(
+                            "このフォームは無効です。再度、再作成リクエストを投稿してください。",
+                        ),
+                )
+                .await?;
+                return Ok(());
+            },
+        };
+
+        if request.requester_id != interaction.user.id.get() {
+            self.respond(
+                interaction,
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .content("このフォームは、再展開をリクエストしたユーザのみ送信できます。"),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        self.defer_response(interaction).await?;
+
+        let reason = interaction
+            .data
+            .components
+            .iter()
+            .flat_map(|row| row.components.iter())
+            .find_map(|component| match component {
+                ActionRowComponent::InputText(input)
+                    if input.custom_id == CUSTOM_ID_REDEPLOY_REASON =>
+                {
+                    input.value.clone()
                 },
+                _ => None,
+            })
+            .map(|reason| reason.trim().to_string())
+            .filter(|reason| !reason.is_empty());
+
+        // コマンドへの応答はモーダルで済んでいるため、エラーはモーダルへの応答として返す。
+        let result = async {
+            let sender_team = self
+                .teams
+                .iter()
+                .find(|team| team.id == request.team_id)
+                .ok_or_else(|| RedeployCommandError::InvalidTeamIdError(request.team_id.clone()))?;
+            let problem = self
+                .find_problem(&request.problem_code)
+                .filter(|problem| problem.is_redeployable())
+                .ok_or_else(|| {
+                    RedeployCommandError::InvalidProblemCodeError(request.problem_code.clone())
+                })?;
+            self.do_redeploy_start_subcommand(
+                interaction,
+                sender_team,
+                request.triggered_by_staff,
+                problem,
+                reason,
             )
-            .timeout(Duration::from_secs(60))
-            .await;
+            .await
+        }
+        .await;
+        if let Err(err) = result {
+            tracing::error!(?err, "failed to do redeploy start subcommand");
+            self.edit_response(
+                interaction,
+                EditInteractionResponse::new().content(err.to_string()),
+            )
+            .await?;
+        }
 
-        let component_interaction = match component_interaction {
-            Some(component_interaction) => component_interaction,
-            None => {
-                self.edit_response(
+        Ok(())
+    }
+}
+
+impl Bot {
+    // 再展開の確認ボタンを処理する。
+    // ボタンのcustom_idに再展開の内容が署名付きで埋め込まれているため、botの再起動後も処理できる。
+    #[tracing::instrument(skip_all)]
+    pub async fn handle_redeploy_confirmation(
+        &self,
+        interaction: &ComponentInteraction,
+    ) -> Result<()> {
+        let request = match RedeployConfirmation::decode(
+            &interaction.data.custom_id,
+            self.confirmation_secret(),
+            Utc::now(),
+        ) {
+            // 理由を入力するモーダルのcustom_idは、ボタンとしては受け付けない。
+            Ok(request) if request.action != RedeployConfirmationAction::Reason => request,
+            Ok(_) => {
+                tracing::warn!("unexpected redeploy confirmation action");
+                self.respond(
                     interaction,
-                    EditInteractionResponse::new()
-                        .content("タイムアウトしました。再度、再作成リクエストを投稿してください。")
-                        .components(create_buttons(true)),
+                    CreateInteractionResponseMessage::new()
+                        .ephemeral(true)
+                        .content(
+                            "このボタンは無効です。再度、再作成リクエストを投稿してください。",
+                        ),
+                )
+                .await?;
+                return Ok(());
+            },
+            Err(RedeployConfirmationError::Expired) => {
+                self.disable_confirmation_buttons(interaction).await?;
+                self.respond(
+                    interaction,
+                    CreateInteractionResponseMessage::new()
+                        .ephemeral(true)
+                        .content(
+                            "タイムアウトしました。再度、再作成リクエストを投稿してください。",
+                        ),
+                )
+                .await?;
+                return Ok(());
+            },
+            Err(err) => {
+                tracing::warn!(?err, "invalid redeploy confirmation");
+                self.respond(
+                    interaction,
+                    CreateInteractionResponseMessage::new()
+                        .ephemeral(true)
+                        .content(
+                            "このボタンは無効です。再度、再作成リクエストを投稿してください。",
+                        ),
                 )
                 .await?;
                 return Ok(());
             },
         };
 
-        self.edit_response(
-            interaction,
-            EditInteractionResponse::new().components(create_buttons(true)),
-        )
-        .await?;
-        self.defer_response(&component_interaction).await?;
+        if request.requester_id != interaction.user.id.get() {
+            self.respond(
+                interaction,
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .content("このボタンは、再展開をリクエストしたユーザのみ押すことができます。"),
+            )
+            .await?;
+            return Ok(());
+        }
 
-        let should_recreate = component_interaction.data.custom_id == CUSTOM_ID_REDEPLOY_CONFIRM;
-        if !should_recreate {
+        // 署名付きのcustom_idは有効期限まで何度でも検証を通るため、同じ確認が二度使われないよう記録しておく。
+        // OKとキャンセルは同じキーを持つため、一方が押された後はもう一方も使えなくなる。
+        let consumed = self
+            .storage
+            .consume_redeploy_confirmation(&request.key(), request.expires_at, Utc::now())
+            .await;
+        let message = match consumed {
+            Ok(true) => None,
+            Ok(false) => Some("このボタンは既に押されています。"),
+            Err(err) => {
+                tracing::error!(?err, "failed to consume redeploy confirmation");
+                Some("エラーが発生しました。再度、再作成リクエストを投稿してください。")
+            },
+        };
+        if let Some(message) = message {
+            self.respond(
+                interaction,
+                CreateInteractionResponseMessage::new()
+                    .ephemeral(true)
+                    .content(message),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // 再展開を実行する前に、確認メッセージからボタンを取り除いておく。
+        self.disable_confirmation_buttons(interaction).await?;
+        self.defer_response(interaction).await?;
+
+        if request.action == RedeployConfirmationAction::Cancel {
             self.edit_response(
-                &component_interaction,
+                interaction,
                 EditInteractionResponse::new().content("再展開を中止しました。"),
             )
             .await?;
             return Ok(());
         }
 
+        if let Err(err) = self.do_redeploy(interaction, &request).await {
+            tracing::error!(?err, "failed to redeploy");
+            self.edit_response(
+                interaction,
+                EditInteractionResponse::new().content(err.to_string()),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    // 二重に押されないよう、確認メッセージのボタンを無効にする
+    async fn disable_confirmation_buttons(
+        &self,
+        interaction: &ComponentInteraction,
+    ) -> RedeployCommandResult<()> {
+        interaction
+            .channel_id
+            .edit_message(
+                &self.discord_client,
+                interaction.message.id,
+                EditMessage::new().components(create_disabled_buttons()),
+            )
+            .await
+            .map_err(HelperError::from)?;
+        Ok(())
+    }
+
+    async fn do_redeploy(
+        &self,
+        interaction: &ComponentInteraction,
+        request: &RedeployConfirmation,
+    ) -> RedeployCommandResult<()> {
+        let sender = &interaction.user;
+        let sender_team = self
+            .teams
+            .iter()
+            .find(|team| team.id == request.team_id)
+            .ok_or_else(|| RedeployCommandError::InvalidTeamIdError(request.team_id.clone()))?;
+        let problem = self
+            .find_problem(&request.problem_code)
+            .filter(|problem| problem.is_redeployable())
+            .ok_or_else(|| {
+                RedeployCommandError::InvalidProblemCodeError(request.problem_code.clone())
+            })?;
+
+        // 確認を表示してから時間が経っている可能性があるため、改めて確認する。
+        self.check_redeploy_available(sender_team, problem, request.triggered_by_staff)
+            .await?;
+
+        let reason = interaction
+            .message
+            .embeds
+            .iter()
+            .find(|embed| embed.title.as_deref() == Some(REDEPLOY_REASON_EMBED_TITLE))
+            .and_then(|embed| embed.description.clone());
+
         let target = RedeployTarget {
             team_id: sender_team.id.clone(),
             problem_id: problem.code.clone(),
+            triggered_by_staff: request.triggered_by_staff,
+            reason,
         };
+        let requested_at = Utc::now();
+        let started = Instant::now();
         let result = self.redeploy_service.redeploy(&target).await;
+        let latency = started.elapsed();
+
+        let record = RedeployRecord {
+            requested_at,
+            team_id: target.team_id.clone(),
+            problem_code: target.problem_id.clone(),
+            user_id: sender.id.get(),
+            user_name: sender.name.clone(),
+            triggered_by_staff: request.triggered_by_staff,
+            job_id: result.as_ref().ok().map(|job| job.id.clone()),
+            error: result.as_ref().err().map(|err| err.to_string()),
+            completed_at: None,
+            timed_out: false,
+        };
+        let record_id = match self.storage.record_redeploy(&record).await {
+            Ok(record_id) => Some(record_id),
+            Err(err) => {
+                tracing::error!(?err, "failed to record redeploy");
+                None
+            },
+        };
 
         match &result {
-            Ok(_) => {
+            Ok(job) => {
                 self.edit_response(
-                    &component_interaction,
-                    EditInteractionResponse::new().content("再展開を開始しました。"),
+                    interaction,
+                    EditInteractionResponse::new().content(
+                        "再展開を開始しました。完了したらチームチャンネルでお知らせします。",
+                    ),
                 )
                 .await?;
+
+                // 完了を通知するチャンネルが見つからない場合は、コマンドが実行されたチャンネルに通知する。
+                let channel_id = match self.find_team_text_channel(sender_team).await {
+                    Ok(Some(channel)) => channel.id,
+                    Ok(None) => interaction.channel_id,
+                    Err(err) => {
+                        tracing::warn!(?err, "failed to find team text channel");
+                        interaction.channel_id
+                    },
+                };
+
+                let watched_job = WatchedRedeployJob {
+                    job: job.clone(),
+                    target: target.clone(),
+                    requester: RedeployRequester {
+                        user_id: sender.id.get(),
+                        user_name: sender.name.clone(),
+                    },
+                    channel_id: Some(channel_id),
+                    requested_at,
+                    record_id,
+                };
+                if let Err(err) = self.redeploy_job_sender.send(watched_job) {
+                    tracing::error!(?err, "failed to watch redeploy job");
+                }
             },
             Err(err) => match err {
+                RedeployError::Unavailable => {
+                    self.edit_response(
+                        interaction,
+                        EditInteractionResponse::new()
+                            .content(RedeployCommandError::RedeployUnavailableError.to_string()),
+                    )
+                    .await?;
+                },
+
                 RedeployError::AnotherJobInQueue(_) => {
                     self.edit_response(
-                        &component_interaction,
+                        interaction,
                         EditInteractionResponse::new() .content("この問題は既に再展開リクエストが投げられています。再展開が完了してから再度お試しください。")
                     )
                     .await?;
@@ -317,7 +909,7 @@ impl Bot {
                 _ => {
                     tracing::error!(?err, "failed to redeploy");
                     self.edit_response(
-                        &component_interaction,
+                        interaction,
                         EditInteractionResponse::new().content(
                             "再展開中にエラーが発生しました。運営にお問い合わせください。",
                         ),
@@ -327,23 +919,163 @@ impl Bot {
             },
         };
 
-        for notifier in &self.redeploy_notifiers {
-            notifier.notify(&target, &result).await;
+        let event = RedeployRequestEvent {
+            target: &target,
+            result: &result,
+            requester: RedeployRequester {
+                user_id: sender.id.get(),
+                user_name: sender.name.clone(),
+            },
+            requested_at,
+            latency,
+            problem_name: Some(problem.name.clone()),
+            message_link: Some(
+                interaction
+                    .message
+                    .id
+                    .link(interaction.channel_id, interaction.guild_id),
+            ),
+        };
+        for notifier in self.redeploy_notifiers.iter() {
+            notifier.notify(&event).await;
         }
 
         Ok(())
     }
 }
 
+impl Bot {
+    // チームの再展開の回数制限を確認し、上限が設定されていれば今回の再展開後の残り回数を返す。
+    // staffが回数制限をリセットした場合、リセット以前の再展開は回数にもクールダウンにも数えない。
+    async fn check_redeploy_limits(
+        &self,
+        team: &Team,
+        problem: &Problem,
+        statuses: &[RedeployStatus],
+    ) -> RedeployCommandResult<Option<u32>> {
+        let last_reset = self
+            .storage
+            .last_redeploy_limit_reset(&team.id, &problem.code)
+            .await?;
+
+        let cooldown_secs = self.redeploy_limits.cooldown_secs(&problem.code);
+        let last_completed_at = statuses
+            .iter()
+            .find(|status| status.problem_code == problem.code)
+            .and_then(|status| status.last_redeploy_completed_at)
+            .filter(|completed_at| last_reset.is_none_or(|reset_at| *completed_at > reset_at));
+
+        if let Some(completed_at) = last_completed_at {
+            let available_at = completed_at + chrono::Duration::seconds(cooldown_secs as i64);
+            let now = Utc::now();
+            if now < available_at {
+                return Err(RedeployCommandError::CooldownError(
+                    problem.name.clone(),
+                    format_remaining(available_at - now),
+                ));
+            }
+        }
+
+        let max_redeploys = match self.redeploy_limits.max_redeploys(problem) {
+            Some(max_redeploys) => max_redeploys,
+            None => return Ok(None),
+        };
+
+        let count = self
+            .storage
+            .count_redeploys(&team.id, &problem.code, last_reset)
+            .await?;
+        if count >= max_redeploys {
+            return Err(RedeployCommandError::QuotaExceededError(
+                problem.name.clone(),
+                max_redeploys,
+            ));
+        }
+
+        Ok(Some(max_redeploys - count - 1))
+    }
+}
+
+impl Bot {
+    #[tracing::instrument(skip_all)]
+    async fn handle_redeploy_reset_subcommand(
+        &self,
+        interaction: &CommandInteraction,
+        options: &[CommandDataOption],
+    ) -> RedeployCommandResult<()> {
+        self.defer_response(interaction).await?;
+
+        if let Err(err) = self
+            .do_redeploy_reset_subcommand(interaction, options)
+            .await
+        {
+            tracing::error!(?err, "failed to do redeploy reset subcommand");
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    async fn do_redeploy_reset_subcommand(
+        &self,
+        interaction: &CommandInteraction,
+        options: &[CommandDataOption],
+    ) -> RedeployCommandResult<()> {
+        let member = self.get_member(&interaction.user).await?;
+        if !self.is_staff_member(&member).await? {
+            return Err(RedeployCommandError::StaffOnlySubcommandError);
+        }
+
+        let (team, _) = self.resolve_redeploy_team(interaction, options).await?;
+
+        let problem = match self.get_option_as_str(options, "problem_code") {
+            Some(problem_code) => Some(self.find_problem(problem_code).ok_or_else(|| {
+                RedeployCommandError::InvalidProblemCodeError(problem_code.to_string())
+            })?),
+            None => None,
+        };
+
+        self.storage
+            .record_redeploy_limit_reset(&RedeployLimitResetRecord {
+                reset_at: Utc::now(),
+                team_id: team.id.clone(),
+                problem_code: problem.map(|problem| problem.code.clone()),
+                user_id: interaction.user.id.get(),
+                user_name: interaction.user.name.clone(),
+            })
+            .await?;
+
+        let content = match problem {
+            Some(problem) => format!(
+                "チーム `{}` の問題 `{}` の再展開の回数制限をリセットしました。",
+                team.role_name, problem.name
+            ),
+            None => format!(
+                "チーム `{}` の全ての問題の再展開の回数制限をリセットしました。",
+                team.role_name
+            ),
+        };
+
+        self.edit_response(interaction, EditInteractionResponse::new().content(content))
+            .await?;
+
+        Ok(())
+    }
+}
+
 impl Bot {
     #[tracing::instrument(skip_all)]
     async fn handle_redeploy_status_subcommand(
         &self,
         interaction: &CommandInteraction,
+        options: &[CommandDataOption],
     ) -> RedeployCommandResult<()> {
         self.defer_response(interaction).await?;
 
-        if let Err(err) = self.do_redeploy_status_subcommand(interaction).await {
+        if let Err(err) = self
+            .do_redeploy_status_subcommand(interaction, options)
+            .await
+        {
             tracing::error!(?err, "failed to do redeploy status subcommand");
             return Err(err);
         }
@@ -354,19 +1086,16 @@ impl Bot {
     async fn do_redeploy_status_subcommand(
         &self,
         interaction: &CommandInteraction,
+        options: &[CommandDataOption],
     ) -> RedeployCommandResult<()> {
-        let sender = &interaction.user;
-        let sender_team = self.get_team_for(sender).await?;
+        let (sender_team, triggered_by_staff) =
+            self.resolve_redeploy_team(interaction, options).await?;
 
-        let statuses = self
-            .redeploy_service
-            .get_status(&sender_team.id)
-            .await
-            .unwrap();
+        let statuses = self.redeploy_service.get_status(&sender_team.id).await?;
 
         let no_deploys = statuses
             .iter()
-            .all(|status| status.last_redeploy_started_at.is_none());
+            .all(|status| !status.is_unknown && status.last_redeploy_started_at.is_none());
 
         if no_deploys {
             self.edit_response(
@@ -377,13 +1106,12 @@ impl Bot {
             return Ok(());
         }
 
-        let mut embed = CreateEmbed::new().title("再展開状況");
+        let mut embed = if triggered_by_staff {
+            CreateEmbed::new().title(format!("再展開状況（{}）", sender_team.role_name))
+        } else {
+            CreateEmbed::new().title("再展開状況")
+        };
         for status in &statuses {
-            let started_at = match status.last_redeploy_started_at {
-                Some(started_at) => started_at,
-                None => continue,
-            };
-
             let name = status.problem_code.clone();
             let problem_name = self
                 .problems
@@ -392,6 +1120,20 @@ impl Bot {
                 .map(|problem| format!("{}: {}", name, problem.name))
                 .unwrap_or_else(|| name);
 
+            if status.is_unknown {
+                embed = embed.field(
+                    problem_name,
+                    "❓ 不明（再展開状況を取得できませんでした）",
+                    false,
+                );
+                continue;
+            }
+
+            let started_at = match status.last_redeploy_started_at {
+                Some(started_at) => started_at,
+                None => continue,
+            };
+
             let value = match status.last_redeploy_completed_at {
                 Some(completed_at) => {
                     let completed_at_local = completed_at.with_timezone(&chrono_tz::Asia::Tokyo);
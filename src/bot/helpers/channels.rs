@@ -18,6 +18,9 @@ pub struct GuildChannelDefinition {
     pub category: Option<ChannelId>,
     #[builder(default)]
     pub permissions: Vec<PermissionOverwrite>,
+    // 以前の名前。nameのチャンネルが存在せず、以前の名前のチャンネルが存在する場合は、そのチャンネルの名前を変更する。
+    #[builder(default)]
+    pub previous_names: Vec<String>,
 }
 
 // Guildのチャンネルを操作するためのヘルパー関数
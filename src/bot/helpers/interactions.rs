@@ -1,17 +1,22 @@
 use serenity::all::CommandDataOption;
 use serenity::all::CommandInteraction;
 use serenity::all::ComponentInteraction;
+use serenity::all::CreateAutocompleteResponse;
 use serenity::all::CreateInteractionResponse;
 use serenity::all::CreateInteractionResponseMessage;
+use serenity::all::CreateModal;
 use serenity::all::Message;
+use serenity::all::ModalInteraction;
 use serenity::builder::EditInteractionResponse;
 
 use super::HelperResult;
 use crate::bot::Bot;
 
+#[allow(clippy::enum_variant_names)]
 pub enum Interaction<'a> {
     CommandInteraction(&'a CommandInteraction),
     ComponentInteraction(&'a ComponentInteraction),
+    ModalInteraction(&'a ModalInteraction),
 }
 
 impl<'a> From<&'a CommandInteraction> for Interaction<'a> {
@@ -26,6 +31,12 @@ impl<'a> From<&'a ComponentInteraction> for Interaction<'a> {
     }
 }
 
+impl<'a> From<&'a ModalInteraction> for Interaction<'a> {
+    fn from(interaction: &'a ModalInteraction) -> Self {
+        Interaction::ModalInteraction(interaction)
+    }
+}
+
 // Interactionに対する操作するためのヘルパー関数
 impl Bot {
     // ユーザからのinteractionに即時応答するメソッド
@@ -56,9 +67,66 @@ impl Bot {
                     )
                     .await?
             },
+            Interaction::ModalInteraction(interaction) => {
+                interaction
+                    .create_response(
+                        &self.discord_client,
+                        CreateInteractionResponse::Message(message),
+                    )
+                    .await?
+            },
         })
     }
 
+    // ユーザからのautocompleteリクエストに候補を返すメソッド
+    #[tracing::instrument(skip_all)]
+    pub async fn respond_autocomplete(
+        &self,
+        interaction: &CommandInteraction,
+        response: CreateAutocompleteResponse,
+    ) -> HelperResult<()> {
+        tracing::trace!("Respond autocomplete");
+        Ok(interaction
+            .create_response(
+                &self.discord_client,
+                CreateInteractionResponse::Autocomplete(response),
+            )
+            .await?)
+    }
+
+    // ユーザからのinteractionにモーダルで応答するメソッド
+    // モーダルの送信は、別のinteractionとして受け取る。
+    #[tracing::instrument(skip_all)]
+    pub async fn show_modal(
+        &self,
+        interaction: &CommandInteraction,
+        modal: CreateModal,
+    ) -> HelperResult<()> {
+        tracing::trace!("Show modal");
+        Ok(interaction
+            .create_response(
+                &self.discord_client,
+                CreateInteractionResponse::Modal(modal),
+            )
+            .await?)
+    }
+
+    // ボタンが押されたメッセージ自体を編集して応答するメソッド
+    #[tracing::instrument(skip_all)]
+    pub async fn update_message(
+        &self,
+        interaction: &ComponentInteraction,
+        message: CreateInteractionResponseMessage,
+    ) -> HelperResult<()> {
+        tracing::trace!("Update message");
+        Ok(interaction
+            .create_response(
+                &self.discord_client,
+                CreateInteractionResponse::UpdateMessage(message),
+            )
+            .await?)
+    }
+
     // ユーザからのinteractionの応答を保留するメソッド
     #[tracing::instrument(skip_all)]
     pub async fn defer_response<'a, I>(&self, interaction: I) -> HelperResult<()>
@@ -83,6 +151,14 @@ impl Bot {
                     )
                     .await?
             },
+            Interaction::ModalInteraction(interaction) => {
+                interaction
+                    .create_response(
+                        &self.discord_client,
+                        CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new()),
+                    )
+                    .await?
+            },
         })
     }
 
@@ -108,6 +184,11 @@ impl Bot {
                     .edit_response(&self.discord_client, message)
                     .await?
             },
+            Interaction::ModalInteraction(interaction) => {
+                interaction
+                    .edit_response(&self.discord_client, message)
+                    .await?
+            },
         })
     }
 
@@ -125,6 +206,9 @@ impl Bot {
             Interaction::ComponentInteraction(interaction) => {
                 interaction.get_response(&self.discord_client).await?
             },
+            Interaction::ModalInteraction(interaction) => {
+                interaction.get_response(&self.discord_client).await?
+            },
         })
     }
 
@@ -19,6 +19,9 @@ pub struct RoleDefinition {
     pub hoist: bool,
     #[builder(default)]
     pub mentionable: bool,
+    // 以前の名前。nameのロールが存在せず、以前の名前のロールが存在する場合は、そのロールの名前を変更する。
+    #[builder(default)]
+    pub previous_names: Vec<String>,
 }
 
 // Guildのロールを操作するためのヘルパー関数
@@ -1,8 +1,16 @@
-mod channels;
+pub mod channels;
 mod commands;
-mod helpers;
+pub mod helpers;
 mod permissions;
-mod roles;
+pub mod plan;
+mod question_dashboard;
+mod question_escalation;
+mod redeploy_watcher;
+pub mod roles;
+mod transcripts;
+
+use std::collections::HashMap;
+use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -10,68 +18,219 @@ use serenity::client::Client;
 use serenity::http::Http;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 
+use crate::bot::question_dashboard::QuestionDashboard;
+use crate::bot::question_escalation::QuestionEscalationWatcher;
+use crate::bot::redeploy_watcher::RedeployJobReceiver;
+use crate::bot::redeploy_watcher::RedeployJobSender;
+use crate::bot::redeploy_watcher::RedeployWatcher;
+use crate::config::ChannelsConfiguration;
+use crate::config::Configuration;
+use crate::config::QuestionEscalationConfiguration;
+use crate::config::QuestionTranscriptConfiguration;
+use crate::config::QuestionsConfiguration;
+use crate::config::RedeployConfirmationConfiguration;
+use crate::config::RedeployLimitsConfiguration;
+use crate::config::RedeployWatcherConfiguration;
+use crate::config::StaffConfiguration;
+use crate::models::CommandAccess;
 use crate::models::Problem;
 use crate::models::Team;
 use crate::services::redeploy::RedeployNotifier;
 use crate::services::redeploy::RedeployService;
+use crate::services::storage::Storage;
 
 pub struct Bot {
     token: String,
     application_id: ApplicationId,
     guild_id: GuildId,
     discord_client: Http,
-    infra_password: String,
+    staff: StaffConfiguration,
     teams: Vec<Team>,
     problems: Vec<Problem>,
+    channels: ChannelsConfiguration,
 
     create_voice_channels: bool,
     disabled_commands: Vec<String>,
+    command_access: HashMap<String, CommandAccess>,
+
+    redeploy_service: Arc<dyn RedeployService + Send + Sync>,
+    redeploy_notifiers: Arc<Vec<Box<dyn RedeployNotifier + Send + Sync>>>,
+
+    // 再展開Jobの完了を監視するタスクへ、監視対象のJobを送るためのチャンネル
+    redeploy_watcher_config: RedeployWatcherConfiguration,
+    redeploy_job_sender: RedeployJobSender,
+    redeploy_job_receiver: Option<RedeployJobReceiver>,
+
+    // チームごとの再展開の回数制限
+    redeploy_limits: RedeployLimitsConfiguration,
+
+    redeploy_confirmation: RedeployConfirmationConfiguration,
+
+    storage: Arc<dyn Storage + Send + Sync>,
+
+    // staffチャンネルにピン留めする、未終了の質問スレッドのダッシュボード
+    question_dashboard: Arc<QuestionDashboard>,
+
+    // staffの回答がない質問スレッドを再通知する設定
+    question_escalation_config: QuestionEscalationConfiguration,
+
+    // 質問スレッドの記録の書き出し先。Noneの場合は書き出さない。
+    question_transcripts: Option<QuestionTranscriptConfiguration>,
 
-    redeploy_service: Box<dyn RedeployService + Send + Sync>,
-    redeploy_notifiers: Vec<Box<dyn RedeployNotifier + Send + Sync>>,
+    // 質問スレッドのチケットの読み込みから保存までを直列化し、担当・回答・終了の更新が失われないようにする。
+    question_ticket_lock: Mutex<()>,
 
     role_cache: RwLock<Option<Vec<Role>>>,
 }
 
+// Botの設定。bot.yamlのConfigurationから作成する。
+#[derive(Debug, Clone)]
+pub struct BotSettings {
+    pub token: String,
+    pub application_id: u64,
+    pub guild_id: u64,
+    pub staff: StaffConfiguration,
+    pub teams: Vec<Team>,
+    pub problems: Vec<Problem>,
+    pub channels: ChannelsConfiguration,
+    pub create_voice_channels: bool,
+    pub disabled_commands: Vec<String>,
+    pub command_access: HashMap<String, CommandAccess>,
+    pub redeploy_watcher: RedeployWatcherConfiguration,
+    pub redeploy_limits: RedeployLimitsConfiguration,
+    pub redeploy_confirmation: RedeployConfirmationConfiguration,
+    pub questions: QuestionsConfiguration,
+}
+
+impl From<Configuration> for BotSettings {
+    fn from(config: Configuration) -> Self {
+        BotSettings {
+            token: config.discord.token,
+            application_id: config.discord.application_id,
+            guild_id: config.discord.guild_id,
+            staff: config.staff,
+            teams: config.teams,
+            problems: config.problems,
+            channels: config.channels,
+            create_voice_channels: config.discord.create_voice_channels,
+            disabled_commands: config.discord.disabled_commands,
+            command_access: config.discord.command_access,
+            redeploy_watcher: config.redeploy.watcher,
+            redeploy_limits: config.redeploy.limits,
+            redeploy_confirmation: config.redeploy.confirmation,
+            questions: config.questions,
+        }
+    }
+}
+
 impl Bot {
     pub fn new(
-        token: String,
-        application_id: u64,
-        guild_id: u64,
-        infra_password: String,
-        teams: Vec<Team>,
-        problems: Vec<Problem>,
-        create_voice_channels: bool,
-        disabled_commands: Vec<String>,
+        settings: BotSettings,
         redeploy_service: Box<dyn RedeployService + Send + Sync>,
-        redeploy_notifiers: Vec<Box<dyn RedeployNotifier + Send + Sync>>,
+        redeploy_notifiers: Arc<Vec<Box<dyn RedeployNotifier + Send + Sync>>>,
+        storage: Arc<dyn Storage + Send + Sync>,
     ) -> Self {
+        let BotSettings {
+            token,
+            application_id,
+            guild_id,
+            staff,
+            teams,
+            problems,
+            channels,
+            create_voice_channels,
+            disabled_commands,
+            command_access,
+            redeploy_watcher: redeploy_watcher_config,
+            redeploy_limits,
+            redeploy_confirmation,
+            questions,
+        } = settings;
+
         let application_id = ApplicationId::new(application_id);
         let guild_id = GuildId::new(guild_id);
         let discord_client = Http::new(&token);
         discord_client.set_application_id(application_id);
+        let (redeploy_job_sender, redeploy_job_receiver) = mpsc::unbounded_channel();
+        let dashboard_channel_name = questions
+            .dashboard
+            .channel
+            .clone()
+            .unwrap_or_else(|| format!("staff-{}", channels.text_suffix));
+        let question_dashboard = Arc::new(QuestionDashboard::new(
+            questions.dashboard,
+            &token,
+            guild_id,
+            dashboard_channel_name,
+            storage.clone(),
+        ));
         Bot {
             token,
             application_id,
             guild_id,
             discord_client,
-            infra_password,
+            staff,
             teams,
             problems,
+            channels,
             create_voice_channels,
             disabled_commands,
-            redeploy_service,
+            command_access,
+            redeploy_service: Arc::from(redeploy_service),
             redeploy_notifiers,
+            redeploy_watcher_config,
+            redeploy_job_sender,
+            redeploy_job_receiver: Some(redeploy_job_receiver),
+            redeploy_limits,
+            redeploy_confirmation,
+            storage,
+            question_dashboard,
+            question_escalation_config: questions.escalation,
+            question_transcripts: questions.transcripts,
+            question_ticket_lock: Mutex::new(()),
             role_cache: RwLock::new(None),
         }
     }
 
-    pub async fn start(self) -> Result<()> {
+    pub async fn start(mut self) -> Result<()> {
         let token = &self.token;
         let application_id = self.application_id;
 
+        if let Err(err) = self.resume_redeploy_jobs().await {
+            tracing::error!(?err, "failed to resume watching redeploy jobs");
+        }
+
+        if let Some(receiver) = self.redeploy_job_receiver.take() {
+            let watcher = RedeployWatcher::new(
+                self.redeploy_watcher_config.clone(),
+                token,
+                self.problems.clone(),
+                self.redeploy_service.clone(),
+                self.redeploy_notifiers.clone(),
+                self.storage.clone(),
+            );
+            tokio::spawn(watcher.run(receiver));
+        }
+
+        tokio::spawn(self.question_dashboard.clone().run());
+
+        let escalation_watcher = QuestionEscalationWatcher::new(
+            self.question_escalation_config.clone(),
+            token,
+            self.guild_id,
+            self.staff.role_name(),
+            self.question_escalation_config
+                .channel
+                .clone()
+                .unwrap_or_else(|| self.text_channel_name("staff")),
+            self.storage.clone(),
+        );
+        tokio::spawn(escalation_watcher.run());
+
         let intents = GatewayIntents::GUILDS
             | GatewayIntents::GUILD_MESSAGES
             | GatewayIntents::GUILD_MESSAGE_REACTIONS
@@ -123,12 +282,19 @@ impl EventHandler for Bot {
         }
     }
 
+    async fn message(&self, _: Context, message: Message) {
+        self.handle_message(&message).await;
+    }
+
     #[tracing::instrument(skip_all)]
-    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+    async fn interaction_create(&self, _: Context, interaction: Interaction) {
         match interaction {
             Interaction::Command(interaction) => {
-                self.handle_application_command(&ctx, &interaction).await
+                self.handle_application_command(&interaction).await
             },
+            Interaction::Autocomplete(interaction) => self.handle_autocomplete(&interaction).await,
+            Interaction::Component(interaction) => self.handle_component(&interaction).await,
+            Interaction::Modal(interaction) => self.handle_modal(&interaction).await,
             _ => {},
         };
     }
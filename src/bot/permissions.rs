@@ -1,9 +1,12 @@
 // This module manages entire permissions for ICTSC Discord channels.
+use std::collections::HashMap;
+
 use anyhow::Result;
 use serenity::model::prelude::*;
 
 use crate::bot::roles;
 use crate::bot::Bot;
+use crate::models::ChannelPolicy;
 
 impl Bot {
     // 全てのユーザに許可してよい権限
@@ -21,6 +24,12 @@ impl Bot {
         Permissions::empty()
     }
 
+    // staff向けコマンドをDiscordのクライアント上でstaff以外に表示しないための権限
+    // staffロールにのみ付与されているギルド単位の権限を指定する。
+    pub fn get_permissions_for_staff_command(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
     // Readonlyなpublic channelに設定される権限
     pub fn get_permissions_for_readonly_channel_member(&self) -> Permissions {
         Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY | Permissions::ADD_REACTIONS
@@ -58,15 +67,35 @@ impl Bot {
             | Permissions::CONNECT // 運営はチームのボイスチャンネルに参加しない。
     }
 
-    // announceチャンネルに設定されるポリシー
-    #[tracing::instrument(skip_all)]
-    pub async fn get_permission_overwrites_for_help_channel(
+    // bot.yamlで定義されたpublic channelに設定されるポリシー
+    pub async fn get_permission_overwrites_for_public_channel(
         &self,
+        policy: ChannelPolicy,
     ) -> Result<Vec<PermissionOverwrite>> {
-        tracing::trace!("get permission overrides for announce channel");
-
         let role_map = self.get_role_map_cached().await?;
+        self.get_permission_overwrites_for_policy(policy, &role_map)
+    }
+
+    // ポリシーに応じた権限の上書きを、ロール名からロールへの対応を用いて求める。
+    pub fn get_permission_overwrites_for_policy(
+        &self,
+        policy: ChannelPolicy,
+        role_map: &HashMap<String, Role>,
+    ) -> Result<Vec<PermissionOverwrite>> {
+        match policy {
+            ChannelPolicy::Help => self.get_permission_overwrites_for_help_channel(role_map),
+            ChannelPolicy::Readonly => {
+                self.get_permission_overwrites_for_announce_channel(role_map)
+            },
+            ChannelPolicy::Writable => self.get_permission_overwrites_for_random_channel(role_map),
+        }
+    }
 
+    // helpチャンネルに設定されるポリシー
+    fn get_permission_overwrites_for_help_channel(
+        &self,
+        role_map: &HashMap<String, Role>,
+    ) -> Result<Vec<PermissionOverwrite>> {
         let mut permissions = Vec::new();
 
         // helpチャンネルにはDiscordの使い方を流すため、全てのユーザに閲覧権限を与える
@@ -83,14 +112,10 @@ impl Bot {
     }
 
     // announceチャンネルに設定されるポリシー
-    #[tracing::instrument(skip_all)]
-    pub async fn get_permission_overwrites_for_announce_channel(
+    fn get_permission_overwrites_for_announce_channel(
         &self,
+        role_map: &HashMap<String, Role>,
     ) -> Result<Vec<PermissionOverwrite>> {
-        tracing::trace!("get permission overrides for announce channel");
-
-        let role_map = self.get_role_map_cached().await?;
-
         let mut permissions = Vec::new();
 
         // announceチャンネルには運営からのメッセージを流すため、チームに参加したユーザに閲覧権限を与える
@@ -109,14 +134,10 @@ impl Bot {
     }
 
     // randomチャンネルに設定されるポリシー
-    #[tracing::instrument(skip_all)]
-    pub async fn get_permission_overwrites_for_random_channel(
+    fn get_permission_overwrites_for_random_channel(
         &self,
+        role_map: &HashMap<String, Role>,
     ) -> Result<Vec<PermissionOverwrite>> {
-        tracing::trace!("get permission overrides for random channel");
-
-        let role_map = self.get_role_map_cached().await?;
-
         let mut permissions = Vec::new();
 
         // randomチャンネルは雑談用に開放するため、チームに参加したユーザに編集権限を与える
@@ -143,7 +164,7 @@ impl Bot {
         tracing::trace!("get permission overrides for random channel");
 
         let staff_roles = self
-            .find_roles_by_name_cached(roles::STAFF_ROLE_NAME)
+            .find_roles_by_name_cached(&self.staff.role_name())
             .await?;
 
         let team_roles = self.find_roles_by_name_cached(&team.role_name).await?;
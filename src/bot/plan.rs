@@ -0,0 +1,371 @@
+// This module computes what `sync` would change without touching the guild.
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::Result;
+use serenity::model::prelude::*;
+
+use crate::bot::channels::ChannelSyncAction;
+use crate::bot::helpers::channels::GuildChannelDefinition;
+use crate::bot::roles::RoleSyncAction;
+use crate::bot::Bot;
+
+// syncコマンドが実行する操作の一覧
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    pub roles: Vec<RoleSyncAction>,
+    pub categories: Vec<ChannelSyncAction>,
+    pub channels: Vec<ChannelSyncAction>,
+
+    // 表示用に、ロールIDとチャンネルIDから名前を引くためのマップ
+    role_names: HashMap<RoleId, String>,
+    channel_names: HashMap<ChannelId, String>,
+
+    // 作成予定のロールやカテゴリに最後に割り当てた仮のID
+    placeholder_id: u64,
+}
+
+impl Bot {
+    // sync_roles, sync_channelsが実行する操作を、ギルドに変更を加えずに列挙する。
+    // 作成予定のロールやカテゴリには仮のIDを割り当て、それらに依存するチャンネルの定義も解決できるようにする。
+    #[tracing::instrument(skip_all)]
+    pub async fn plan_sync(&self) -> Result<SyncPlan> {
+        tracing::info!("plan roles");
+
+        self.update_role_cache().await?;
+        let current_roles = self.get_roles_cached().await?;
+
+        // チャンネルの定義はロールキャッシュからロールIDを解決するため、同期後のロールを一時的にキャッシュに反映する。
+        // 計画が終わったら、失敗した場合も含めて元のロールに戻す。
+        let result = self.plan_sync_with_roles(current_roles.clone()).await;
+        *self.role_cache.write().await = Some(current_roles);
+        result
+    }
+
+    async fn plan_sync_with_roles(&self, current_roles: Vec<Role>) -> Result<SyncPlan> {
+        let mut plan = SyncPlan::default();
+
+        let roles = self.plan_roles(self.define_roles()?).await?;
+        let projected_roles = plan.add_roles(current_roles, roles);
+        *self.role_cache.write().await = Some(projected_roles);
+
+        tracing::info!("plan categories");
+
+        let current_categories = self.get_channels(&[ChannelType::Category]).await?;
+        let categories = self
+            .plan_channels(&[ChannelType::Category], self.define_categories()?)
+            .await?;
+        let category_map = plan.add_categories(current_categories, categories);
+
+        tracing::info!("plan channels");
+
+        let channels = self
+            .plan_channels(
+                &[ChannelType::Text, ChannelType::Voice],
+                self.define_channels(&category_map).await?,
+            )
+            .await?;
+        plan.add_channels(channels);
+
+        Ok(plan)
+    }
+}
+
+impl SyncPlan {
+    // ロールに対する操作を計画に加え、操作を適用した後のロールの一覧を返す。
+    // 作成予定のロールには仮のIDを割り当てる。
+    pub fn add_roles(
+        &mut self,
+        current_roles: Vec<Role>,
+        actions: Vec<RoleSyncAction>,
+    ) -> Vec<Role> {
+        for role in &current_roles {
+            self.role_names.insert(role.id, role.name.clone());
+        }
+
+        let mut projected_roles = current_roles;
+        for action in &actions {
+            match action {
+                RoleSyncAction::Create(definition) => {
+                    let mut role = Role::default();
+                    role.id = RoleId::new(self.next_placeholder_id());
+                    role.name = definition.name.clone();
+                    role.permissions = definition.permissions;
+                    self.role_names.insert(role.id, role.name.clone());
+                    projected_roles.push(role);
+                },
+                RoleSyncAction::Update(role, definition) => {
+                    // 以前の名前のロールは名前が変更されるため、変更後の名前で解決できるようにする。
+                    self.role_names.insert(role.id, definition.name.clone());
+                    for projected_role in projected_roles.iter_mut().filter(|r| r.id == role.id) {
+                        projected_role.name = definition.name.clone();
+                    }
+                },
+                RoleSyncAction::Delete(role) => projected_roles.retain(|r| r.id != role.id),
+            }
+        }
+
+        self.roles.extend(actions);
+        projected_roles
+    }
+
+    // カテゴリに対する操作を計画に加え、操作を適用した後のカテゴリ名からIDへの対応を返す。
+    // 作成予定のカテゴリには仮のIDを割り当てる。
+    pub fn add_categories(
+        &mut self,
+        current_categories: Vec<GuildChannel>,
+        actions: Vec<ChannelSyncAction>,
+    ) -> HashMap<String, ChannelId> {
+        let mut category_map = HashMap::new();
+        for category in &current_categories {
+            self.channel_names
+                .insert(category.id, category.name.clone());
+            category_map.insert(category.name.clone(), category.id);
+        }
+
+        for action in &actions {
+            match action {
+                ChannelSyncAction::Create(definition) => {
+                    let id = ChannelId::new(self.next_placeholder_id());
+                    self.channel_names.insert(id, definition.name.clone());
+                    category_map.insert(definition.name.clone(), id);
+                },
+                ChannelSyncAction::Update(category, definition) => {
+                    // 名前が変更されるカテゴリ配下のチャンネルは、変更後のカテゴリ名で表示する。
+                    self.channel_names
+                        .insert(category.id, definition.name.clone());
+                    category_map.retain(|_, id| *id != category.id);
+                    category_map.insert(definition.name.clone(), category.id);
+                },
+                ChannelSyncAction::Delete(category) => {
+                    category_map.retain(|_, id| *id != category.id);
+                },
+            }
+        }
+
+        self.categories.extend(actions);
+        category_map
+    }
+
+    pub fn add_channels(&mut self, actions: Vec<ChannelSyncAction>) {
+        self.channels.extend(actions);
+    }
+
+    // 仮のIDは実在するsnowflakeと衝突しないよう、小さな値から割り当てる。
+    fn next_placeholder_id(&mut self) -> u64 {
+        self.placeholder_id += 1;
+        self.placeholder_id
+    }
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.roles.is_empty() && self.categories.is_empty() && self.channels.is_empty()
+    }
+
+    fn role_name(&self, id: RoleId) -> String {
+        self.role_names
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    fn category_name(&self, id: Option<ChannelId>) -> String {
+        match id {
+            Some(id) => self
+                .channel_names
+                .get(&id)
+                .cloned()
+                .unwrap_or_else(|| id.to_string()),
+            None => String::from("-"),
+        }
+    }
+
+    fn describe_overwrite(&self, overwrite: &PermissionOverwrite) -> String {
+        let target = match overwrite.kind {
+            PermissionOverwriteType::Role(id) => format!("role \"{}\"", self.role_name(id)),
+            PermissionOverwriteType::Member(id) => format!("member {}", id),
+            _ => String::from("unknown"),
+        };
+        format!(
+            "{}: allow [{}], deny [{}]",
+            target,
+            overwrite.allow.get_permission_names().join(", "),
+            overwrite.deny.get_permission_names().join(", "),
+        )
+    }
+
+    fn fmt_role_action(&self, f: &mut fmt::Formatter<'_>, action: &RoleSyncAction) -> fmt::Result {
+        match action {
+            RoleSyncAction::Create(definition) => {
+                writeln!(f, "  + create role \"{}\"", definition.name)?;
+                writeln!(
+                    f,
+                    "      permissions: [{}]",
+                    definition.permissions.get_permission_names().join(", ")
+                )?;
+            },
+            RoleSyncAction::Update(role, definition) => {
+                writeln!(f, "  ~ update role \"{}\"", role.name)?;
+                if role.name != definition.name {
+                    writeln!(
+                        f,
+                        "      name: \"{}\" -> \"{}\"",
+                        role.name, definition.name
+                    )?;
+                }
+                if role.permissions != definition.permissions {
+                    writeln!(
+                        f,
+                        "      permissions: [{}] -> [{}]",
+                        role.permissions.get_permission_names().join(", "),
+                        definition.permissions.get_permission_names().join(", ")
+                    )?;
+                }
+                if role.colour.0 != definition.colour {
+                    writeln!(
+                        f,
+                        "      colour: #{:06x} -> #{:06x}",
+                        role.colour.0, definition.colour
+                    )?;
+                }
+                if role.hoist != definition.hoist {
+                    writeln!(f, "      hoist: {} -> {}", role.hoist, definition.hoist)?;
+                }
+                if role.mentionable != definition.mentionable {
+                    writeln!(
+                        f,
+                        "      mentionable: {} -> {}",
+                        role.mentionable, definition.mentionable
+                    )?;
+                }
+            },
+            RoleSyncAction::Delete(role) => {
+                writeln!(f, "  - delete role \"{}\"", role.name)?;
+            },
+        }
+        Ok(())
+    }
+
+    fn fmt_channel_action(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        action: &ChannelSyncAction,
+    ) -> fmt::Result {
+        match action {
+            ChannelSyncAction::Create(definition) => {
+                writeln!(
+                    f,
+                    "  + create {} \"{}\" (category: {})",
+                    channel_kind_name(definition.kind),
+                    definition.name,
+                    self.category_name(definition.category)
+                )?;
+                if let Some(topic) = &definition.topic {
+                    writeln!(f, "      topic: {:?}", topic)?;
+                }
+                for overwrite in &definition.permissions {
+                    writeln!(f, "      + {}", self.describe_overwrite(overwrite))?;
+                }
+            },
+            ChannelSyncAction::Update(channel, definition) => {
+                self.fmt_channel_update(f, channel, definition)?;
+            },
+            ChannelSyncAction::Delete(channel) => {
+                writeln!(
+                    f,
+                    "  - delete {} \"{}\" (category: {})",
+                    channel_kind_name(channel.kind),
+                    channel.name,
+                    self.category_name(channel.parent_id)
+                )?;
+            },
+        }
+        Ok(())
+    }
+
+    fn fmt_channel_update(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        channel: &GuildChannel,
+        definition: &GuildChannelDefinition,
+    ) -> fmt::Result {
+        writeln!(
+            f,
+            "  ~ update {} \"{}\" (category: {})",
+            channel_kind_name(channel.kind),
+            channel.name,
+            self.category_name(channel.parent_id)
+        )?;
+        if channel.name != definition.name {
+            writeln!(
+                f,
+                "      name: \"{}\" -> \"{}\"",
+                channel.name, definition.name
+            )?;
+        }
+        if channel.kind != definition.kind {
+            writeln!(
+                f,
+                "      kind: {} -> {}",
+                channel_kind_name(channel.kind),
+                channel_kind_name(definition.kind)
+            )?;
+        }
+        if channel.topic != definition.topic {
+            writeln!(
+                f,
+                "      topic: {:?} -> {:?}",
+                channel.topic, definition.topic
+            )?;
+        }
+        for overwrite in &channel.permission_overwrites {
+            if !definition.permissions.contains(overwrite) {
+                writeln!(f, "      - {}", self.describe_overwrite(overwrite))?;
+            }
+        }
+        for overwrite in &definition.permissions {
+            if !channel.permission_overwrites.contains(overwrite) {
+                writeln!(f, "      + {}", self.describe_overwrite(overwrite))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn channel_kind_name(kind: ChannelType) -> &'static str {
+    match kind {
+        ChannelType::Category => "category",
+        ChannelType::Text => "text channel",
+        ChannelType::Voice => "voice channel",
+        _ => "channel",
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(
+                f,
+                "No changes. The guild is in sync with the configuration."
+            );
+        }
+
+        writeln!(f, "roles:")?;
+        for action in &self.roles {
+            self.fmt_role_action(f, action)?;
+        }
+
+        writeln!(f, "categories:")?;
+        for action in &self.categories {
+            self.fmt_channel_action(f, action)?;
+        }
+
+        writeln!(f, "channels:")?;
+        for action in &self.channels {
+            self.fmt_channel_action(f, action)?;
+        }
+
+        Ok(())
+    }
+}
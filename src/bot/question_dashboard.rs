@@ -0,0 +1,191 @@
+// This module keeps a pinned dashboard of open question threads in the staff channel.
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::all::CreateEmbed;
+use serenity::all::CreateEmbedFooter;
+use serenity::all::CreateMessage;
+use serenity::all::EditMessage;
+use serenity::http::Http;
+use serenity::model::prelude::*;
+use tokio::sync::Mutex;
+
+use crate::config::QuestionDashboardConfiguration;
+use crate::services::questions::list_open_questions;
+use crate::services::questions::OpenQuestion;
+use crate::services::storage::Storage;
+
+// ダッシュボードのEmbedのタイトル。ピン留めされたメッセージから、ダッシュボードを探すのに使う。
+const DASHBOARD_EMBED_TITLE: &str = "未終了の質問スレッド";
+
+// Embedのdescriptionの最大文字数
+const DASHBOARD_DESCRIPTION_MAX_LENGTH: usize = 4096;
+
+// staffチャンネルなど、名前が設定で指定されるテキストチャンネルを探す。
+pub async fn find_text_channel(
+    discord_client: &Http,
+    guild_id: GuildId,
+    name: &str,
+) -> anyhow::Result<GuildChannel> {
+    guild_id
+        .channels(discord_client)
+        .await?
+        .into_values()
+        .find(|channel| channel.kind == ChannelType::Text && channel.name == name)
+        .ok_or_else(|| anyhow::anyhow!("channel {} is not found", name))
+}
+
+pub struct QuestionDashboard {
+    config: QuestionDashboardConfiguration,
+    discord_client: Http,
+    guild_id: GuildId,
+
+    // ダッシュボードを投稿するチャンネル名
+    channel_name: String,
+
+    storage: Arc<dyn Storage + Send + Sync>,
+
+    // 同時に更新してダッシュボードが重複して投稿されないよう、更新を直列化する。
+    refresh_lock: Mutex<()>,
+}
+
+impl QuestionDashboard {
+    pub fn new(
+        config: QuestionDashboardConfiguration,
+        token: &str,
+        guild_id: GuildId,
+        channel_name: String,
+        storage: Arc<dyn Storage + Send + Sync>,
+    ) -> Self {
+        QuestionDashboard {
+            config,
+            discord_client: Http::new(token),
+            guild_id,
+            channel_name,
+            storage,
+            refresh_lock: Mutex::new(()),
+        }
+    }
+
+    // 一定間隔でダッシュボードを更新する。
+    #[tracing::instrument(skip_all)]
+    pub async fn run(self: Arc<Self>) {
+        if !self.config.enabled {
+            return;
+        }
+        tracing::info!("start question dashboard");
+
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.refresh_interval_secs));
+        loop {
+            interval.tick().await;
+            self.refresh().await;
+        }
+    }
+
+    // ダッシュボードを最新の状態に更新する。失敗してもログに記録するのみとする。
+    #[tracing::instrument(skip_all)]
+    pub async fn refresh(&self) {
+        if !self.config.enabled {
+            return;
+        }
+        if let Err(err) = self.try_refresh().await {
+            tracing::error!(?err, "failed to refresh question dashboard");
+        }
+    }
+
+    async fn try_refresh(&self) -> anyhow::Result<()> {
+        let _guard = self.refresh_lock.lock().await;
+
+        let questions = list_open_questions(
+            self.storage.list_questions().await?,
+            self.storage.list_question_tickets().await?,
+        );
+        let embed = self.create_embed(&questions);
+
+        let channel =
+            find_text_channel(&self.discord_client, self.guild_id, &self.channel_name).await?;
+
+        let current_user = self.discord_client.get_current_user().await?;
+        let dashboard = channel
+            .id
+            .pins(&self.discord_client)
+            .await?
+            .into_iter()
+            .find(|message| {
+                message.author.id == current_user.id
+                    && message
+                        .embeds
+                        .iter()
+                        .any(|embed| embed.title.as_deref() == Some(DASHBOARD_EMBED_TITLE))
+            });
+
+        match dashboard {
+            Some(message) => {
+                channel
+                    .id
+                    .edit_message(
+                        &self.discord_client,
+                        message.id,
+                        EditMessage::new().embed(embed),
+                    )
+                    .await?;
+            },
+            None => {
+                tracing::info!("post question dashboard");
+                let message = channel
+                    .id
+                    .send_message(&self.discord_client, CreateMessage::new().embed(embed))
+                    .await?;
+                message.pin(&self.discord_client).await?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn create_embed(&self, questions: &[OpenQuestion]) -> CreateEmbed {
+        let mut description = String::new();
+        for (index, question) in questions.iter().enumerate() {
+            let line = self.format_question(question);
+            let rest = format!("\nほか{}件", questions.len() - index);
+            if description.chars().count() + line.chars().count() + rest.chars().count()
+                > DASHBOARD_DESCRIPTION_MAX_LENGTH
+            {
+                description.push_str(&rest);
+                break;
+            }
+            description.push_str(&line);
+        }
+        if questions.is_empty() {
+            description.push_str("未終了の質問スレッドはありません。");
+        }
+
+        CreateEmbed::new()
+            .title(DASHBOARD_EMBED_TITLE)
+            .description(description)
+            .footer(CreateEmbedFooter::new(format!("{}件", questions.len())))
+            .timestamp(Timestamp::now())
+    }
+
+    // 例: - **team1** [問題ABCの初期条件について](https://discord.com/channels/...) 12分前 / 対応中: @staff
+    fn format_question(&self, question: &OpenQuestion) -> String {
+        let link = format!(
+            "https://discord.com/channels/{}/{}",
+            self.guild_id, question.record.thread_id
+        );
+        let assignee = match question.ticket.assignee_id {
+            Some(assignee_id) => Mention::from(UserId::new(assignee_id)).to_string(),
+            None => String::from("なし"),
+        };
+        format!(
+            "- **{}** [{}]({}) <t:{}:R> / {}: {}\n",
+            question.record.team_id.as_deref().unwrap_or("-"),
+            question.record.title.replace(['[', ']'], ""),
+            link,
+            question.record.created_at.timestamp(),
+            question.ticket.status.label(),
+            assignee,
+        )
+    }
+}
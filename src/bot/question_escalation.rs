@@ -0,0 +1,192 @@
+// This module reminds staff of question threads that have not been answered for a while.
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serenity::all::CreateMessage;
+use serenity::http::Http;
+use serenity::model::prelude::*;
+
+use crate::bot::question_dashboard::find_text_channel;
+use crate::bot::Bot;
+use crate::config::QuestionEscalationConfiguration;
+use crate::services::questions::list_open_questions;
+use crate::services::questions::next_escalation;
+use crate::services::questions::OpenQuestion;
+use crate::services::storage::Storage;
+
+pub struct QuestionEscalationWatcher {
+    config: QuestionEscalationConfiguration,
+    discord_client: Http,
+    guild_id: GuildId,
+    staff_role_name: String,
+
+    // 再通知を投稿するチャンネル名
+    channel_name: String,
+
+    storage: Arc<dyn Storage + Send + Sync>,
+}
+
+impl QuestionEscalationWatcher {
+    pub fn new(
+        config: QuestionEscalationConfiguration,
+        token: &str,
+        guild_id: GuildId,
+        staff_role_name: String,
+        channel_name: String,
+        storage: Arc<dyn Storage + Send + Sync>,
+    ) -> Self {
+        QuestionEscalationWatcher {
+            config,
+            discord_client: Http::new(token),
+            guild_id,
+            staff_role_name,
+            channel_name,
+            storage,
+        }
+    }
+
+    // 一定間隔で質問スレッドを確認し、回答がないまま閾値を超えたものを再通知する。
+    #[tracing::instrument(skip_all)]
+    pub async fn run(self) {
+        if !self.config.enabled || self.config.thresholds_secs.is_empty() {
+            return;
+        }
+        tracing::info!("start question escalation watcher");
+
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.check_interval_secs));
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.check().await {
+                tracing::error!(?err, "failed to check unanswered questions");
+            }
+        }
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        let questions = list_open_questions(
+            self.storage.list_questions().await?,
+            self.storage.list_question_tickets().await?,
+        );
+
+        let now = Utc::now();
+        for question in questions {
+            let level = match next_escalation(&question, &self.config.thresholds_secs, now) {
+                Some(level) => level,
+                None => continue,
+            };
+
+            tracing::info!(
+                thread_id = question.record.thread_id,
+                level,
+                "escalate question"
+            );
+            if let Err(err) = self.escalate(&question).await {
+                tracing::error!(?err, "failed to escalate question");
+                continue;
+            }
+
+            // 再通知している間に担当者などが更新されている可能性があるため、回数のみを更新する。
+            self.storage
+                .record_question_escalation(question.record.thread_id, level)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn escalate(&self, question: &OpenQuestion) -> anyhow::Result<()> {
+        let waiting_since = question
+            .ticket
+            .waiting_since
+            .unwrap_or(question.record.created_at);
+        let waiting_minutes = (Utc::now() - waiting_since).num_minutes();
+
+        let staff_mentions: Vec<_> = self
+            .guild_id
+            .roles(&self.discord_client)
+            .await?
+            .into_values()
+            .filter(|role| role.name == self.staff_role_name)
+            .map(|role| Mention::from(role.id).to_string())
+            .collect();
+
+        let thread_id = ChannelId::new(question.record.thread_id);
+        thread_id
+            .send_message(
+                &self.discord_client,
+                CreateMessage::new().content(format!(
+                    "{} この質問に{}分間staffからの回答がありません。対応をお願いします。",
+                    staff_mentions.join(" "),
+                    waiting_minutes
+                )),
+            )
+            .await?;
+
+        let channel =
+            find_text_channel(&self.discord_client, self.guild_id, &self.channel_name).await?;
+        channel
+            .id
+            .send_message(
+                &self.discord_client,
+                CreateMessage::new().content(format!(
+                    "{} の質問「{}」に{}分間staffからの回答がありません。 {}",
+                    question.record.team_id.as_deref().unwrap_or("-"),
+                    question.record.title,
+                    waiting_minutes,
+                    Mention::from(thread_id)
+                )),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl Bot {
+    // 質問スレッドへの投稿を記録する。
+    // 再通知は、staffの最後の投稿の後にチームが投稿し、回答を待っている質問スレッドのみを対象とする。
+    #[tracing::instrument(skip_all, fields(
+        id = ?message.id,
+        channel_id = ?message.channel_id,
+        author_id = ?message.author.id,
+    ))]
+    pub async fn handle_message(&self, message: &Message) {
+        if message.author.bot || message.guild_id != Some(self.guild_id) {
+            return;
+        }
+
+        if let Err(err) = self.record_question_reply(message).await {
+            tracing::error!(?err, "failed to record question reply");
+        }
+    }
+
+    async fn record_question_reply(&self, message: &Message) -> anyhow::Result<()> {
+        // 質問スレッド以外のメッセージでは、staffかを確認しない。
+        if self
+            .storage
+            .get_question_ticket(message.channel_id.get())
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        let is_staff = match &message.member {
+            Some(member) => self.has_staff_role(&member.roles).await?,
+            None => false,
+        };
+        let thread_id = message.channel_id.get();
+        if is_staff {
+            self.storage
+                .record_question_staff_reply(thread_id, Utc::now())
+                .await?;
+        } else {
+            self.storage
+                .record_question_team_message(thread_id, Utc::now())
+                .await?;
+        }
+        Ok(())
+    }
+}
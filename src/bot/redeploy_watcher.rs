@@ -0,0 +1,226 @@
+// This module watches redeploy jobs in the background and announces their completion.
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serenity::all::CreateMessage;
+use serenity::http::Http;
+use serenity::model::prelude::*;
+use tokio::sync::mpsc;
+
+use crate::bot::Bot;
+use crate::config::RedeployWatcherConfiguration;
+use crate::models::Problem;
+use crate::services::redeploy::wait_for_redeploy_completion;
+use crate::services::redeploy::RedeployCompletion;
+use crate::services::redeploy::RedeployJob;
+use crate::services::redeploy::RedeployNotifier;
+use crate::services::redeploy::RedeployRequester;
+use crate::services::redeploy::RedeployService;
+use crate::services::redeploy::RedeployTarget;
+use crate::services::storage::Storage;
+
+// 監視対象の再展開Job
+#[derive(Debug, Clone)]
+pub struct WatchedRedeployJob {
+    pub job: RedeployJob,
+    pub target: RedeployTarget,
+
+    // 再展開をリクエストしたユーザ
+    pub requester: RedeployRequester,
+
+    // 完了を通知するチャンネル（見つからない場合はチャンネルには通知しない）
+    pub channel_id: Option<ChannelId>,
+
+    pub requested_at: DateTime<Utc>,
+
+    // ストレージ上の再展開リクエストの記録のID
+    pub record_id: Option<i64>,
+}
+
+pub type RedeployJobSender = mpsc::UnboundedSender<WatchedRedeployJob>;
+pub type RedeployJobReceiver = mpsc::UnboundedReceiver<WatchedRedeployJob>;
+
+pub struct RedeployWatcher {
+    config: RedeployWatcherConfiguration,
+    discord_client: Http,
+    problems: Vec<Problem>,
+
+    redeploy_service: Arc<dyn RedeployService + Send + Sync>,
+    redeploy_notifiers: Arc<Vec<Box<dyn RedeployNotifier + Send + Sync>>>,
+    storage: Arc<dyn Storage + Send + Sync>,
+}
+
+impl RedeployWatcher {
+    pub fn new(
+        config: RedeployWatcherConfiguration,
+        token: &str,
+        problems: Vec<Problem>,
+        redeploy_service: Arc<dyn RedeployService + Send + Sync>,
+        redeploy_notifiers: Arc<Vec<Box<dyn RedeployNotifier + Send + Sync>>>,
+        storage: Arc<dyn Storage + Send + Sync>,
+    ) -> Self {
+        RedeployWatcher {
+            config,
+            discord_client: Http::new(token),
+            problems,
+            redeploy_service,
+            redeploy_notifiers,
+            storage,
+        }
+    }
+
+    // 監視対象のJobを受け取り、Jobごとに監視タスクを起動する。
+    // 全てのsenderがdropされるまで終了しない。
+    #[tracing::instrument(skip_all)]
+    pub async fn run(self, mut receiver: RedeployJobReceiver) {
+        tracing::info!("start redeploy watcher");
+
+        let watcher = Arc::new(self);
+        while let Some(job) = receiver.recv().await {
+            let watcher = watcher.clone();
+            tokio::spawn(async move { watcher.watch(job).await });
+        }
+
+        tracing::info!("stop redeploy watcher");
+    }
+
+    #[tracing::instrument(skip_all, fields(job = ?job.job))]
+    async fn watch(&self, job: WatchedRedeployJob) {
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let completion = wait_for_redeploy_completion(
+            self.redeploy_service.as_ref(),
+            &job.target,
+            job.requested_at,
+            self.config.poll_intervals(),
+            timeout,
+        )
+        .await;
+
+        tracing::info!(?completion, "finished to watch redeploy job");
+
+        if let Some(record_id) = job.record_id {
+            let (completed_at, timed_out) = match &completion {
+                RedeployCompletion::Completed { completed_at }
+                | RedeployCompletion::Failed { completed_at } => (Some(*completed_at), false),
+                RedeployCompletion::TimedOut => (None, true),
+            };
+            if let Err(err) = self
+                .storage
+                .record_redeploy_completion(record_id, completed_at, timed_out)
+                .await
+            {
+                tracing::error!(?err, "failed to record redeploy completion");
+            }
+        }
+
+        if let Err(err) = self.announce(&job, &completion).await {
+            tracing::error!(?err, "failed to announce redeploy completion");
+        }
+
+        for notifier in self.redeploy_notifiers.iter() {
+            notifier
+                .notify_completion(&job.target, &job.job, &completion, &job.requester)
+                .await;
+        }
+    }
+
+    async fn announce(
+        &self,
+        job: &WatchedRedeployJob,
+        completion: &RedeployCompletion,
+    ) -> anyhow::Result<()> {
+        let channel_id = match job.channel_id {
+            Some(channel_id) => channel_id,
+            None => {
+                tracing::warn!("team text channel is not found, skip announcement");
+                return Ok(());
+            },
+        };
+
+        let problem_name = self
+            .problems
+            .iter()
+            .find(|problem| problem.code == job.target.problem_id)
+            .map(|problem| problem.name.clone())
+            .unwrap_or_else(|| job.target.problem_id.clone());
+
+        let requester_mention = Mention::from(UserId::new(job.requester.user_id));
+
+        let content = match completion {
+            RedeployCompletion::Completed { .. } => format!(
+                "{} 問題 `{}` の再展開が完了しました。",
+                requester_mention, problem_name
+            ),
+            RedeployCompletion::Failed { .. } => format!(
+                "{} 問題 `{}` の再展開に失敗しました。運営にお問い合わせください。",
+                requester_mention, problem_name
+            ),
+            RedeployCompletion::TimedOut => format!(
+                "{} 問題 `{}` の再展開が{}分以内に完了しませんでした。運営にお問い合わせください。",
+                requester_mention,
+                problem_name,
+                self.config.timeout_secs / 60
+            ),
+        };
+
+        channel_id
+            .send_message(&self.discord_client, CreateMessage::new().content(content))
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl Bot {
+    // ストレージに記録された、完了していない再展開Jobの監視を再開する。
+    // 監視はメモリ上にしかないため、ボットを再起動すると失われてしまう。
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn resume_redeploy_jobs(&self) -> anyhow::Result<()> {
+        let records = self.storage.list_pending_redeploys().await?;
+        tracing::info!(count = records.len(), "resume watching redeploy jobs");
+
+        for (record_id, record) in records {
+            let job_id = match record.job_id {
+                Some(job_id) => job_id,
+                None => continue,
+            };
+
+            let channel_id = match self.teams.iter().find(|team| team.id == record.team_id) {
+                Some(team) => match self.find_team_text_channel(team).await {
+                    Ok(channel) => channel.map(|channel| channel.id),
+                    Err(err) => {
+                        tracing::warn!(?err, "failed to find team text channel");
+                        None
+                    },
+                },
+                None => None,
+            };
+
+            let watched_job = WatchedRedeployJob {
+                job: RedeployJob {
+                    id: job_id,
+                    team_id: record.team_id.clone(),
+                    problem_code: record.problem_code.clone(),
+                },
+                target: RedeployTarget {
+                    team_id: record.team_id,
+                    problem_id: record.problem_code,
+                    triggered_by_staff: record.triggered_by_staff,
+                    reason: None,
+                },
+                requester: RedeployRequester {
+                    user_id: record.user_id,
+                    user_name: record.user_name,
+                },
+                channel_id,
+                requested_at: record.requested_at,
+                record_id: Some(record_id),
+            };
+            self.redeploy_job_sender.send(watched_job)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,14 +1,12 @@
 use anyhow::Result;
 use serenity::model::prelude::*;
 
-use super::helpers::roles::RoleDefinition;
+use crate::bot::helpers::roles::RoleDefinition;
 use crate::bot::helpers::roles::RoleDefinitionBuilder;
+use crate::bot::helpers::HelperResult;
 use crate::bot::Bot;
 
 pub static EVERYONE_ROLE_NAME: &str = "@everyone";
-pub static STAFF_ROLE_NAME: &str = "ICTSC2025 Staff";
-
-static STAFF_ROLE_COLOUR: u32 = 14942278;
 
 impl Bot {
     pub fn is_team_role(&self, role: &Role) -> bool {
@@ -19,6 +17,27 @@ impl Bot {
         }
         false
     }
+
+    pub fn is_staff_role(&self, role: &Role) -> bool {
+        role.name == self.staff.role_name()
+    }
+
+    // メンバーがstaffロールを持っているかを、ロールキャッシュを用いて確認する。
+    pub async fn is_staff_member(&self, member: &Member) -> HelperResult<bool> {
+        self.has_staff_role(&member.roles).await
+    }
+
+    // ロールの中にstaffロールが含まれているかを、ロールキャッシュを用いて確認する。
+    pub async fn has_staff_role(&self, role_ids: &[RoleId]) -> HelperResult<bool> {
+        for role_id in role_ids {
+            if let Some(role) = self.find_roles_by_id_cached(*role_id).await? {
+                if self.is_staff_role(&role) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
 }
 
 impl Bot {
@@ -28,6 +47,22 @@ impl Bot {
 
         self.update_role_cache().await?;
 
+        let definitions = self.define_roles()?;
+        self._sync_roles(definitions).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn delete_roles(&self) -> Result<()> {
+        tracing::info!("delete all roles");
+        self.update_role_cache().await?;
+        self._sync_roles(&[]).await?;
+        Ok(())
+    }
+
+    // 競技用ギルドに存在すべきロールの定義を返す。
+    pub fn define_roles(&self) -> Result<Vec<RoleDefinition>> {
         let mut definitions = Vec::new();
 
         definitions.push(
@@ -40,11 +75,12 @@ impl Bot {
 
         definitions.push(
             RoleDefinitionBuilder::default()
-                .name(STAFF_ROLE_NAME.to_string())
+                .name(self.staff.role_name())
                 .permissions(self.get_permissions_for_staff())
-                .colour(STAFF_ROLE_COLOUR)
+                .colour(self.staff.role_colour)
                 .hoist(true)
                 .mentionable(true)
+                .previous_names(self.staff.previous_names.clone())
                 .build()?,
         );
 
@@ -59,94 +95,138 @@ impl Bot {
             );
         }
 
-        self._sync_roles(definitions).await?;
-
-        Ok(())
+        Ok(definitions)
     }
+}
 
-    #[tracing::instrument(skip_all)]
-    pub async fn delete_roles(&self) -> Result<()> {
-        tracing::info!("delete all roles");
-        self.update_role_cache().await?;
-        self._sync_roles(&[]).await?;
-        Ok(())
-    }
+// ロールの同期時に実行される操作
+#[derive(Clone, Debug)]
+pub enum RoleSyncAction {
+    Create(RoleDefinition),
+    Update(Role, RoleDefinition),
+    Delete(Role),
 }
 
 impl Bot {
     // 与えられたRoleDefinitionのリストを基に、競技用ギルドのロールを同期する。
     // 実行前に、self.update_role_cache()を呼び出して、ロールキャッシュを更新しておく必要がある。
     async fn _sync_roles<T: AsRef<[RoleDefinition]>>(&self, definitions: T) -> Result<()> {
+        let actions = self.plan_roles(definitions).await?;
+
+        for action in &actions {
+            match action {
+                RoleSyncAction::Create(definition) => {
+                    tracing::debug!(?definition, "create role");
+                    if let Err(err) = self.create_role(definition).await {
+                        tracing::warn!(?err, "Failed to create role, skip");
+                    }
+                },
+                RoleSyncAction::Update(role, definition) => {
+                    tracing::debug!(?role, "role is created, but is not synced, update role");
+                    if let Err(err) = self.edit_role(role, definition).await {
+                        tracing::warn!(?err, "Failed to update role, skip");
+                    }
+                },
+                RoleSyncAction::Delete(role) => {
+                    tracing::debug!(?role, "delete role");
+                    if let Err(err) = self.delete_role(role).await {
+                        tracing::warn!(?err, "Failed to delete role, skip");
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    // 与えられたRoleDefinitionのリストと現在のロールを比較し、同期に必要な操作を列挙する。
+    // このメソッド自体はギルドに対して何も変更を加えない。
+    // 実行前に、self.update_role_cache()を呼び出して、ロールキャッシュを更新しておく必要がある。
+    pub async fn plan_roles<T: AsRef<[RoleDefinition]>>(
+        &self,
+        definitions: T,
+    ) -> Result<Vec<RoleSyncAction>> {
         tracing::debug!("fetch current roles");
         let roles = self.get_roles_cached().await?;
 
-        tracing::debug!("sync defined roles");
-        for definition in definitions.as_ref() {
-            let matched_roles: Vec<_> =
-                roles.iter().filter(|r| r.name == definition.name).collect();
+        Ok(plan_role_actions(roles, definitions.as_ref()))
+    }
+}
 
-            if matched_roles.len() == 1 {
-                let role = matched_roles[0];
-                if self.check_role_synced(role, definition) {
-                    tracing::debug!(?role, "target role is created and synced, skip");
-                    continue;
-                }
-                tracing::debug!(?role, "role is created, but is not synced, update role");
-                if let Err(err) = self.edit_role(role, &definition).await {
-                    tracing::warn!(?err, "Failed to update role, skip");
-                }
-                continue;
-            }
+// 与えられたRoleDefinitionのリストと現在のロールを比較し、同期に必要な操作を列挙する。
+pub fn plan_role_actions(roles: Vec<Role>, definitions: &[RoleDefinition]) -> Vec<RoleSyncAction> {
+    let mut actions = Vec::new();
+    let mut renamed_role_ids = Vec::new();
 
-            if matched_roles.len() != 0 {
-                tracing::debug!(
-                    ?matched_roles,
-                    "several matched roles are found, delete them"
-                );
-                for role in matched_roles {
-                    if let Err(err) = self.delete_role(role).await {
-                        tracing::warn!(?err, "Failed to delete role, skip");
-                    }
-                }
-            }
+    tracing::debug!("plan defined roles");
+    for definition in definitions {
+        let matched_roles: Vec<_> = roles.iter().filter(|r| r.name == definition.name).collect();
 
-            tracing::debug!(?definition, "create role");
-            if let Err(err) = self.create_role(&definition).await {
-                tracing::warn!(?err, "Failed to create role, skip");
+        if matched_roles.len() == 1 {
+            let role = matched_roles[0];
+            if check_role_synced(role, definition) {
+                tracing::debug!(?role, "target role is created and synced, skip");
+                continue;
             }
+            actions.push(RoleSyncAction::Update(role.clone(), definition.clone()));
+            continue;
         }
 
-        tracing::debug!("delete not-defined roles");
-        for ref role in roles {
-            let found = definitions
-                .as_ref()
+        if !matched_roles.is_empty() {
+            tracing::debug!(
+                ?matched_roles,
+                "several matched roles are found, delete them"
+            );
+            for role in matched_roles {
+                actions.push(RoleSyncAction::Delete(role.clone()));
+            }
+        } else {
+            // 以前の名前のロールが存在する場合、メンバーへの付与状況を維持するため、名前を変更して使い回す。
+            let previous_roles: Vec<_> = roles
                 .iter()
-                .find(|d| d.name == role.name)
-                .is_some();
-
-            if !found {
-                // @everyoneロールは必ず存在するため、削除対象から外す。
-                // managedなロールは削除できない（integrationによって管理されている）ため、削除対象から外す
-                if role.name == EVERYONE_ROLE_NAME || role.managed {
-                    tracing::debug!(?role, "role can't delete it, skip");
-                    continue;
-                }
+                .filter(|r| definition.previous_names.contains(&r.name))
+                .collect();
 
-                tracing::debug!(?role, "role is not defined, delete it");
-                if let Err(err) = self.delete_role(&role).await {
-                    tracing::warn!(?err, "Failed to delete role, skip");
-                }
+            if previous_roles.len() == 1 {
+                let role = previous_roles[0];
+                tracing::debug!(
+                    ?role,
+                    ?definition,
+                    "role with previous name is found, rename it"
+                );
+                renamed_role_ids.push(role.id);
+                actions.push(RoleSyncAction::Update(role.clone(), definition.clone()));
+                continue;
             }
         }
 
-        Ok(())
+        actions.push(RoleSyncAction::Create(definition.clone()));
     }
 
-    fn check_role_synced(&self, role: &Role, definition: &RoleDefinition) -> bool {
-        role.name == definition.name
-            && role.permissions == definition.permissions
-            && role.colour.0 == definition.colour
-            && role.hoist == definition.hoist
-            && role.mentionable == definition.mentionable
+    tracing::debug!("plan not-defined roles");
+    for role in roles {
+        let found = definitions.iter().any(|d| d.name == role.name);
+
+        if !found && !renamed_role_ids.contains(&role.id) {
+            // @everyoneロールは必ず存在するため、削除対象から外す。
+            // managedなロールは削除できない（integrationによって管理されている）ため、削除対象から外す
+            if role.name == EVERYONE_ROLE_NAME || role.managed {
+                tracing::debug!(?role, "role can't delete it, skip");
+                continue;
+            }
+
+            tracing::debug!(?role, "role is not defined, delete it");
+            actions.push(RoleSyncAction::Delete(role));
+        }
     }
+
+    actions
+}
+
+pub fn check_role_synced(role: &Role, definition: &RoleDefinition) -> bool {
+    role.name == definition.name
+        && role.permissions == definition.permissions
+        && role.colour.0 == definition.colour
+        && role.hoist == definition.hoist
+        && role.mentionable == definition.mentionable
 }
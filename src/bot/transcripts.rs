@@ -0,0 +1,190 @@
+// This module exports the history of question threads as transcripts.
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
+use futures::TryStreamExt;
+use serenity::model::prelude::*;
+
+use crate::bot::helpers::HelperResult;
+use crate::bot::Bot;
+use crate::services::transcripts::Transcript;
+use crate::services::transcripts::TranscriptAttachment;
+use crate::services::transcripts::TranscriptMessage;
+
+// アーカイブ済みのスレッドを一度に取得する件数（Discordの上限）
+const ARCHIVED_THREADS_LIMIT: u64 = 100;
+
+impl Bot {
+    // /archiveで終了する質問スレッドの記録を、設定されたディレクトリに書き出す。
+    // 書き出しに失敗しても、スレッドの終了は続行する。
+    pub async fn export_question_transcript(&self, thread: &GuildChannel) {
+        let directory = match &self.question_transcripts {
+            Some(transcripts) => Path::new(&transcripts.directory),
+            None => return,
+        };
+
+        let result = async {
+            let team_id = self.find_thread_team_id(thread).await?;
+            let transcript = self.fetch_transcript(thread, team_id).await?;
+            transcript.write_to(directory).await
+        }
+        .await;
+        match result {
+            Ok((markdown_path, json_path)) => {
+                tracing::info!(?markdown_path, ?json_path, "exported question transcript")
+            },
+            Err(err) => tracing::error!(?err, "failed to export question transcript"),
+        }
+    }
+
+    // 各チームのカテゴリ以下にある全てのスレッドの記録を書き出す。
+    // 出力先を省略した場合は、questions.transcripts.directoryに書き出す。
+    #[tracing::instrument(skip_all)]
+    pub async fn export_transcripts(&self, output: Option<&str>) -> Result<()> {
+        let directory = match output.or(self
+            .question_transcripts
+            .as_ref()
+            .map(|transcripts| transcripts.directory.as_str()))
+        {
+            Some(directory) => Path::new(directory),
+            None => anyhow::bail!("output directory is not specified"),
+        };
+
+        let channels = self.guild_id.channels(&self.discord_client).await?;
+
+        // チームのカテゴリに属するテキストチャンネルのIDから、チームIDを引けるようにする。
+        let mut team_channels = HashMap::new();
+        for team in &self.teams {
+            let category = channels
+                .values()
+                .find(|channel| channel.kind == ChannelType::Category && channel.name == team.id);
+            let category = match category {
+                Some(category) => category,
+                None => {
+                    tracing::warn!(team = ?team.id, "team category is not found");
+                    continue;
+                },
+            };
+            for channel in channels.values().filter(|channel| {
+                channel.kind == ChannelType::Text && channel.parent_id == Some(category.id)
+            }) {
+                team_channels.insert(channel.id, team.id.clone());
+            }
+        }
+
+        let mut threads = self
+            .guild_id
+            .get_active_threads(&self.discord_client)
+            .await?
+            .threads;
+        for channel_id in team_channels.keys() {
+            let archived = channel_id
+                .get_archived_public_threads(
+                    &self.discord_client,
+                    None,
+                    Some(ARCHIVED_THREADS_LIMIT),
+                )
+                .await?;
+            if archived.has_more {
+                tracing::warn!(
+                    ?channel_id,
+                    "only the latest {} archived threads are exported",
+                    ARCHIVED_THREADS_LIMIT
+                );
+            }
+            threads.extend(archived.threads);
+        }
+
+        // 一部のスレッドの書き出しに失敗しても、残りのスレッドは書き出す。
+        let mut failures = 0;
+        for thread in threads {
+            let team_id = match thread
+                .parent_id
+                .and_then(|parent_id| team_channels.get(&parent_id))
+            {
+                Some(team_id) => team_id.clone(),
+                None => continue,
+            };
+
+            tracing::info!(thread = ?thread.name, team = ?team_id, "export transcript");
+            let result = async {
+                let transcript = self.fetch_transcript(&thread, Some(team_id)).await?;
+                transcript.write_to(directory).await
+            }
+            .await;
+            if let Err(err) = result {
+                tracing::error!(?err, thread = ?thread.name, "failed to export transcript");
+                failures += 1;
+            }
+        }
+
+        if failures > 0 {
+            anyhow::bail!("failed to export {} transcripts", failures);
+        }
+        Ok(())
+    }
+
+    // スレッドの全てのメッセージを取得する。
+    async fn fetch_transcript(
+        &self,
+        thread: &GuildChannel,
+        team_id: Option<String>,
+    ) -> HelperResult<Transcript> {
+        // messages_iterは新しい順にメッセージを返すため、古い順に並べ替える。
+        let mut messages: Vec<_> = thread
+            .id
+            .messages_iter(&self.discord_client)
+            .map_ok(to_transcript_message)
+            .try_collect()
+            .await?;
+        messages.reverse();
+
+        Ok(Transcript {
+            thread_id: thread.id.get(),
+            thread_name: thread.name.clone(),
+            team_id,
+            exported_at: Utc::now(),
+            messages,
+        })
+    }
+
+    // スレッドが作成されたチームチャンネルから、チームIDを求める。
+    async fn find_thread_team_id(&self, thread: &GuildChannel) -> HelperResult<Option<String>> {
+        let parent = match thread.parent_id {
+            Some(parent_id) => self.get_channel(parent_id).await?,
+            None => return Ok(None),
+        };
+        let parent = match parent {
+            Channel::Guild(parent) => parent,
+            _ => return Ok(None),
+        };
+
+        Ok(self
+            .teams
+            .iter()
+            .find(|team| parent.name == self.text_channel_name(&team.id))
+            .map(|team| team.id.clone()))
+    }
+}
+
+fn to_transcript_message(message: Message) -> TranscriptMessage {
+    TranscriptMessage {
+        id: message.id.get(),
+        author_id: message.author.id.get(),
+        author_name: message.author.name,
+        created_at: DateTime::from_timestamp(message.timestamp.unix_timestamp(), 0)
+            .unwrap_or_default(),
+        content: message.content,
+        attachments: message
+            .attachments
+            .into_iter()
+            .map(|attachment| TranscriptAttachment {
+                filename: attachment.filename,
+                url: attachment.url,
+            })
+            .collect(),
+    }
+}
@@ -1,22 +1,46 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::path::Path;
+use std::time::Duration;
 
-use anyhow::Ok;
 use anyhow::Result;
 use serde::Deserialize;
 use validator::Validate;
+use validator::ValidationError;
 
+use crate::models::ChannelPolicy;
+use crate::models::CommandAccess;
 use crate::models::Problem;
+use crate::models::PublicChannel;
 use crate::models::Team;
+use crate::services::redeploy::command::CommandRedeployConfig;
+use crate::services::redeploy::confirmation::validate_ids;
+use crate::services::redeploy::confirmation::RedeployConfirmationError;
+use crate::services::redeploy::filter::RedeployNotifierFilter;
+use crate::services::redeploy::http::HttpRedeployConfig;
+use crate::services::redeploy::notifiers::WebhookRedeployNotifierConfig;
 
 #[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_problem_max_redeploys"))]
+#[validate(schema(function = "validate_redeploy_confirmation_ids"))]
+#[validate(schema(function = "validate_audit_log_filter"))]
 pub struct Configuration {
     pub staff: StaffConfiguration,
     pub discord: DiscordConfiguration,
 
+    #[serde(default)]
+    #[validate(nested)]
+    pub channels: ChannelsConfiguration,
+
     #[serde(default)]
     pub redeploy: RedeployConfiguration,
 
+    #[serde(default)]
+    pub storage: StorageConfiguration,
+
+    #[serde(default)]
+    pub questions: QuestionsConfiguration,
+
     #[serde(default)]
     #[validate(nested)]
     pub teams: Vec<Team>,
@@ -34,9 +58,149 @@ impl Configuration {
     }
 }
 
-#[derive(Debug, Deserialize)]
+// 問題ごとの再展開回数の上限は、problems[].max_redeploysとredeploy.limits.problemsのどちらか一方でのみ指定できる。
+// 両方に指定されていると、どちらが適用されるかが分かりにくいため、設定の読み込み時に拒否する。
+fn validate_problem_max_redeploys(config: &Configuration) -> Result<(), ValidationError> {
+    for problem in &config.problems {
+        let overridden = config
+            .redeploy
+            .limits
+            .problems
+            .get(&problem.code)
+            .is_some_and(|limit| limit.max_redeploys.is_some());
+
+        if problem.max_redeploys.is_some() && overridden {
+            return Err(
+                ValidationError::new("max_redeploys_conflict").with_message(
+                    format!(
+                        "max_redeploys of problem {} is set in both problems and redeploy.limits.problems",
+                        problem.code
+                    )
+                    .into(),
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+// 再展開の確認ボタンのcustom_idには、チームIDと問題コードがそのまま埋め込まれる。
+// 最も長いチームIDと問題コードの組み合わせでも、custom_idの長さの制限に収まる必要がある。
+fn validate_redeploy_confirmation_ids(config: &Configuration) -> Result<(), ValidationError> {
+    let team_id = config.teams.iter().map(|team| team.id.as_str());
+    let problem_code = config.problems.iter().map(|problem| problem.code.as_str());
+
+    for team_id in team_id.clone() {
+        validate_ids(team_id, "").map_err(|err| confirmation_ids_error(team_id, "", err))?;
+    }
+    for problem_code in problem_code.clone() {
+        validate_ids("", problem_code)
+            .map_err(|err| confirmation_ids_error("", problem_code, err))?;
+    }
+
+    let longest_team_id = team_id.max_by_key(|team_id| team_id.len()).unwrap_or("");
+    let longest_problem_code = problem_code
+        .max_by_key(|problem_code| problem_code.len())
+        .unwrap_or("");
+    validate_ids(longest_team_id, longest_problem_code)
+        .map_err(|err| confirmation_ids_error(longest_team_id, longest_problem_code, err))
+}
+
+// 監査ログは全ての再展開を記録するためのものなので、通知するイベントの条件は指定できない。
+fn validate_audit_log_filter(config: &Configuration) -> Result<(), ValidationError> {
+    let filtered = config.redeploy.notifiers.iter().any(|notifier| {
+        matches!(
+            notifier.notifier,
+            RedeployNotifiersConfiguration::AuditLog(_)
+        ) && !notifier.filter.is_empty()
+    });
+    if filtered {
+        return Err(ValidationError::new("audit_log_filter")
+            .with_message("filter cannot be set for the audit_log notifier".into()));
+    }
+    Ok(())
+}
+
+fn confirmation_ids_error(
+    team_id: &str,
+    problem_code: &str,
+    err: RedeployConfirmationError,
+) -> ValidationError {
+    ValidationError::new("redeploy_confirmation_ids").with_message(
+        format!(
+            "team {:?} and problem {:?} cannot be used for redeploy confirmation: {}",
+            team_id, problem_code, err
+        )
+        .into(),
+    )
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct StaffConfiguration {
     pub password: String,
+
+    // コンテスト名と開催年。staffロール名・staffカテゴリ名の既定値に使われる（例: ICTSC2025 Staff）
+    #[serde(default = "default_contest_name")]
+    pub contest_name: String,
+
+    #[serde(default = "default_contest_year")]
+    pub contest_year: u32,
+
+    // staffロール名。省略した場合は「{contest_name}{contest_year} Staff」になる。
+    #[serde(default)]
+    pub role_name: Option<String>,
+
+    #[serde(default = "default_staff_role_colour")]
+    pub role_colour: u32,
+
+    // staffカテゴリ名。省略した場合はstaffロール名と同じになる。
+    #[serde(default)]
+    pub category_name: Option<String>,
+
+    // 以前のシーズンのstaffロール名。
+    // 同期時にこれらの名前のロールが存在すれば、削除・再作成せずに名前を変更する。
+    #[serde(default)]
+    pub previous_names: Vec<String>,
+
+    // 以前のシーズンのstaffカテゴリ名。同期時の扱いはprevious_namesと同じ。
+    // 省略した場合、category_nameも省略していればprevious_namesと同じになる。
+    #[serde(default)]
+    pub previous_category_names: Vec<String>,
+}
+
+impl StaffConfiguration {
+    pub fn role_name(&self) -> String {
+        match &self.role_name {
+            Some(role_name) => role_name.clone(),
+            None => format!("{}{} Staff", self.contest_name, self.contest_year),
+        }
+    }
+
+    pub fn category_name(&self) -> String {
+        match &self.category_name {
+            Some(category_name) => category_name.clone(),
+            None => self.role_name(),
+        }
+    }
+
+    pub fn previous_category_names(&self) -> Vec<String> {
+        match (&self.category_name, self.previous_category_names.is_empty()) {
+            (None, true) => self.previous_names.clone(),
+            _ => self.previous_category_names.clone(),
+        }
+    }
+}
+
+fn default_contest_name() -> String {
+    String::from("ICTSC")
+}
+
+fn default_contest_year() -> u32 {
+    2025
+}
+
+fn default_staff_role_colour() -> u32 {
+    14942278
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,15 +212,88 @@ pub struct DiscordConfiguration {
     #[serde(default)]
     pub disabled_commands: Vec<String>,
 
+    // コマンドごとに、実行できるユーザの範囲を既定値から変更する
+    #[serde(default)]
+    pub command_access: HashMap<String, CommandAccess>,
+
     #[serde(default)]
     pub create_voice_channels: bool,
 }
 
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct ChannelsConfiguration {
+    // チームチャンネル・staffチャンネルの名前に付与される接尾辞（例: team1-text）
+    #[serde(default = "default_text_channel_suffix")]
+    #[validate(length(min = 1))]
+    pub text_suffix: String,
+
+    #[serde(default = "default_voice_channel_suffix")]
+    #[validate(length(min = 1))]
+    pub voice_suffix: String,
+
+    // 全チームに公開されるチャンネル
+    #[serde(default = "default_public_channels")]
+    pub public: Vec<PublicChannel>,
+}
+
+impl Default for ChannelsConfiguration {
+    fn default() -> Self {
+        ChannelsConfiguration {
+            text_suffix: default_text_channel_suffix(),
+            voice_suffix: default_voice_channel_suffix(),
+            public: default_public_channels(),
+        }
+    }
+}
+
+fn default_text_channel_suffix() -> String {
+    String::from("text")
+}
+
+fn default_voice_channel_suffix() -> String {
+    String::from("voice")
+}
+
+fn default_public_channels() -> Vec<PublicChannel> {
+    let public_channel = |name: &str, policy| PublicChannel {
+        name: name.to_string(),
+        policy,
+        topic: None,
+        category: None,
+    };
+
+    vec![
+        // Discordの使い方を案内するread-onlyなチャンネル
+        public_channel("help", ChannelPolicy::Help),
+        // 協議開始等のアナウンスを流すread-onlyなチャンネル
+        public_channel("announce", ChannelPolicy::Readonly),
+        // プラチナスポンサーの方々からのメッセージを流すread-onlyなチャンネル
+        public_channel("platinum-sponsors", ChannelPolicy::Readonly),
+        // 参加者が自由に読み書きできるチャンネル
+        public_channel("random", ChannelPolicy::Writable),
+    ]
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RedeployConfiguration {
     #[serde(flatten)]
     pub service: RedeployServiceConfiguration,
-    pub notifiers: Vec<RedeployNotifiersConfiguration>,
+    pub notifiers: Vec<RedeployNotifierConfiguration>,
+
+    #[serde(default)]
+    pub watcher: RedeployWatcherConfiguration,
+
+    #[serde(default)]
+    pub limits: RedeployLimitsConfiguration,
+
+    // 省略した場合、再展開状況をキャッシュしない。
+    pub cache: Option<RedeployCacheConfiguration>,
+
+    #[serde(default)]
+    pub resilience: RedeployResilienceConfiguration,
+
+    #[serde(default)]
+    pub confirmation: RedeployConfirmationConfiguration,
 }
 
 impl Default for RedeployConfiguration {
@@ -64,14 +301,203 @@ impl Default for RedeployConfiguration {
         RedeployConfiguration {
             service: RedeployServiceConfiguration::Fake,
             notifiers: vec![],
+            watcher: RedeployWatcherConfiguration::default(),
+            limits: RedeployLimitsConfiguration::default(),
+            cache: None,
+            resilience: RedeployResilienceConfiguration::default(),
+            confirmation: RedeployConfirmationConfiguration::default(),
         }
     }
 }
 
+// 再展開の完了を監視し、チームチャンネルに通知する機能の設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedeployWatcherConfiguration {
+    // 再展開状況を取得する間隔の初期値（秒）
+    #[serde(default = "default_redeploy_watcher_initial_interval_secs")]
+    pub initial_interval_secs: u64,
+
+    // 再展開状況を取得する間隔の最大値（秒）。取得するたびに間隔を倍にしていく。
+    #[serde(default = "default_redeploy_watcher_max_interval_secs")]
+    pub max_interval_secs: u64,
+
+    // 再展開の完了を待つ時間（秒）。これを超えるとタイムアウトとして通知する。
+    #[serde(default = "default_redeploy_watcher_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for RedeployWatcherConfiguration {
+    fn default() -> Self {
+        RedeployWatcherConfiguration {
+            initial_interval_secs: default_redeploy_watcher_initial_interval_secs(),
+            max_interval_secs: default_redeploy_watcher_max_interval_secs(),
+            timeout_secs: default_redeploy_watcher_timeout_secs(),
+        }
+    }
+}
+
+impl RedeployWatcherConfiguration {
+    // 再展開状況を取得する間隔を順に返す。初期値から取得するたびに倍にしていき、最大値で頭打ちにする。
+    pub fn poll_intervals(&self) -> impl Iterator<Item = Duration> {
+        let max_interval = Duration::from_secs(self.max_interval_secs);
+        let initial_interval = Duration::from_secs(self.initial_interval_secs).min(max_interval);
+        std::iter::successors(Some(initial_interval), move |interval| {
+            Some((*interval * 2).min(max_interval))
+        })
+    }
+}
+
+fn default_redeploy_watcher_initial_interval_secs() -> u64 {
+    10
+}
+
+fn default_redeploy_watcher_max_interval_secs() -> u64 {
+    60
+}
+
+fn default_redeploy_watcher_timeout_secs() -> u64 {
+    30 * 60
+}
+
+// 再展開状況のキャッシュの設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedeployCacheConfiguration {
+    // 再展開状況をキャッシュする時間（秒）
+    #[serde(default = "default_redeploy_cache_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+fn default_redeploy_cache_ttl_secs() -> u64 {
+    10
+}
+
+// 再展開システムの一時的な障害に備えるための設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedeployResilienceConfiguration {
+    // 再展開状況の取得を試行する回数の上限（初回を含む）。再展開のリクエストは再試行しない。
+    #[serde(default = "default_redeploy_max_attempts")]
+    pub max_attempts: u32,
+
+    // 再試行までの待ち時間の初期値（ミリ秒）。試行するたびに倍にしていく。
+    #[serde(default = "default_redeploy_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+
+    // 再試行までの待ち時間の最大値（ミリ秒）
+    #[serde(default = "default_redeploy_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+
+    // この回数だけ連続して失敗すると、再展開システムへのリクエストを一時的に止める。
+    #[serde(default = "default_redeploy_failure_threshold")]
+    pub failure_threshold: u32,
+
+    // リクエストを止めておく時間（秒）
+    #[serde(default = "default_redeploy_open_secs")]
+    pub open_secs: u64,
+}
+
+impl Default for RedeployResilienceConfiguration {
+    fn default() -> Self {
+        RedeployResilienceConfiguration {
+            max_attempts: default_redeploy_max_attempts(),
+            initial_backoff_ms: default_redeploy_initial_backoff_ms(),
+            max_backoff_ms: default_redeploy_max_backoff_ms(),
+            failure_threshold: default_redeploy_failure_threshold(),
+            open_secs: default_redeploy_open_secs(),
+        }
+    }
+}
+
+fn default_redeploy_max_attempts() -> u32 {
+    3
+}
+
+fn default_redeploy_initial_backoff_ms() -> u64 {
+    200
+}
+
+fn default_redeploy_max_backoff_ms() -> u64 {
+    2000
+}
+
+fn default_redeploy_failure_threshold() -> u32 {
+    5
+}
+
+fn default_redeploy_open_secs() -> u64 {
+    30
+}
+
+// 再展開の確認ボタンの設定。
+// ボタンには署名付きで再展開の内容が埋め込まれるため、botを再起動しても押すことができる。
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedeployConfirmationConfiguration {
+    // 署名に使う鍵。省略した場合はDiscordのトークンを使う。
+    pub secret: Option<String>,
+
+    // 確認ボタンを押せる期間（秒）
+    #[serde(default = "default_redeploy_confirmation_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+impl Default for RedeployConfirmationConfiguration {
+    fn default() -> Self {
+        RedeployConfirmationConfiguration {
+            secret: None,
+            ttl_secs: default_redeploy_confirmation_ttl_secs(),
+        }
+    }
+}
+
+fn default_redeploy_confirmation_ttl_secs() -> u64 {
+    600
+}
+
+// チームごとの再展開の回数制限の設定。staffによる代理実行には適用されない。
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RedeployLimitsConfiguration {
+    // 再展開が完了してから、同じ問題を再度再展開できるようになるまでの時間（秒）
+    #[serde(default)]
+    pub cooldown_secs: u64,
+
+    // 1チームが1問題あたりに再展開できる回数の上限。省略した場合は無制限
+    pub max_redeploys: Option<u32>,
+
+    // 問題コードごとに上書きする設定
+    #[serde(default)]
+    pub problems: HashMap<String, RedeployLimitConfiguration>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RedeployLimitConfiguration {
+    pub cooldown_secs: Option<u64>,
+    pub max_redeploys: Option<u32>,
+}
+
+impl RedeployLimitsConfiguration {
+    pub fn cooldown_secs(&self, problem_code: &str) -> u64 {
+        self.problems
+            .get(problem_code)
+            .and_then(|limit| limit.cooldown_secs)
+            .unwrap_or(self.cooldown_secs)
+    }
+
+    // 問題ごとの上限（problemsの各問題またはredeploy.limits.problems）を、redeploy.limitsより優先する。
+    // 問題ごとの上限が両方に指定されている設定は、読み込み時に拒否される。
+    pub fn max_redeploys(&self, problem: &Problem) -> Option<u32> {
+        self.problems
+            .get(&problem.code)
+            .and_then(|limit| limit.max_redeploys)
+            .or(problem.max_redeploys)
+            .or(self.max_redeploys)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RedeployServiceConfiguration {
     Rstate(RstateRedeployServiceConfiguration),
+    Http(Box<HttpRedeployConfig>),
+    Command(CommandRedeployConfig),
     Fake,
 }
 
@@ -80,15 +506,179 @@ pub struct RstateRedeployServiceConfiguration {
     pub baseurl: String,
     pub username: String,
     pub password: String,
+
+    // 再展開状況を同時に取得する問題の数の上限
+    #[serde(default = "default_rstate_max_concurrency")]
+    pub max_concurrency: usize,
+
+    // 再展開状況を取得するリクエストのタイムアウト（秒）
+    #[serde(default = "default_rstate_status_timeout_secs")]
+    pub status_timeout_secs: u64,
+}
+
+fn default_rstate_max_concurrency() -> usize {
+    8
+}
+
+fn default_rstate_status_timeout_secs() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedeployNotifierConfiguration {
+    #[serde(flatten)]
+    pub notifier: RedeployNotifiersConfiguration,
+
+    // 省略した場合、全てのイベントを通知する。
+    #[serde(default)]
+    pub filter: RedeployNotifierFilter,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RedeployNotifiersConfiguration {
     Discord(DiscordRedeployNotifierConfiguration),
+    Slack(SlackRedeployNotifierConfiguration),
+    Webhook(WebhookRedeployNotifierConfig),
+    Log,
+    AuditLog(AuditLogRedeployNotifierConfiguration),
 }
 
 #[derive(Debug, Deserialize)]
 pub struct DiscordRedeployNotifierConfiguration {
     pub webhook_url: String,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct SlackRedeployNotifierConfiguration {
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditLogRedeployNotifierConfiguration {
+    // 監査ログの出力先。ファイルが存在する場合は追記する。
+    pub path: String,
+}
+
+// /askで作成される質問スレッドに関する設定
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QuestionsConfiguration {
+    #[serde(default)]
+    pub dashboard: QuestionDashboardConfiguration,
+
+    #[serde(default)]
+    pub escalation: QuestionEscalationConfiguration,
+
+    // 省略した場合、/archiveで質問スレッドの記録を書き出さない。
+    pub transcripts: Option<QuestionTranscriptConfiguration>,
+}
+
+// 質問スレッドの記録を書き出す機能の設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestionTranscriptConfiguration {
+    // 記録をMarkdownとJSONで書き出すディレクトリ。存在しない場合は作成する。
+    pub directory: String,
+}
+
+// 未終了の質問スレッドを一覧するダッシュボードの設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestionDashboardConfiguration {
+    #[serde(default = "default_question_dashboard_enabled")]
+    pub enabled: bool,
+
+    // ダッシュボードを投稿するチャンネル名。省略した場合はstaffのテキストチャンネル（例: staff-text）
+    pub channel: Option<String>,
+
+    // ダッシュボードを定期的に更新する間隔（秒）
+    #[serde(default = "default_question_dashboard_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+impl Default for QuestionDashboardConfiguration {
+    fn default() -> Self {
+        QuestionDashboardConfiguration {
+            enabled: default_question_dashboard_enabled(),
+            channel: None,
+            refresh_interval_secs: default_question_dashboard_refresh_interval_secs(),
+        }
+    }
+}
+
+fn default_question_dashboard_enabled() -> bool {
+    true
+}
+
+fn default_question_dashboard_refresh_interval_secs() -> u64 {
+    60
+}
+
+// staffの回答がない質問スレッドを、staffに再通知する機能の設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuestionEscalationConfiguration {
+    #[serde(default = "default_question_escalation_enabled")]
+    pub enabled: bool,
+
+    // チームが回答を待ち始めてから（質問の作成時、またはstaffの投稿の後にチームが投稿した時）、
+    // staffの回答がないまま経過した時間（秒）。
+    // それぞれの時間を超えるたびに、スレッドとstaffチャンネルで再通知する。
+    #[serde(default = "default_question_escalation_thresholds_secs")]
+    pub thresholds_secs: Vec<u64>,
+
+    // 再通知を投稿するチャンネル名。省略した場合はstaffのテキストチャンネル（例: staff-text）
+    pub channel: Option<String>,
+
+    // 質問スレッドを確認する間隔（秒）
+    #[serde(default = "default_question_escalation_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+impl Default for QuestionEscalationConfiguration {
+    fn default() -> Self {
+        QuestionEscalationConfiguration {
+            enabled: default_question_escalation_enabled(),
+            thresholds_secs: default_question_escalation_thresholds_secs(),
+            channel: None,
+            check_interval_secs: default_question_escalation_check_interval_secs(),
+        }
+    }
+}
+
+fn default_question_escalation_enabled() -> bool {
+    true
+}
+
+fn default_question_escalation_thresholds_secs() -> Vec<u64> {
+    vec![600, 1200]
+}
+
+fn default_question_escalation_check_interval_secs() -> u64 {
+    60
+}
+
+// 操作履歴の保存先
+// 質問スレッドの再通知や再展開の回数制限は保存された履歴に依存するため、省略した場合はSQLiteに保存する。
+// memoryはbotを再起動すると履歴が失われるため、動作確認のために明示的に指定した場合のみ使う。
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageConfiguration {
+    Sqlite(SqliteStorageConfiguration),
+    Memory,
+}
+
+impl Default for StorageConfiguration {
+    fn default() -> Self {
+        StorageConfiguration::Sqlite(SqliteStorageConfiguration {
+            path: default_sqlite_path(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SqliteStorageConfiguration {
+    #[serde(default = "default_sqlite_path")]
+    pub path: String,
+}
+
+fn default_sqlite_path() -> String {
+    "bot.sqlite3".to_string()
+}
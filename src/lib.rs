@@ -4,3 +4,4 @@ pub mod models;
 pub mod services;
 
 pub use bot::Bot;
+pub use bot::BotSettings;
@@ -1,16 +1,40 @@
+use std::fs::File;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
 use anyhow::Result;
 use bot::config::Configuration;
 use bot::config::RedeployNotifiersConfiguration;
 use bot::config::RedeployServiceConfiguration;
+use bot::config::StorageConfiguration;
+use bot::services::redeploy::cache::CachedRedeployService;
+use bot::services::redeploy::command::CommandRedeployService;
+use bot::services::redeploy::filter::FilteredRedeployNotifier;
+use bot::services::redeploy::http::HttpRedeployService;
+use bot::services::redeploy::notifiers::AuditLogRedeployNotifier;
+use bot::services::redeploy::notifiers::LogRedeployNotifier;
+use bot::services::redeploy::notifiers::SlackRedeployNotifier;
+use bot::services::redeploy::notifiers::WebhookRedeployNotifier;
+use bot::services::redeploy::resilient::ResilientRedeployConfig;
+use bot::services::redeploy::resilient::ResilientRedeployService;
 use bot::services::redeploy::DiscordRedeployNotifier;
 use bot::services::redeploy::FakeRedeployService;
 use bot::services::redeploy::RState;
 use bot::services::redeploy::RStateConfig;
 use bot::services::redeploy::RedeployNotifier;
 use bot::services::redeploy::RedeployService;
+use bot::services::storage;
+use bot::services::storage::InMemoryStorage;
+use bot::services::storage::SqliteStorage;
+use bot::services::storage::Storage;
 use bot::Bot;
+use bot::BotSettings;
 use clap::Parser;
 use clap::Subcommand;
+use clap::ValueEnum;
+use serde::Serialize;
 
 #[derive(Debug, Parser)]
 #[clap(author, version)]
@@ -25,23 +49,98 @@ struct Arguments {
 #[derive(Debug, Subcommand)]
 enum Commands {
     Start,
-    Sync,
+    Sync {
+        // ギルドに変更を加えず、実行される操作の一覧を表示する
+        #[clap(long)]
+        dry_run: bool,
+    },
+    Plan,
+    // 保存されている操作履歴を書き出す
+    ExportHistory {
+        #[clap(value_enum)]
+        kind: HistoryKind,
+
+        #[clap(long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+
+        // 書き出し先のファイル。省略した場合は標準出力に書き出す。
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+    // 各チームのカテゴリ以下にある全てのスレッドの記録を、MarkdownとJSONで書き出す
+    ExportTranscripts {
+        // 書き出し先のディレクトリ。省略した場合はquestions.transcripts.directoryに書き出す。
+        #[clap(short, long)]
+        output: Option<String>,
+    },
     DeleteRoles,
     DeleteChannels,
     DeleteCommands,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum HistoryKind {
+    Redeploys,
+    Questions,
+    Joins,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+fn build_storage(config: &Configuration) -> Result<Arc<dyn Storage + Send + Sync>> {
+    Ok(match &config.storage {
+        StorageConfiguration::Sqlite(sqlite) => Arc::new(SqliteStorage::open(&sqlite.path)?),
+        StorageConfiguration::Memory => Arc::new(InMemoryStorage::default()),
+    })
+}
+
 fn build_redeploy_service(
     config: &Configuration,
+    notifiers: Arc<Vec<Box<dyn RedeployNotifier + Send + Sync>>>,
 ) -> Result<Box<dyn RedeployService + Send + Sync>> {
-    Ok(match &config.redeploy.service {
+    let service: Box<dyn RedeployService + Send + Sync> = match &config.redeploy.service {
         RedeployServiceConfiguration::Rstate(rstate) => Box::new(RState::new(RStateConfig {
             baseurl: rstate.baseurl.clone(),
             username: rstate.username.clone(),
             password: rstate.password.clone(),
             problems: config.problems.clone(),
+            max_concurrency: rstate.max_concurrency,
+            timeout: Duration::from_secs(rstate.status_timeout_secs),
         })?),
+        RedeployServiceConfiguration::Http(http) => Box::new(HttpRedeployService::new(
+            http.as_ref().clone(),
+            config.problems.clone(),
+        )?),
+        RedeployServiceConfiguration::Command(command) => Box::new(CommandRedeployService::new(
+            command.clone(),
+            config.problems.clone(),
+        )?),
         RedeployServiceConfiguration::Fake => Box::new(FakeRedeployService),
+    };
+
+    let resilience = &config.redeploy.resilience;
+    let service = Box::new(ResilientRedeployService::new(
+        service,
+        ResilientRedeployConfig {
+            max_attempts: resilience.max_attempts,
+            initial_backoff: Duration::from_millis(resilience.initial_backoff_ms),
+            max_backoff: Duration::from_millis(resilience.max_backoff_ms),
+            failure_threshold: resilience.failure_threshold,
+            open_duration: Duration::from_secs(resilience.open_secs),
+        },
+        notifiers,
+    ));
+
+    Ok(match &config.redeploy.cache {
+        Some(cache) => Box::new(CachedRedeployService::new(
+            service,
+            Duration::from_secs(cache.ttl_secs),
+        )),
+        None => service,
     })
 }
 
@@ -50,14 +149,27 @@ async fn build_redeploy_notifiers(
 ) -> Result<Vec<Box<dyn RedeployNotifier + Send + Sync>>> {
     let mut notifiers: Vec<Box<dyn RedeployNotifier + Send + Sync>> = Vec::new();
     for notifier_config in &config.redeploy.notifiers {
-        match notifier_config {
-            RedeployNotifiersConfiguration::Discord(discord) => {
-                notifiers.push(Box::new(
-                    DiscordRedeployNotifier::new(&config.discord.token, &discord.webhook_url)
-                        .await?,
-                ));
+        let notifier: Box<dyn RedeployNotifier + Send + Sync> = match &notifier_config.notifier {
+            RedeployNotifiersConfiguration::Discord(discord) => Box::new(
+                DiscordRedeployNotifier::new(&config.discord.token, &discord.webhook_url).await?,
+            ),
+            RedeployNotifiersConfiguration::Slack(slack) => {
+                Box::new(SlackRedeployNotifier::new(&slack.webhook_url)?)
             },
-        }
+            RedeployNotifiersConfiguration::Webhook(webhook) => {
+                Box::new(WebhookRedeployNotifier::new(webhook.clone())?)
+            },
+            RedeployNotifiersConfiguration::Log => Box::new(LogRedeployNotifier),
+            // 監査ログは全ての再展開を記録する必要があるため、条件を適用しない。
+            RedeployNotifiersConfiguration::AuditLog(audit_log) => {
+                notifiers.push(Box::new(AuditLogRedeployNotifier::new(&audit_log.path)));
+                continue;
+            },
+        };
+        notifiers.push(FilteredRedeployNotifier::wrap(
+            notifier,
+            notifier_config.filter.clone(),
+        ));
     }
     Ok(notifiers)
 }
@@ -68,6 +180,58 @@ async fn sync(bot: &Bot) -> Result<()> {
     Ok(())
 }
 
+async fn export_history(
+    storage: &(dyn Storage + Send + Sync),
+    kind: HistoryKind,
+    format: ExportFormat,
+    output: Option<String>,
+) -> Result<()> {
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    match kind {
+        HistoryKind::Redeploys => write_records(writer, format, &storage.list_redeploys().await?),
+        HistoryKind::Questions => write_records(writer, format, &storage.list_questions().await?),
+        HistoryKind::Joins => write_records(writer, format, &storage.list_joins().await?),
+    }
+}
+
+fn write_records<W: Write, T: Serialize>(
+    writer: W,
+    format: ExportFormat,
+    records: &[T],
+) -> Result<()> {
+    match format {
+        ExportFormat::Csv => storage::write_csv(writer, records),
+        ExportFormat::Json => storage::write_json(writer, records),
+    }
+}
+
+async fn plan(bot: &Bot) -> Result<()> {
+    let plan = bot.plan_sync().await?;
+    print!("{}", plan);
+    Ok(())
+}
+
+async fn build_bot(config: Configuration, storage: Arc<dyn Storage + Send + Sync>) -> Result<Bot> {
+    let redeploy_notifiers = Arc::new(
+        build_redeploy_notifiers(&config)
+            .await
+            .context("couldn't instantiate redeploy notifiers")?,
+    );
+    let redeploy_service = build_redeploy_service(&config, redeploy_notifiers.clone())
+        .context("couldn't instantiate redeploy service")?;
+
+    Ok(Bot::new(
+        BotSettings::from(config),
+        redeploy_service,
+        redeploy_notifiers,
+        storage,
+    ))
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
@@ -81,42 +245,39 @@ async fn main() {
         },
     };
 
-    let redeploy_service = match build_redeploy_service(&config) {
-        Ok(service) => service,
-        Err(err) => {
-            tracing::error!(?err, "couldn't instantiate redeploy service");
-            return;
-        },
-    };
-
-    let redeploy_notifiers = match build_redeploy_notifiers(&config).await {
-        Ok(notifiers) => notifiers,
+    let storage = match build_storage(&config) {
+        Ok(storage) => storage,
         Err(err) => {
-            tracing::error!("couldn't instantiate DiscordRedeployNotifier: {:?}", err);
+            tracing::error!(?err, "couldn't open storage");
             return;
         },
     };
 
-    let bot = Bot::new(
-        config.discord.token,
-        config.discord.application_id,
-        config.discord.guild_id,
-        config.staff.password,
-        config.teams,
-        config.problems,
-        config.discord.create_voice_channels,
-        config.discord.disabled_commands,
-        redeploy_service,
-        redeploy_notifiers,
-    );
-
-    let result = match args.command {
-        Commands::Start => bot.start().await,
-        Commands::Sync => sync(&bot).await,
-        Commands::DeleteRoles => bot.delete_roles().await,
-        Commands::DeleteChannels => bot.delete_channels().await,
-        Commands::DeleteCommands => bot.delete_commands().await,
-    };
+    let result = async {
+        match args.command {
+            Commands::Start => build_bot(config, storage).await?.start().await,
+            Commands::Sync { dry_run: false } => sync(&build_bot(config, storage).await?).await,
+            Commands::Sync { dry_run: true } | Commands::Plan => {
+                plan(&build_bot(config, storage).await?).await
+            },
+            Commands::DeleteRoles => build_bot(config, storage).await?.delete_roles().await,
+            Commands::DeleteChannels => build_bot(config, storage).await?.delete_channels().await,
+            Commands::DeleteCommands => build_bot(config, storage).await?.delete_commands().await,
+            Commands::ExportTranscripts { output } => {
+                build_bot(config, storage)
+                    .await?
+                    .export_transcripts(output.as_deref())
+                    .await
+            },
+            // 履歴の書き出しはDiscordに接続する必要がないため、Botを作成しない。
+            Commands::ExportHistory {
+                kind,
+                format,
+                output,
+            } => export_history(storage.as_ref(), kind, format, output).await,
+        }
+    }
+    .await;
 
     if let Err(reason) = result {
         tracing::error!("finished unsuccessfully: {:?}", reason);
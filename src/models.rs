@@ -1,5 +1,6 @@
 use serde::Deserialize;
-use validator::{Validate, ValidationError};
+use validator::Validate;
+use validator::ValidationError;
 
 #[derive(Debug, Clone, Deserialize, Validate)]
 pub struct Team {
@@ -22,8 +23,187 @@ fn validate_team_id(id: &str) -> Result<(), ValidationError> {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct Problem {
     pub code: String,
     pub name: String,
+
+    // 再展開できる問題か？ VMを持たない問題などではfalseにする。省略した場合はtrue
+    pub redeployable: Option<bool>,
+
+    // 再展開の確認時に表示する注意事項
+    pub confirmation_message: Option<String>,
+
+    // 再展開システム上での問題のID。省略した場合は問題コードを小文字にしたものを使う。
+    pub backend_id: Option<String>,
+
+    // 1チームがこの問題を再展開できる回数の上限。省略した場合はredeploy.limits.max_redeploysに従う。
+    // redeploy.limits.problemsで同じ問題の上限を指定している場合は、設定の読み込みに失敗する。
+    pub max_redeploys: Option<u32>,
+
+    // 問題を担当するstaffのDiscordのユーザID。
+    // /askでこの問題が指定された場合、staffロールの代わりにメンションする。
+    #[serde(default)]
+    pub owners: Vec<u64>,
+}
+
+impl Problem {
+    pub fn is_redeployable(&self) -> bool {
+        self.redeployable.unwrap_or(true)
+    }
+
+    pub fn backend_id(&self) -> String {
+        self.backend_id
+            .clone()
+            .unwrap_or_else(|| self.code.to_lowercase())
+    }
+
+    // 検索語に対する一致度を返す。値が小さいほどよく一致し、一致しない場合はNoneを返す。
+    // 問題コード・問題名の両方を、全角・半角や大文字・小文字を区別せずに比較する。
+    pub fn match_score(&self, query: &str) -> Option<u32> {
+        let query = fold_for_search(query);
+        if query.is_empty() {
+            return Some(0);
+        }
+
+        let code = fold_for_search(&self.code);
+        let name = fold_for_search(&self.name);
+
+        if code == query {
+            Some(0)
+        } else if code.starts_with(&query) {
+            Some(1)
+        } else if name.starts_with(&query) {
+            Some(2)
+        } else if code.contains(&query) || name.contains(&query) {
+            Some(3)
+        } else if is_subsequence(&query, &code) || is_subsequence(&query, &name) {
+            Some(4)
+        } else {
+            None
+        }
+    }
+}
+
+// 検索語に一致する問題を、一致度の高い順に返す。
+pub fn search_problems<'a>(problems: &'a [Problem], query: &str) -> Vec<&'a Problem> {
+    let mut matches: Vec<_> = problems
+        .iter()
+        .filter_map(|problem| problem.match_score(query).map(|score| (score, problem)))
+        .collect();
+    matches.sort_by(|(a, a_problem), (b, b_problem)| {
+        a.cmp(b).then_with(|| a_problem.code.cmp(&b_problem.code))
+    });
+    matches.into_iter().map(|(_, problem)| problem).collect()
+}
+
+// チームIDまたはロール名に検索語を含むチームを返す。
+// 検索語とチームの両方を、全角・半角や大文字・小文字を区別せずに比較する。
+pub fn search_teams<'a>(teams: &'a [Team], query: &str) -> Vec<&'a Team> {
+    let query = fold_for_search(query);
+    teams
+        .iter()
+        .filter(|team| {
+            fold_for_search(&team.id).contains(&query)
+                || fold_for_search(&team.role_name).contains(&query)
+        })
+        .collect()
+}
+
+// チームIDに対応するチームを返す。入力の全角・半角や大文字・小文字は区別しない。
+pub fn find_team<'a>(teams: &'a [Team], team_id: &str) -> Option<&'a Team> {
+    let team_id = fold_for_search(team_id);
+    teams
+        .iter()
+        .find(|team| fold_for_search(&team.id) == team_id)
+}
+
+// 問題コードの入力を正規化する。
+// 全角英数字・記号を半角にし、空白を取り除いた上で大文字にする。
+pub fn normalize_problem_code(input: &str) -> String {
+    input
+        .chars()
+        .map(to_half_width)
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// 全角英数字・記号（U+FF01〜U+FF5E）と全角空白を半角に変換する。
+fn to_half_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+fn fold_for_search(input: &str) -> String {
+    input
+        .chars()
+        .map(to_half_width)
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// queryの文字がこの順番でtargetに含まれるか？
+fn is_subsequence(query: &str, target: &str) -> bool {
+    let mut target = target.chars();
+    query.chars().all(|c| target.any(|t| t == c))
+}
+
+// public channelに設定されるアクセスポリシー
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelPolicy {
+    // チームに参加したユーザが閲覧のみできる（announce等）
+    Readonly,
+    // チームに参加したユーザが読み書きできる（random等）
+    Writable,
+    // チーム参加前のユーザを含む全てのユーザが閲覧のみできる（help等）
+    Help,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublicChannel {
+    pub name: String,
+    pub policy: ChannelPolicy,
+    #[serde(default)]
+    pub topic: Option<String>,
+    // チャンネルを配置するカテゴリ名。指定しない場合はカテゴリに属さない。
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+// コマンドを実行できるユーザの範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandAccess {
+    // 全てのユーザ
+    Everyone,
+    // いずれかのチームに参加しているユーザとstaff
+    TeamMember,
+    // staffのみ
+    Staff,
+}
+
+impl CommandAccess {
+    // コマンドの呼び出し元がstaffか、いずれかのチームに参加しているかを基に、実行できるかを判定する。
+    pub fn allows(self, is_staff: bool, is_team_member: bool) -> bool {
+        match self {
+            CommandAccess::Everyone => true,
+            CommandAccess::TeamMember => is_staff || is_team_member,
+            CommandAccess::Staff => is_staff,
+        }
+    }
+}
+
+// コマンドごとの既定のアクセスポリシー
+// bot.yamlのdiscord.command_accessで上書きできる。
+pub fn default_command_access(name: &str) -> CommandAccess {
+    match name {
+        "archive" | "ask" | "redeploy" => CommandAccess::TeamMember,
+        _ => CommandAccess::Everyone,
+    }
 }
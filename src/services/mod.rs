@@ -1 +1,4 @@
+pub mod questions;
 pub mod redeploy;
+pub mod storage;
+pub mod transcripts;
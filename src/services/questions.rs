@@ -0,0 +1,58 @@
+// This module aggregates question threads created by /ask and their tickets.
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use chrono::Utc;
+
+use crate::services::storage::QuestionRecord;
+use crate::services::storage::QuestionStatus;
+use crate::services::storage::QuestionTicket;
+
+// 終了していない質問スレッド
+#[derive(Debug, Clone)]
+pub struct OpenQuestion {
+    pub record: QuestionRecord,
+    pub ticket: QuestionTicket,
+}
+
+// botが作成した質問スレッドのうち、終了していないものを作成日時の古い順に返す。
+// チケットが保存されていないスレッドは、状態が分からないため含めない。
+pub fn list_open_questions(
+    records: Vec<QuestionRecord>,
+    tickets: Vec<QuestionTicket>,
+) -> Vec<OpenQuestion> {
+    let mut tickets: HashMap<_, _> = tickets
+        .into_iter()
+        .map(|ticket| (ticket.thread_id, ticket))
+        .collect();
+
+    let mut questions: Vec<_> = records
+        .into_iter()
+        .filter_map(|record| {
+            let ticket = tickets.remove(&record.thread_id)?;
+            (ticket.status != QuestionStatus::Closed).then_some(OpenQuestion { record, ticket })
+        })
+        .collect();
+    questions.sort_by_key(|question| question.record.created_at);
+    questions
+}
+
+// チームがstaffの回答を待ち始めてから、まだ再通知していない閾値を超えていれば、新たに到達した段階（1始まり）を返す。
+// 回答済みの質問や、staffの最後の投稿の後にチームが投稿していない質問は再通知しない。
+pub fn next_escalation(
+    question: &OpenQuestion,
+    thresholds_secs: &[u64],
+    now: DateTime<Utc>,
+) -> Option<u32> {
+    if question.ticket.status == QuestionStatus::Answered {
+        return None;
+    }
+
+    let waiting_secs = (now - question.ticket.waiting_since?).num_seconds();
+    let level = thresholds_secs
+        .iter()
+        .filter(|threshold| waiting_secs >= **threshold as i64)
+        .count() as u32;
+
+    (level > question.ticket.escalation_count).then_some(level)
+}
@@ -0,0 +1,109 @@
+// This module provides a decorator that caches redeploy statuses in front of any backend.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::services::redeploy::RedeployJob;
+use crate::services::redeploy::RedeployResult;
+use crate::services::redeploy::RedeployService;
+use crate::services::redeploy::RedeployStatusList;
+use crate::services::redeploy::RedeployTarget;
+
+#[derive(Default)]
+struct TeamCacheEntry {
+    // 同じチームへの同時リクエストを1つにまとめるためのロック
+    fetch_lock: Arc<tokio::sync::Mutex<()>>,
+
+    statuses: Option<(Instant, RedeployStatusList)>,
+
+    // キャッシュを破棄するたびに増やす世代番号。
+    // 破棄する前に始まったリクエストの結果をキャッシュしないようにするために使う。
+    generation: u64,
+}
+
+// チームごとの再展開状況を一定時間キャッシュするRedeployService
+pub struct CachedRedeployService {
+    inner: Box<dyn RedeployService + Send + Sync>,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, TeamCacheEntry>>,
+}
+
+impl CachedRedeployService {
+    pub fn new(inner: Box<dyn RedeployService + Send + Sync>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lookup(&self, team_id: &str) -> Option<RedeployStatusList> {
+        let entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        entries
+            .get(team_id)
+            .and_then(|entry| entry.statuses.as_ref())
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, statuses)| statuses.clone())
+    }
+
+    fn fetch_lock_and_generation(&self, team_id: &str) -> (Arc<tokio::sync::Mutex<()>>, u64) {
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        let entry = entries.entry(team_id.to_string()).or_default();
+        (entry.fetch_lock.clone(), entry.generation)
+    }
+
+    fn store(&self, team_id: &str, generation: u64, statuses: &RedeployStatusList) {
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        let entry = entries.entry(team_id.to_string()).or_default();
+        if entry.generation == generation {
+            entry.statuses = Some((Instant::now(), statuses.clone()));
+        }
+    }
+
+    fn invalidate(&self, team_id: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(entry) = entries.get_mut(team_id) {
+            entry.statuses = None;
+            entry.generation += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl RedeployService for CachedRedeployService {
+    #[tracing::instrument(skip_all, fields(target = ?target))]
+    async fn redeploy(&self, target: &RedeployTarget) -> RedeployResult<RedeployJob> {
+        let result = self.inner.redeploy(target).await;
+        if result.is_ok() {
+            self.invalidate(&target.team_id);
+        }
+        result
+    }
+
+    #[tracing::instrument(skip_all, fields(team_id = ?team_id))]
+    async fn get_status(&self, team_id: &str) -> RedeployResult<RedeployStatusList> {
+        if let Some(statuses) = self.lookup(team_id) {
+            tracing::trace!("redeploy status cache hit");
+            return Ok(statuses);
+        }
+
+        let (fetch_lock, _) = self.fetch_lock_and_generation(team_id);
+        let _guard = fetch_lock.lock().await;
+
+        // ロックを待っている間に、他のリクエストが取得した結果があればそれを返す。
+        if let Some(statuses) = self.lookup(team_id) {
+            tracing::trace!("redeploy status fetched by another request");
+            return Ok(statuses);
+        }
+
+        let (_, generation) = self.fetch_lock_and_generation(team_id);
+        let statuses = self.inner.get_status(team_id).await?;
+        self.store(team_id, generation, &statuses);
+
+        Ok(statuses)
+    }
+}
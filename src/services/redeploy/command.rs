@@ -0,0 +1,314 @@
+// This module implements a redeploy service that rebuilds problem environments with local scripts.
+use std::collections::HashMap;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+use crate::models::Problem;
+use crate::services::redeploy::backend_id;
+use crate::services::redeploy::render_template;
+use crate::services::redeploy::RedeployError;
+use crate::services::redeploy::RedeployJob;
+use crate::services::redeploy::RedeployResult;
+use crate::services::redeploy::RedeployService;
+use crate::services::redeploy::RedeployStatus;
+use crate::services::redeploy::RedeployStatusList;
+use crate::services::redeploy::RedeployTarget;
+
+// コマンドを実行して再展開する再展開システムの設定
+// args, envでは {team_id}, {problem_code}, {backend_id} が置換される。
+// また、環境変数 REDEPLOY_TEAM_ID, REDEPLOY_PROBLEM_CODE, REDEPLOY_BACKEND_ID は常に設定される。
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandRedeployConfig {
+    pub program: String,
+
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    pub working_dir: Option<PathBuf>,
+
+    // コマンドの標準出力・標準エラー出力を保存するディレクトリ
+    pub log_dir: PathBuf,
+
+    // 同時に実行するコマンドの数の上限。上限を超えたJobは順番に実行される。
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+
+    // コマンドの実行時間の上限（秒）。これを超えるとコマンドを強制終了する。
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_max_concurrency() -> usize {
+    4
+}
+
+fn default_timeout_secs() -> u64 {
+    30 * 60
+}
+
+// チーム・問題ごとの最後のJobの状態。
+// メモリ上にのみ保持するため、botを再起動すると失われる。
+// 再起動の前から実行中のコマンドは再展開中として扱われないため、同じ問題のコマンドを重ねて実行できてしまう。
+// コマンドの側で、同じ問題の環境を同時に操作しないようにしておく（terraformのstate lock等）。
+#[derive(Debug, Clone)]
+struct CommandJobState {
+    // 同時実行数の上限により順番を待っている間はNone
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+
+    // コマンドが失敗・タイムアウトした、または0以外の終了コードで終了したか
+    failed: bool,
+}
+
+type JobKey = (String, String);
+
+pub struct CommandRedeployService {
+    config: CommandRedeployConfig,
+    problems: Vec<Problem>,
+
+    jobs: Arc<Mutex<HashMap<JobKey, CommandJobState>>>,
+    semaphore: Arc<Semaphore>,
+    next_job_id: AtomicU64,
+}
+
+impl CommandRedeployService {
+    pub fn new(config: CommandRedeployConfig, problems: Vec<Problem>) -> Result<Self> {
+        std::fs::create_dir_all(&config.log_dir)?;
+
+        let semaphore = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+        Ok(Self {
+            config,
+            problems,
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            semaphore,
+            next_job_id: AtomicU64::new(1),
+        })
+    }
+
+    fn build_command(&self, target: &RedeployTarget, log: &File) -> std::io::Result<Command> {
+        let backend_id = backend_id(&self.problems, &target.problem_id);
+        let vars = [
+            ("team_id", target.team_id.as_str()),
+            ("problem_code", target.problem_id.as_str()),
+            ("backend_id", backend_id.as_str()),
+        ];
+
+        let mut command = Command::new(&self.config.program);
+        command
+            .args(
+                self.config
+                    .args
+                    .iter()
+                    .map(|arg| render_template(arg, &vars)),
+            )
+            .env("REDEPLOY_TEAM_ID", &target.team_id)
+            .env("REDEPLOY_PROBLEM_CODE", &target.problem_id)
+            .env("REDEPLOY_BACKEND_ID", &backend_id)
+            .envs(
+                self.config
+                    .env
+                    .iter()
+                    .map(|(name, value)| (name, render_template(value, &vars))),
+            )
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log.try_clone()?)
+            .kill_on_drop(true);
+
+        // コマンドが起動した子プロセス（terraform, ansible等）もまとめて終了できるよう、
+        // 新しいプロセスグループで実行する。
+        #[cfg(unix)]
+        command.process_group(0);
+
+        if let Some(working_dir) = &self.config.working_dir {
+            command.current_dir(working_dir);
+        }
+
+        Ok(command)
+    }
+}
+
+#[async_trait]
+impl RedeployService for CommandRedeployService {
+    #[tracing::instrument(skip_all, fields(target = ?target))]
+    async fn redeploy(&self, target: &RedeployTarget) -> RedeployResult<RedeployJob> {
+        tracing::trace!("redeploy request received");
+
+        let key = (target.team_id.clone(), target.problem_id.clone());
+
+        {
+            let mut jobs = self.jobs.lock().unwrap_or_else(|err| err.into_inner());
+            if let Some(job) = jobs.get(&key) {
+                if job.completed_at.is_none() {
+                    return Err(RedeployError::AnotherJobInQueue(String::from(
+                        "redeploy command is already running",
+                    )));
+                }
+            }
+            jobs.insert(
+                key.clone(),
+                CommandJobState {
+                    started_at: None,
+                    completed_at: None,
+                    failed: false,
+                },
+            );
+        }
+
+        // 連番はbotを再起動すると1に戻るため、開始時刻を含めて以前のJobのログを上書きしないようにする。
+        let id = format!(
+            "{}-{}-{}-{}",
+            target.team_id,
+            target.problem_id,
+            Utc::now().format("%Y%m%d%H%M%S%3f"),
+            self.next_job_id.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let log_path = self.config.log_dir.join(format!("{}.log", id));
+        let command = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&log_path)
+            .and_then(|log| Ok((self.build_command(target, &log)?, log)));
+        let (command, log) = match command {
+            Ok(command) => command,
+            Err(err) => {
+                // Jobを開始できなかったため、再展開中の状態を取り消す。
+                self.jobs
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .remove(&key);
+                return Err(RedeployError::Unexpected(Box::new(err)));
+            },
+        };
+
+        tracing::info!(id, ?log_path, "start redeploy command");
+
+        let jobs = self.jobs.clone();
+        let semaphore = self.semaphore.clone();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let job_id = id.clone();
+        tokio::spawn(async move {
+            let result = match semaphore.acquire_owned().await {
+                Ok(_permit) => {
+                    // 順番待ちの時間を含めないよう、実行を開始した時刻を記録する。
+                    if let Some(job) = jobs
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .get_mut(&key)
+                    {
+                        job.started_at = Some(Utc::now());
+                    }
+
+                    run_command(command, timeout).await
+                },
+                Err(err) => Err(anyhow::anyhow!(err)),
+            };
+
+            match &result {
+                Ok(status) if status.success() => {
+                    tracing::info!(job_id, "redeploy command finished")
+                },
+                Ok(status) => tracing::error!(job_id, ?status, "redeploy command failed"),
+                Err(err) => tracing::error!(job_id, ?err, "failed to run redeploy command"),
+            }
+
+            let summary = match &result {
+                Ok(status) => format!("redeploy command exited: {}", status),
+                Err(err) => format!("redeploy command failed: {}", err),
+            };
+            let mut log = tokio::fs::File::from_std(log);
+            if let Err(err) = log.write_all(format!("{}\n", summary).as_bytes()).await {
+                tracing::warn!(job_id, ?err, "failed to write redeploy log");
+            }
+
+            // 失敗した場合も完了として扱い、失敗したことを記録する。詳細はログファイルで確認する。
+            let mut jobs = jobs.lock().unwrap_or_else(|err| err.into_inner());
+            if let Some(job) = jobs.get_mut(&key) {
+                job.completed_at = Some(Utc::now());
+                job.failed = !matches!(&result, Ok(status) if status.success());
+            }
+        });
+
+        Ok(RedeployJob {
+            id,
+            team_id: target.team_id.clone(),
+            problem_code: target.problem_id.clone(),
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(team_id = ?team_id))]
+    async fn get_status(&self, team_id: &str) -> RedeployResult<RedeployStatusList> {
+        tracing::trace!("get_status request received");
+
+        let jobs = self.jobs.lock().unwrap_or_else(|err| err.into_inner());
+
+        Ok(self
+            .problems
+            .iter()
+            .map(|problem| {
+                let job = jobs.get(&(team_id.to_string(), problem.code.clone()));
+                RedeployStatus {
+                    team_id: team_id.to_string(),
+                    problem_code: problem.code.clone(),
+                    is_redeploying: job.is_some_and(|job| job.completed_at.is_none()),
+                    last_redeploy_started_at: job.and_then(|job| job.started_at),
+                    last_redeploy_completed_at: job.and_then(|job| job.completed_at),
+                    last_redeploy_failed: job.is_some_and(|job| job.failed),
+                    is_unknown: false,
+                }
+            })
+            .collect())
+    }
+}
+
+async fn run_command(mut command: Command, timeout: Duration) -> Result<std::process::ExitStatus> {
+    let mut child = command.spawn()?;
+
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => Ok(status?),
+        Err(_) => {
+            kill_process_group(&child);
+            child.kill().await?;
+            anyhow::bail!("redeploy command timed out after {:?}", timeout)
+        },
+    }
+}
+
+// コマンドのプロセスグループ全体を強制終了する。
+// child.kill()は直接の子プロセスしか終了しないため、孫プロセスが残らないようにする。
+#[cfg(unix)]
+fn kill_process_group(child: &tokio::process::Child) {
+    if let Some(pid) = child.id() {
+        // SAFETY: killpgは引数のプロセスグループにシグナルを送るだけで、メモリを操作しない。
+        if unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) } != 0 {
+            tracing::warn!(
+                pid,
+                err = ?std::io::Error::last_os_error(),
+                "failed to kill process group"
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_child: &tokio::process::Child) {}
@@ -0,0 +1,241 @@
+// This module encodes redeploy confirmation requests into signed button custom_ids.
+use base64::URL_SAFE_NO_PAD;
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use hmac::Hmac;
+use hmac::Mac;
+use rand::Rng;
+use sha2::Sha256;
+
+// 再展開の確認ボタンのcustom_idの接頭辞
+pub const CUSTOM_ID_PREFIX: &str = "redeploy";
+
+// 署名の長さ（バイト）。custom_idは100文字までに制限されているため、切り詰めて使う。
+const SIGNATURE_LENGTH: usize = 16;
+
+// チームIDと問題コード以外のフィールドを詰めたバイト列の長さ。
+// 操作（1）・staffフラグ（1）・ユーザID（8）・nonce（4）・有効期限（4）からなる。
+const FIELDS_LENGTH: usize = 1 + 1 + 8 + 4 + 4;
+
+// Discordが許容するcustom_idの最大長
+const CUSTOM_ID_MAX_LENGTH: usize = 100;
+
+// custom_idのうち、チームIDと問題コード以外の部分の長さ。
+// 接頭辞と、フィールドと署名をまとめてbase64にしたものと、3つの区切り文字からなる。
+const FIXED_PARTS_LENGTH: usize =
+    CUSTOM_ID_PREFIX.len() + ((FIELDS_LENGTH + SIGNATURE_LENGTH) * 4).div_ceil(3) + 3;
+
+// チームIDと問題コードの長さの合計の上限
+pub const MAX_IDS_LENGTH: usize = CUSTOM_ID_MAX_LENGTH - FIXED_PARTS_LENGTH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedeployConfirmationAction {
+    Confirm,
+    Cancel,
+
+    // 確認の前に、再展開の理由を入力するモーダルの送信
+    Reason,
+}
+
+impl RedeployConfirmationAction {
+    fn as_u8(&self) -> u8 {
+        match self {
+            RedeployConfirmationAction::Confirm => 0,
+            RedeployConfirmationAction::Cancel => 1,
+            RedeployConfirmationAction::Reason => 2,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(RedeployConfirmationAction::Confirm),
+            1 => Some(RedeployConfirmationAction::Cancel),
+            2 => Some(RedeployConfirmationAction::Reason),
+            _ => None,
+        }
+    }
+}
+
+// 再展開の確認ボタンが押された時に、再展開を実行するために必要な情報。
+// botが再起動しても確認できるよう、ボタン（と理由を入力するモーダル）のcustom_idに署名付きで埋め込む。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedeployConfirmation {
+    pub action: RedeployConfirmationAction,
+    pub team_id: String,
+    pub problem_code: String,
+
+    // staffがチームの代わりに再展開を実行したかを表すフラグ
+    pub triggered_by_staff: bool,
+
+    // 再展開をリクエストしたユーザ。このユーザ以外はボタンを押せない。
+    pub requester_id: u64,
+
+    // 同じ内容の確認を区別するための値
+    pub nonce: u32,
+
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RedeployConfirmationError {
+    #[error("malformed custom_id")]
+    Malformed,
+
+    #[error("invalid signature")]
+    InvalidSignature,
+
+    #[error("confirmation expired")]
+    Expired,
+
+    #[error("team id and problem code must not contain ':'")]
+    InvalidCharacter,
+
+    #[error(
+        "team id and problem code must be at most {} characters in total",
+        MAX_IDS_LENGTH
+    )]
+    TooLong,
+}
+
+// チームIDと問題コードを、custom_idに埋め込めるかを確認する。
+// 区切り文字の ':' を含まず、長さの合計がMAX_IDS_LENGTH以下である必要がある。
+pub fn validate_ids(team_id: &str, problem_code: &str) -> Result<(), RedeployConfirmationError> {
+    if team_id.contains(':') || problem_code.contains(':') {
+        return Err(RedeployConfirmationError::InvalidCharacter);
+    }
+    if team_id.len() + problem_code.len() > MAX_IDS_LENGTH {
+        return Err(RedeployConfirmationError::TooLong);
+    }
+    Ok(())
+}
+
+impl RedeployConfirmation {
+    pub fn new(
+        team_id: &str,
+        problem_code: &str,
+        triggered_by_staff: bool,
+        requester_id: u64,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        RedeployConfirmation {
+            action: RedeployConfirmationAction::Confirm,
+            team_id: team_id.to_string(),
+            problem_code: problem_code.to_string(),
+            triggered_by_staff,
+            requester_id,
+            nonce: rand::thread_rng().gen(),
+            expires_at,
+        }
+    }
+
+    pub fn with_action(&self, action: RedeployConfirmationAction) -> Self {
+        RedeployConfirmation {
+            action,
+            ..self.clone()
+        }
+    }
+
+    // 同じ確認メッセージのボタン（OKとキャンセル）で共通の、確認を一度しか使えないようにするためのキー
+    pub fn key(&self) -> String {
+        format!(
+            "{}:{}:{}:{:08x}",
+            self.team_id, self.problem_code, self.requester_id, self.nonce
+        )
+    }
+
+    // redeploy:<team_id>:<problem_code>:<base64(fields + signature)>
+    // custom_idの長さの制限に収めるため、チームIDと問題コード以外のフィールドはバイト列に詰めて埋め込む。
+    pub fn encode(&self, secret: &str) -> String {
+        let ids = format!(
+            "{}:{}:{}:",
+            CUSTOM_ID_PREFIX, self.team_id, self.problem_code
+        );
+
+        let mut fields = Vec::with_capacity(FIELDS_LENGTH + SIGNATURE_LENGTH);
+        fields.push(self.action.as_u8());
+        fields.push(u8::from(self.triggered_by_staff));
+        fields.extend_from_slice(&self.requester_id.to_be_bytes());
+        fields.extend_from_slice(&self.nonce.to_be_bytes());
+        // 有効期限は2106年まで表現できれば十分なため、u32で埋め込む。
+        let expires_at = u32::try_from(self.expires_at.timestamp()).unwrap_or(u32::MAX);
+        fields.extend_from_slice(&expires_at.to_be_bytes());
+
+        let signature = sign(secret, ids.as_bytes(), &fields);
+        fields.extend_from_slice(&signature);
+
+        format!("{}{}", ids, base64::encode_config(fields, URL_SAFE_NO_PAD))
+    }
+
+    // 署名と有効期限を検証した上で、custom_idを復元する。
+    pub fn decode(
+        custom_id: &str,
+        secret: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Self, RedeployConfirmationError> {
+        let (ids, encoded) = custom_id
+            .rsplit_once(':')
+            .ok_or(RedeployConfirmationError::Malformed)?;
+        let fields = base64::decode_config(encoded, URL_SAFE_NO_PAD)
+            .map_err(|_| RedeployConfirmationError::Malformed)?;
+        if fields.len() != FIELDS_LENGTH + SIGNATURE_LENGTH {
+            return Err(RedeployConfirmationError::Malformed);
+        }
+        let (fields, signature) = fields.split_at(FIELDS_LENGTH);
+
+        // 接頭辞の後の ':' も署名の対象に含める。
+        let signed_ids = &custom_id[..ids.len() + 1];
+        let mut mac = new_mac(secret);
+        mac.update(signed_ids.as_bytes());
+        mac.update(fields);
+        mac.verify_truncated_left(signature)
+            .map_err(|_| RedeployConfirmationError::InvalidSignature)?;
+
+        let [prefix, team_id, problem_code] = ids.split(':').collect::<Vec<_>>()[..] else {
+            return Err(RedeployConfirmationError::Malformed);
+        };
+        if prefix != CUSTOM_ID_PREFIX {
+            return Err(RedeployConfirmationError::Malformed);
+        }
+
+        let requester_id = u64::from_be_bytes(fields[2..10].try_into().unwrap());
+        let nonce = u32::from_be_bytes(fields[10..14].try_into().unwrap());
+        let expires_at = u32::from_be_bytes(fields[14..18].try_into().unwrap());
+
+        let confirmation = RedeployConfirmation {
+            action: RedeployConfirmationAction::from_u8(fields[0])
+                .ok_or(RedeployConfirmationError::Malformed)?,
+            team_id: team_id.to_string(),
+            problem_code: problem_code.to_string(),
+            triggered_by_staff: fields[1] == 1,
+            requester_id,
+            nonce,
+            expires_at: Utc
+                .timestamp_opt(i64::from(expires_at), 0)
+                .single()
+                .ok_or(RedeployConfirmationError::Malformed)?,
+        };
+
+        if confirmation.expires_at < now {
+            return Err(RedeployConfirmationError::Expired);
+        }
+
+        Ok(confirmation)
+    }
+}
+
+// custom_idが再展開の確認ボタン（または理由を入力するモーダル）のものか？
+pub fn is_confirmation_custom_id(custom_id: &str) -> bool {
+    custom_id.starts_with(&format!("{}:", CUSTOM_ID_PREFIX))
+}
+
+fn new_mac(secret: &str) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size")
+}
+
+fn sign(secret: &str, ids: &[u8], fields: &[u8]) -> Vec<u8> {
+    let mut mac = new_mac(secret);
+    mac.update(ids);
+    mac.update(fields);
+    mac.finalize().into_bytes()[..SIGNATURE_LENGTH].to_vec()
+}
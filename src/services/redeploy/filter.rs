@@ -0,0 +1,113 @@
+// This module implements filtering of redeploy events shared by all notifiers.
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::services::redeploy::notifiers::RedeployEventKind;
+use crate::services::redeploy::notifiers::RedeployNotification;
+use crate::services::redeploy::RedeployAlert;
+use crate::services::redeploy::RedeployCompletion;
+use crate::services::redeploy::RedeployJob;
+use crate::services::redeploy::RedeployNotifier;
+use crate::services::redeploy::RedeployRequestEvent;
+use crate::services::redeploy::RedeployRequester;
+use crate::services::redeploy::RedeployTarget;
+
+// 通知するイベントの条件。
+// 省略した項目は全てのイベントにマッチし、指定した項目は全て満たす必要がある。
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RedeployNotifierFilter {
+    // 通知するイベントの種類（requested, failed, completed, timed_out, unavailable, recovered）
+    pub outcomes: Option<Vec<RedeployEventKind>>,
+
+    // 通知する問題コード。
+    // 再展開システムの障害・復旧はどの問題にも紐づかないため、指定した場合は通知しない。
+    pub problem_codes: Option<Vec<String>>,
+
+    // 通知するチームID。problem_codesと同様に、障害・復旧は通知しない。
+    pub team_ids: Option<Vec<String>>,
+}
+
+impl RedeployNotifierFilter {
+    pub fn matches(&self, notification: &RedeployNotification) -> bool {
+        fn contains(values: &Option<Vec<String>>, value: &Option<String>) -> bool {
+            match (values, value) {
+                (None, _) => true,
+                (Some(values), Some(value)) => values.contains(value),
+                (Some(_), None) => false,
+            }
+        }
+
+        self.outcomes
+            .as_ref()
+            .is_none_or(|outcomes| outcomes.contains(&notification.event))
+            && contains(&self.problem_codes, &notification.problem_code)
+            && contains(&self.team_ids, &notification.team_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_none() && self.problem_codes.is_none() && self.team_ids.is_none()
+    }
+}
+
+// 条件にマッチしたイベントだけを内側のRedeployNotifierに渡す
+pub struct FilteredRedeployNotifier {
+    inner: Box<dyn RedeployNotifier + Send + Sync>,
+    filter: RedeployNotifierFilter,
+}
+
+impl FilteredRedeployNotifier {
+    pub fn new(
+        inner: Box<dyn RedeployNotifier + Send + Sync>,
+        filter: RedeployNotifierFilter,
+    ) -> Self {
+        Self { inner, filter }
+    }
+
+    // 条件が空の場合は、ラップせずにそのまま返す。
+    pub fn wrap(
+        inner: Box<dyn RedeployNotifier + Send + Sync>,
+        filter: RedeployNotifierFilter,
+    ) -> Box<dyn RedeployNotifier + Send + Sync> {
+        if filter.is_empty() {
+            return inner;
+        }
+        Box::new(Self::new(inner, filter))
+    }
+}
+
+#[async_trait]
+impl RedeployNotifier for FilteredRedeployNotifier {
+    async fn notify(&self, event: &RedeployRequestEvent<'_>) {
+        if self
+            .filter
+            .matches(&RedeployNotification::from_event(event))
+        {
+            self.inner.notify(event).await;
+        }
+    }
+
+    async fn notify_completion(
+        &self,
+        target: &RedeployTarget,
+        job: &RedeployJob,
+        completion: &RedeployCompletion,
+        requester: &RedeployRequester,
+    ) {
+        if self.filter.matches(&RedeployNotification::from_completion(
+            target, job, completion, requester,
+        )) {
+            self.inner
+                .notify_completion(target, job, completion, requester)
+                .await;
+        }
+    }
+
+    async fn notify_alert(&self, alert: &RedeployAlert) {
+        if self
+            .filter
+            .matches(&RedeployNotification::from_alert(alert))
+        {
+            self.inner.notify_alert(alert).await;
+        }
+    }
+}
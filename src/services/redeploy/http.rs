@@ -0,0 +1,477 @@
+// This module implements a redeploy service for REST APIs described entirely in the configuration.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use reqwest::ClientBuilder;
+use reqwest::Method;
+use reqwest::RequestBuilder;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::models::Problem;
+use crate::services::redeploy::backend_id;
+use crate::services::redeploy::collect_problem_statuses;
+use crate::services::redeploy::render_json_template;
+use crate::services::redeploy::render_template;
+use crate::services::redeploy::RedeployError;
+use crate::services::redeploy::RedeployJob;
+use crate::services::redeploy::RedeployResult;
+use crate::services::redeploy::RedeployService;
+use crate::services::redeploy::RedeployStatus;
+use crate::services::redeploy::RedeployStatusList;
+use crate::services::redeploy::RedeployTarget;
+
+// 汎用的なHTTP APIを利用する再展開システムの設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpRedeployConfig {
+    pub auth: Option<HttpAuthConfig>,
+
+    // 再展開をリクエストするAPI
+    pub redeploy: HttpRedeployEndpointConfig,
+
+    // 再展開状況を取得するAPI
+    pub status: HttpStatusEndpointConfig,
+
+    // 1回のリクエストのタイムアウト（秒）
+    #[serde(default = "default_http_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_http_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpAuthConfig {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+    // 任意のヘッダに認証情報を載せる（例: X-API-Key）
+    Header { name: String, value: String },
+}
+
+// URL、ヘッダ、ボディでは {team_id}, {problem_code}, {backend_id} が置換される。
+// JSONのボディでは、置換する値はJSONの文字列としてエスケープされる（"{team_id}" のように引用符で囲んで使う）。
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpRequestConfig {
+    #[serde(default = "default_http_method")]
+    pub method: String,
+    pub url: String,
+
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    // ボディのテンプレート。Content-Typeが指定されていない場合はJSONとして送信する。
+    pub body: Option<String>,
+}
+
+fn default_http_method() -> String {
+    String::from("GET")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpRedeployEndpointConfig {
+    #[serde(flatten)]
+    pub request: HttpRequestConfig,
+
+    #[serde(default)]
+    pub response: HttpRedeployResponseConfig,
+}
+
+// レスポンスのフィールドはJSON Pointer（例: /data/id）で指定する。
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpRedeployResponseConfig {
+    #[serde(default = "default_job_id_pointer")]
+    pub job_id: String,
+
+    // 既に再展開中であることを表すステータスコード
+    #[serde(default = "default_conflict_statuses")]
+    pub conflict_statuses: Vec<u16>,
+
+    // リクエストのパラメータが不正であることを表すステータスコード
+    #[serde(default = "default_invalid_statuses")]
+    pub invalid_statuses: Vec<u16>,
+}
+
+impl Default for HttpRedeployResponseConfig {
+    fn default() -> Self {
+        HttpRedeployResponseConfig {
+            job_id: default_job_id_pointer(),
+            conflict_statuses: default_conflict_statuses(),
+            invalid_statuses: default_invalid_statuses(),
+        }
+    }
+}
+
+fn default_job_id_pointer() -> String {
+    String::from("/id")
+}
+
+fn default_conflict_statuses() -> Vec<u16> {
+    vec![409]
+}
+
+fn default_invalid_statuses() -> Vec<u16> {
+    vec![400]
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpStatusEndpointConfig {
+    #[serde(flatten)]
+    pub request: HttpRequestConfig,
+
+    #[serde(default)]
+    pub response: HttpStatusResponseConfig,
+
+    // 問題ごとにリクエストする場合に、同時に取得する問題の数の上限
+    #[serde(default = "default_status_max_concurrency")]
+    pub max_concurrency: usize,
+}
+
+fn default_status_max_concurrency() -> usize {
+    8
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpStatusResponseConfig {
+    // 問題ごとの状態の配列を指すJSON Pointer。
+    // 指定した場合はチームごとに1回、省略した場合は問題ごとに1回リクエストする。
+    pub items: Option<String>,
+
+    // 以下は配列の各要素（または問題ごとのレスポンス）からの相対パス
+    #[serde(default = "default_problem_code_pointer")]
+    pub problem_code: String,
+
+    // 省略した場合は、開始時刻と完了時刻から再展開中かを判定する。
+    pub is_redeploying: Option<String>,
+
+    #[serde(default = "default_started_at_pointer")]
+    pub started_at: String,
+
+    #[serde(default = "default_completed_at_pointer")]
+    pub completed_at: String,
+
+    // 最後の再展開が失敗したかを表すbooleanのJSON Pointer。省略した場合は失敗を検出しない。
+    pub failed: Option<String>,
+}
+
+impl Default for HttpStatusResponseConfig {
+    fn default() -> Self {
+        HttpStatusResponseConfig {
+            items: None,
+            problem_code: default_problem_code_pointer(),
+            is_redeploying: None,
+            started_at: default_started_at_pointer(),
+            completed_at: default_completed_at_pointer(),
+            failed: None,
+        }
+    }
+}
+
+fn default_problem_code_pointer() -> String {
+    String::from("/problem_code")
+}
+
+fn default_started_at_pointer() -> String {
+    String::from("/started_at")
+}
+
+fn default_completed_at_pointer() -> String {
+    String::from("/completed_at")
+}
+
+pub struct HttpRedeployService {
+    config: HttpRedeployConfig,
+    problems: Vec<Problem>,
+    client: Client,
+}
+
+impl HttpRedeployService {
+    pub fn new(config: HttpRedeployConfig, problems: Vec<Problem>) -> Result<Self> {
+        // 設定の誤りは起動時に検出する。
+        parse_method(&config.redeploy.request.method)?;
+        parse_method(&config.status.request.method)?;
+
+        let client = ClientBuilder::new()
+            .user_agent("ICTSC Discord Bot")
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+
+        Ok(Self {
+            config,
+            problems,
+            client,
+        })
+    }
+
+    fn build_request(
+        &self,
+        request: &HttpRequestConfig,
+        vars: &[(&str, &str)],
+    ) -> RedeployResult<RequestBuilder> {
+        let method = parse_method(&request.method)?;
+        let mut builder = self
+            .client
+            .request(method, render_template(&request.url, vars));
+
+        builder = match &self.config.auth {
+            Some(HttpAuthConfig::Basic { username, password }) => {
+                builder.basic_auth(username, Some(password))
+            },
+            Some(HttpAuthConfig::Bearer { token }) => builder.bearer_auth(token),
+            Some(HttpAuthConfig::Header { name, value }) => builder.header(name, value),
+            None => builder,
+        };
+
+        for (name, value) in &request.headers {
+            builder = builder.header(name, render_template(value, vars));
+        }
+
+        if let Some(body) = &request.body {
+            let content_type = request
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+                .map(|(_, value)| value.to_lowercase());
+
+            let is_json = match &content_type {
+                Some(content_type) => content_type.contains("json"),
+                None => {
+                    builder = builder.header(CONTENT_TYPE, "application/json");
+                    true
+                },
+            };
+
+            let body = if is_json {
+                render_json_template(body, vars)
+            } else {
+                render_template(body, vars)
+            };
+            builder = builder.body(body);
+        }
+
+        Ok(builder)
+    }
+
+    async fn fetch_json(
+        &self,
+        request: &HttpRequestConfig,
+        vars: &[(&str, &str)],
+    ) -> RedeployResult<Value> {
+        let response = self.build_request(request, vars)?.send().await?;
+
+        if !response.status().is_success() {
+            return Err(unexpected(format!(
+                "unexpected status code: {}",
+                response.status()
+            )));
+        }
+
+        Ok(serde_json::from_slice(response.bytes().await?.as_ref())?)
+    }
+
+    async fn get_problem_status(
+        &self,
+        team_id: &str,
+        problem: &Problem,
+    ) -> RedeployResult<RedeployStatus> {
+        let backend_id = problem.backend_id();
+        let vars = [
+            ("team_id", team_id),
+            ("problem_code", problem.code.as_str()),
+            ("backend_id", backend_id.as_str()),
+        ];
+        let body = self.fetch_json(&self.config.status.request, &vars).await?;
+        self.parse_status(team_id, Some(&problem.code), &body)
+    }
+
+    // 配列の要素から問題コードを取得する。
+    // APIが大文字小文字を区別せずに扱う場合や、backend_idを返す場合でも、設定上の問題コードに揃える。
+    fn lookup_problem_code(&self, item: &Value) -> Option<String> {
+        let problem_code = lookup_string(item, &self.config.status.response.problem_code)?;
+        Some(
+            self.problems
+                .iter()
+                .find(|problem| {
+                    problem.code.eq_ignore_ascii_case(&problem_code)
+                        || problem.backend_id() == problem_code
+                })
+                .map(|problem| problem.code.clone())
+                .unwrap_or(problem_code),
+        )
+    }
+
+    fn parse_status(
+        &self,
+        team_id: &str,
+        problem_code: Option<&str>,
+        item: &Value,
+    ) -> RedeployResult<RedeployStatus> {
+        let response = &self.config.status.response;
+
+        let problem_code = match problem_code {
+            Some(problem_code) => problem_code.to_string(),
+            None => self
+                .lookup_problem_code(item)
+                .ok_or_else(|| unexpected("problem code not found in response"))?,
+        };
+
+        let started_at = lookup_datetime(item, &response.started_at)?;
+        let completed_at = lookup_datetime(item, &response.completed_at)?;
+
+        let is_redeploying = match &response.is_redeploying {
+            Some(pointer) => item
+                .pointer(pointer)
+                .and_then(Value::as_bool)
+                .ok_or_else(|| unexpected(format!("boolean not found at {}", pointer)))?,
+            None => match (started_at, completed_at) {
+                (Some(started_at), Some(completed_at)) => completed_at < started_at,
+                (Some(_), None) => true,
+                (None, _) => false,
+            },
+        };
+
+        // 値がない場合は、まだ結果が出ていないものとして扱う。
+        let failed = response
+            .failed
+            .as_ref()
+            .and_then(|pointer| item.pointer(pointer))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        Ok(RedeployStatus {
+            team_id: team_id.to_string(),
+            problem_code,
+            is_redeploying,
+            last_redeploy_started_at: started_at,
+            last_redeploy_completed_at: completed_at,
+            last_redeploy_failed: failed,
+            is_unknown: false,
+        })
+    }
+}
+
+#[async_trait]
+impl RedeployService for HttpRedeployService {
+    #[tracing::instrument(skip_all, fields(target = ?target))]
+    async fn redeploy(&self, target: &RedeployTarget) -> RedeployResult<RedeployJob> {
+        tracing::trace!("redeploy request received");
+
+        let backend_id = backend_id(&self.problems, &target.problem_id);
+        let vars = [
+            ("team_id", target.team_id.as_str()),
+            ("problem_code", target.problem_id.as_str()),
+            ("backend_id", backend_id.as_str()),
+        ];
+        let response = self
+            .build_request(&self.config.redeploy.request, &vars)?
+            .send()
+            .await?;
+
+        let status = response.status();
+        let config = &self.config.redeploy.response;
+
+        if status.is_success() {
+            let body: Value = serde_json::from_slice(response.bytes().await?.as_ref())?;
+            let id = lookup_string(&body, &config.job_id)
+                .ok_or_else(|| unexpected("job id not found in response"))?;
+
+            return Ok(RedeployJob {
+                id,
+                team_id: target.team_id.clone(),
+                problem_code: target.problem_id.clone(),
+            });
+        }
+
+        if config.conflict_statuses.contains(&status.as_u16()) {
+            return Err(RedeployError::AnotherJobInQueue(response.text().await?));
+        }
+
+        if config.invalid_statuses.contains(&status.as_u16()) {
+            return Err(RedeployError::InvalidParameters);
+        }
+
+        Err(unexpected(format!("unexpected status code: {}", status)))
+    }
+
+    #[tracing::instrument(skip_all, fields(team_id = ?team_id))]
+    async fn get_status(&self, team_id: &str) -> RedeployResult<RedeployStatusList> {
+        tracing::trace!("get_status request received");
+
+        let request = &self.config.status.request;
+
+        if let Some(items) = &self.config.status.response.items {
+            let body = self.fetch_json(request, &[("team_id", team_id)]).await?;
+            let items = body
+                .pointer(items)
+                .and_then(Value::as_array)
+                .ok_or_else(|| unexpected(format!("array not found at {}", items)))?;
+
+            // 一部の要素が不正な場合でも、他の問題の状態は返す。
+            // 不正な要素は、問題コードが分かればその問題を状態不明として扱い、分からなければ無視する。
+            return Ok(items
+                .iter()
+                .filter_map(|item| match self.parse_status(team_id, None, item) {
+                    Ok(status) => Some(status),
+                    Err(err) => {
+                        let problem_code = self.lookup_problem_code(item);
+                        tracing::warn!(?err, ?problem_code, "failed to parse redeploy status");
+                        problem_code
+                            .map(|problem_code| RedeployStatus::unknown(team_id, &problem_code))
+                    },
+                })
+                .collect());
+        }
+
+        // async_traitの制約上、クロージャをストリームに保持させずにFutureを先に作っておく。
+        // 再展開できない問題は、状態を取得しない。
+        let requests: Vec<_> = self
+            .problems
+            .iter()
+            .filter(|problem| problem.is_redeployable())
+            .map(
+                |problem| async move { (problem, self.get_problem_status(team_id, problem).await) },
+            )
+            .collect();
+
+        collect_problem_statuses(team_id, requests, self.config.status.max_concurrency).await
+    }
+}
+
+fn unexpected<M>(message: M) -> RedeployError
+where
+    M: std::fmt::Display,
+{
+    RedeployError::Unexpected(anyhow::anyhow!("{}", message).into())
+}
+
+fn parse_method(method: &str) -> RedeployResult<Method> {
+    Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|err| RedeployError::Unexpected(Box::new(err)))
+}
+
+// 文字列以外の値（数値のIDなど）も文字列として扱う。
+fn lookup_string(value: &Value, pointer: &str) -> Option<String> {
+    match value.pointer(pointer) {
+        None | Some(Value::Null) => None,
+        Some(Value::String(value)) => Some(value.clone()),
+        Some(value) => Some(value.to_string()),
+    }
+}
+
+fn lookup_datetime(value: &Value, pointer: &str) -> RedeployResult<Option<DateTime<Utc>>> {
+    let value = match lookup_string(value, pointer) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    DateTime::parse_from_rfc3339(&value)
+        .map(|datetime| Some(datetime.with_timezone(&Utc)))
+        .map_err(|err| RedeployError::Unexpected(Box::new(err)))
+}
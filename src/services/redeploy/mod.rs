@@ -0,0 +1,774 @@
+pub mod cache;
+pub mod command;
+pub mod confirmation;
+pub mod filter;
+pub mod http;
+pub mod notifiers;
+pub mod resilient;
+
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use futures::stream;
+use futures::StreamExt;
+use reqwest::Client;
+use reqwest::ClientBuilder;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::Serialize;
+use serenity::all::Colour;
+use serenity::all::CreateEmbed;
+use serenity::all::ExecuteWebhook;
+use serenity::http::Http;
+use serenity::model::webhook::Webhook;
+
+use crate::models::Problem;
+
+#[derive(Debug, Clone)]
+pub struct RedeployJob {
+    pub id: String,
+    pub team_id: String,
+    pub problem_code: String,
+}
+
+type RedeployStatusList = Vec<RedeployStatus>;
+
+#[derive(Debug, Clone)]
+pub struct RedeployStatus {
+    pub team_id: String,
+    pub problem_code: String,
+
+    // 再展開中かを表すフラグ
+    pub is_redeploying: bool,
+
+    // 最後の再展開が開始された時刻
+    pub last_redeploy_started_at: Option<DateTime<Utc>>,
+
+    // 最後の再展開が完了した時刻
+    pub last_redeploy_completed_at: Option<DateTime<Utc>>,
+
+    // 最後の再展開が失敗したかを表すフラグ。失敗を検出できない再展開システムでは常にfalse
+    pub last_redeploy_failed: bool,
+
+    // 再展開状況を取得できなかったかを表すフラグ
+    pub is_unknown: bool,
+}
+
+impl RedeployStatus {
+    // 再展開状況を取得できなかった問題の状態
+    pub fn unknown(team_id: &str, problem_code: &str) -> Self {
+        RedeployStatus {
+            team_id: team_id.to_string(),
+            problem_code: problem_code.to_string(),
+            is_redeploying: false,
+            last_redeploy_started_at: None,
+            last_redeploy_completed_at: None,
+            last_redeploy_failed: false,
+            is_unknown: true,
+        }
+    }
+}
+
+type RedeployResult<T> = Result<T, RedeployError>;
+
+// テンプレート中の {name} を値に置換する。
+// 置換した値に {name} が含まれていても再び置換しないよう、テンプレートを先頭から一度だけ走査する。
+// 変数名に一致しない {...} は、そのまま残す。
+fn render_template(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let var = after.find('}').and_then(|end| {
+            vars.iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, *value))
+        });
+        match var {
+            Some((end, value)) => {
+                rendered.push_str(value);
+                rest = &after[end + 1..];
+            },
+            None => {
+                rendered.push('{');
+                rest = after;
+            },
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+// JSONのテンプレートでは、置換する値をJSONの文字列としてエスケープする。
+fn render_json_template(template: &str, vars: &[(&str, &str)]) -> String {
+    let escaped: Vec<_> = vars
+        .iter()
+        .map(|(name, value)| (*name, escape_json_string(value)))
+        .collect();
+    let escaped: Vec<_> = escaped
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+    render_template(template, &escaped)
+}
+
+// JSONの文字列リテラルの中に埋め込めるよう、値をエスケープする（前後の引用符は含めない）。
+fn escape_json_string(value: &str) -> String {
+    let quoted = serde_json::Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+// 再展開システム上での問題のIDを返す。
+// 問題ごとにbackend_idが設定されている場合はそれを、それ以外の場合は問題コードを小文字にしたものを使う。
+fn backend_id(problems: &[Problem], problem_code: &str) -> String {
+    problems
+        .iter()
+        .find(|problem| problem.code == problem_code)
+        .map(|problem| problem.backend_id())
+        .unwrap_or_else(|| problem_code.to_lowercase())
+}
+
+// 問題ごとの再展開状況を取得するFutureを、同時実行数を制限して並行に実行する。
+// 一部の問題の取得に失敗した場合、その問題は状態不明として扱い、取得できた結果を返す。
+async fn collect_problem_statuses<'a, F>(
+    team_id: &str,
+    requests: Vec<F>,
+    max_concurrency: usize,
+) -> RedeployResult<RedeployStatusList>
+where
+    F: Future<Output = (&'a Problem, RedeployResult<RedeployStatus>)>,
+{
+    let results: Vec<_> = stream::iter(requests)
+        .buffered(max_concurrency.max(1))
+        .collect()
+        .await;
+
+    // 全ての問題の取得に失敗した場合は、再展開システム自体に問題があるとみなしてエラーを返す。
+    if !results.is_empty() && results.iter().all(|(_, result)| result.is_err()) {
+        let (_, result) = results.into_iter().next().unwrap();
+        return Err(result.unwrap_err());
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|(problem, result)| {
+            result.unwrap_or_else(|err| {
+                tracing::warn!(?err, problem_code = ?problem.code, "failed to get redeploy status");
+                RedeployStatus::unknown(team_id, &problem.code)
+            })
+        })
+        .collect())
+}
+
+#[async_trait]
+pub trait RedeployService {
+    async fn redeploy(&self, target: &RedeployTarget) -> RedeployResult<RedeployJob>;
+    async fn get_status(&self, team_id: &str) -> RedeployResult<RedeployStatusList>;
+}
+
+#[derive(Debug, Clone)]
+pub struct RedeployTarget {
+    pub team_id: String,
+    pub problem_id: String,
+
+    // staffがチームの代わりに再展開を実行したかを表すフラグ
+    pub triggered_by_staff: bool,
+
+    // 再展開の理由（任意）
+    pub reason: Option<String>,
+}
+
+// 再展開Jobの監視結果
+#[derive(Debug, Clone)]
+pub enum RedeployCompletion {
+    // 再展開が完了した
+    Completed { completed_at: DateTime<Utc> },
+    // 再展開が失敗して終了した
+    Failed { completed_at: DateTime<Utc> },
+    // 制限時間内に再展開が完了しなかった
+    TimedOut,
+}
+
+// 再展開を開始した時刻と、再展開システムが記録する完了時刻の間で許容する時計のずれ
+pub const CLOCK_SKEW_TOLERANCE_SECS: i64 = 60;
+
+// チームの再展開状況から、requested_atに開始した再展開の結果を判定する。
+// まだ完了していなければNoneを返す。
+pub fn find_redeploy_completion(
+    statuses: &[RedeployStatus],
+    problem_code: &str,
+    requested_at: DateTime<Utc>,
+) -> Option<RedeployCompletion> {
+    let status = statuses
+        .iter()
+        .find(|status| status.problem_code == problem_code)?;
+
+    if status.is_redeploying {
+        return None;
+    }
+
+    // 以前の再展開の完了時刻を、今回の再展開の完了と誤認しないようにする。
+    let threshold = requested_at - chrono::Duration::seconds(CLOCK_SKEW_TOLERANCE_SECS);
+    status
+        .last_redeploy_completed_at
+        .filter(|completed_at| *completed_at >= threshold)
+        .map(|completed_at| match status.last_redeploy_failed {
+            true => RedeployCompletion::Failed { completed_at },
+            false => RedeployCompletion::Completed { completed_at },
+        })
+}
+
+// 再展開が完了するか、requested_atからtimeoutが経過するまで再展開状況をポーリングする。
+// ボットの再起動後に監視を再開した場合も、既に経過した時間はtimeoutに含める。
+// ただし、停止中に完了している可能性があるため、少なくとも一度は再展開状況を確認する。
+pub async fn wait_for_redeploy_completion(
+    service: &(dyn RedeployService + Send + Sync),
+    target: &RedeployTarget,
+    requested_at: DateTime<Utc>,
+    mut intervals: impl Iterator<Item = Duration>,
+    timeout: Duration,
+) -> RedeployCompletion {
+    let elapsed = (Utc::now() - requested_at).to_std().unwrap_or_default();
+    let deadline = tokio::time::Instant::now() + timeout.saturating_sub(elapsed);
+
+    loop {
+        let interval = intervals.next().unwrap_or(timeout);
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        tokio::time::sleep(interval.min(remaining)).await;
+
+        match service.get_status(&target.team_id).await {
+            Ok(statuses) => {
+                match find_redeploy_completion(&statuses, &target.problem_id, requested_at) {
+                    Some(completion) => return completion,
+                    None => tracing::trace!("redeploy is not completed yet"),
+                }
+            },
+            Err(err) => tracing::warn!(?err, "failed to get redeploy status, retry later"),
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            return RedeployCompletion::TimedOut;
+        }
+    }
+}
+
+// 再展開をリクエストしたDiscordユーザー
+#[derive(Debug, Clone)]
+pub struct RedeployRequester {
+    pub user_id: u64,
+    pub user_name: String,
+}
+
+// 再展開のリクエスト結果と、その付帯情報
+#[derive(Debug, Clone)]
+pub struct RedeployRequestEvent<'a> {
+    pub target: &'a RedeployTarget,
+    pub result: &'a RedeployResult<RedeployJob>,
+    pub requester: RedeployRequester,
+
+    // リクエストを受け付けた時刻
+    pub requested_at: DateTime<Utc>,
+
+    // 再展開システムが応答するまでにかかった時間
+    pub latency: Duration,
+
+    // 問題名（問題コードに対応する問題が見つからない場合はNone）
+    pub problem_name: Option<String>,
+
+    // 再展開を実行したメッセージへのリンク
+    pub message_link: Option<String>,
+}
+
+#[async_trait]
+pub trait RedeployNotifier {
+    // 再展開のリクエスト結果を通知する
+    async fn notify(&self, event: &RedeployRequestEvent<'_>);
+
+    // 再展開Jobの完了（またはタイムアウト）を、再展開をリクエストしたユーザと共に通知する
+    async fn notify_completion(
+        &self,
+        target: &RedeployTarget,
+        job: &RedeployJob,
+        completion: &RedeployCompletion,
+        requester: &RedeployRequester,
+    );
+
+    // 再展開システムの障害・復旧をstaffに通知する
+    async fn notify_alert(&self, alert: &RedeployAlert);
+}
+
+// 再展開システムの状態の変化
+#[derive(Debug, Clone)]
+pub enum RedeployAlert {
+    // 失敗が続いたため、再展開システムへのリクエストを一時的に止めた
+    Unavailable {
+        consecutive_failures: u32,
+        retry_at: DateTime<Utc>,
+        last_error: String,
+    },
+    // 再展開システムへのリクエストが再び成功した
+    Recovered,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RedeployError {
+    #[error("invalid parameters")]
+    InvalidParameters,
+    #[error("another job is in queue")]
+    AnotherJobInQueue(String),
+
+    // serde_jsonでserialize/deserializeに失敗した時に出るエラー
+    #[error("serde_json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    // reqwestでHTTP接続に失敗した時に出るエラー
+    #[error("reqwest error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+
+    // 障害により、再展開システムへのリクエストを一時的に止めている時に出るエラー
+    #[error("redeploy system is temporarily unavailable")]
+    Unavailable,
+
+    // なんだかよくわからないエラー
+    #[error("unexpected error occured: {0}")]
+    Unexpected(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+pub struct RState {
+    config: RStateConfig,
+    client: Client,
+}
+
+pub struct RStateConfig {
+    pub baseurl: String,
+    pub username: String,
+    pub password: String,
+    pub problems: Vec<Problem>,
+
+    // 再展開状況を同時に取得する問題の数の上限
+    pub max_concurrency: usize,
+
+    // 再展開状況を取得するリクエストのタイムアウト
+    pub timeout: Duration,
+}
+
+impl RState {
+    pub fn new(config: RStateConfig) -> Result<Self> {
+        let client = ClientBuilder::new()
+            .user_agent("ICTSC Discord Bot")
+            .build()?;
+
+        Ok(Self { config, client })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RStatePostJobRequest<'a> {
+    team_id: &'a str,
+    prob_id: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct RStatePostJobResponse {
+    id: String,
+    team_id: String,
+    prob_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RStateGetRedeployStatusResponse {
+    available: bool,
+    created_time: Option<DateTime<Utc>>,
+    completed_time: Option<DateTime<Utc>>,
+}
+
+#[async_trait]
+impl RedeployService for RState {
+    #[tracing::instrument(skip_all, fields(target = ?target))]
+    async fn redeploy(&self, target: &RedeployTarget) -> RedeployResult<RedeployJob> {
+        tracing::trace!("redeploy request received");
+
+        // RStateでは、問題コードは小文字で取り扱う必要がある。
+        let problem_id = backend_id(&self.config.problems, &target.problem_id);
+
+        let response = self
+            .client
+            .post(format!("{}/admin/postJob", self.config.baseurl))
+            .form(&RStatePostJobRequest {
+                team_id: &target.team_id,
+                prob_id: &problem_id,
+            })
+            .basic_auth(&self.config.username, Some(&self.config.password))
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let response: RStatePostJobResponse =
+                    serde_json::from_slice(response.bytes().await?.as_ref())?;
+
+                Ok(RedeployJob {
+                    id: response.id,
+                    team_id: response.team_id,
+                    problem_code: response.prob_id,
+                })
+            },
+            StatusCode::BAD_REQUEST => {
+                let data = String::from_utf8(response.bytes().await?.to_vec())
+                    .map_err(|err| RedeployError::Unexpected(Box::new(err)))?;
+
+                if data == "BadRequest!" {
+                    return Err(RedeployError::InvalidParameters);
+                }
+
+                Err(RedeployError::AnotherJobInQueue(data))
+            },
+
+            _ => Err(RedeployError::Unexpected(
+                anyhow::anyhow!("unexpected status code: {}", response.status()).into(),
+            )),
+        }
+    }
+
+    // 問題ごとの再展開状況を並行して取得する。
+    // 一部の問題の取得に失敗した場合、その問題は状態不明として扱い、取得できた結果を返す。
+    #[tracing::instrument(skip_all, fields(team_id = ?team_id))]
+    async fn get_status(&self, team_id: &str) -> RedeployResult<RedeployStatusList> {
+        tracing::trace!("get_status request received");
+
+        // async_traitの制約上、クロージャをストリームに保持させずにFutureを先に作っておく。
+        // 再展開できない問題はVMを持たないため、問い合わせない。
+        let requests: Vec<_> = self
+            .config
+            .problems
+            .iter()
+            .filter(|problem| problem.is_redeployable())
+            .map(
+                |problem| async move { (problem, self.get_problem_status(team_id, problem).await) },
+            )
+            .collect();
+
+        collect_problem_statuses(team_id, requests, self.config.max_concurrency).await
+    }
+}
+
+impl RState {
+    async fn get_problem_status(
+        &self,
+        team_id: &str,
+        problem: &Problem,
+    ) -> RedeployResult<RedeployStatus> {
+        let response = self
+            .client
+            .get(format!(
+                "{}/backend/{}/{}",
+                self.config.baseurl,
+                team_id,
+                problem.backend_id()
+            ))
+            .timeout(self.config.timeout)
+            .send()
+            .await?;
+
+        // /backend/statusは常に200を返すので、エラーハンドリングしない
+        let response: RStateGetRedeployStatusResponse =
+            serde_json::from_slice(response.bytes().await?.as_ref())?;
+
+        Ok(RedeployStatus {
+            team_id: team_id.to_string(),
+            problem_code: problem.code.clone(),
+            is_redeploying: !response.available,
+            last_redeploy_started_at: response.created_time,
+            last_redeploy_completed_at: response.completed_time,
+            last_redeploy_failed: false,
+            is_unknown: false,
+        })
+    }
+}
+
+pub struct FakeRedeployService;
+
+#[async_trait]
+impl RedeployService for FakeRedeployService {
+    #[tracing::instrument(skip_all, fields(target = ?target))]
+    async fn redeploy(&self, target: &RedeployTarget) -> RedeployResult<RedeployJob> {
+        tracing::trace!("redeploy request received");
+        Ok(RedeployJob {
+            id: String::from("00000000-0000-0000-0000-000000000000"),
+            team_id: target.team_id.clone(),
+            problem_code: target.problem_id.clone(),
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(team_id = ?team_id))]
+    async fn get_status(&self, team_id: &str) -> RedeployResult<RedeployStatusList> {
+        tracing::trace!("get_status request received");
+
+        let now = Utc::now();
+
+        Ok(vec![
+            RedeployStatus {
+                team_id: team_id.to_string(),
+                problem_code: String::from("ABC"),
+                is_redeploying: false,
+                last_redeploy_started_at: None,
+                last_redeploy_completed_at: None,
+                last_redeploy_failed: false,
+                is_unknown: false,
+            },
+            RedeployStatus {
+                team_id: team_id.to_string(),
+                problem_code: String::from("DEF"),
+                is_redeploying: true,
+                last_redeploy_started_at: Some(now),
+                last_redeploy_completed_at: None,
+                last_redeploy_failed: false,
+                is_unknown: false,
+            },
+            RedeployStatus {
+                team_id: team_id.to_string(),
+                problem_code: String::from("GHI"),
+                is_redeploying: false,
+                last_redeploy_started_at: Some(now),
+                last_redeploy_completed_at: Some(now),
+                last_redeploy_failed: false,
+                is_unknown: false,
+            },
+            RedeployStatus {
+                team_id: team_id.to_string(),
+                problem_code: String::from("JKL"),
+                is_redeploying: false,
+                last_redeploy_started_at: Some(now),
+                last_redeploy_completed_at: Some(now),
+                last_redeploy_failed: true,
+                is_unknown: false,
+            },
+        ])
+    }
+}
+
+#[derive(Debug)]
+pub struct DiscordRedeployNotifier {
+    discord_client: Http,
+    webhook: Webhook,
+}
+
+impl DiscordRedeployNotifier {
+    pub async fn new(token: &str, webhook_url: &str) -> Result<Self> {
+        let discord_client = Http::new(token);
+        let webhook = Webhook::from_url(&discord_client, webhook_url).await?;
+        Ok(Self {
+            discord_client,
+            webhook,
+        })
+    }
+}
+
+#[async_trait]
+impl RedeployNotifier for DiscordRedeployNotifier {
+    #[tracing::instrument(skip_all, fields(target = ?event.target, result = ?event.result))]
+    async fn notify(&self, event: &RedeployRequestEvent<'_>) {
+        if let Err(err) = self._notify(event).await {
+            tracing::error!("failed to notify: {:?}", err)
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(target = ?target, job = ?job, completion = ?completion))]
+    async fn notify_completion(
+        &self,
+        target: &RedeployTarget,
+        job: &RedeployJob,
+        completion: &RedeployCompletion,
+        requester: &RedeployRequester,
+    ) {
+        if let Err(err) = self
+            ._notify_completion(target, job, completion, requester)
+            .await
+        {
+            tracing::error!("failed to notify completion: {:?}", err)
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(alert = ?alert))]
+    async fn notify_alert(&self, alert: &RedeployAlert) {
+        if let Err(err) = self._notify_alert(alert).await {
+            tracing::error!("failed to notify alert: {:?}", err)
+        }
+    }
+}
+
+impl DiscordRedeployNotifier {
+    async fn _notify_alert(&self, alert: &RedeployAlert) -> Result<()> {
+        let embed = match alert {
+            RedeployAlert::Unavailable {
+                consecutive_failures,
+                retry_at,
+                last_error,
+            } => CreateEmbed::new()
+                .title("再展開システム障害")
+                .description("再展開システムへのリクエストが失敗し続けているため、一時的にリクエストを止めています。")
+                .color(Colour::from_rgb(236, 76, 82))
+                .field("連続失敗回数", consecutive_failures.to_string(), true)
+                .field(
+                    "再試行時刻",
+                    retry_at
+                        .with_timezone(&chrono_tz::Asia::Tokyo)
+                        .format("%Y/%m/%d %H:%M:%S")
+                        .to_string(),
+                    true,
+                )
+                .field("最後のエラー", last_error, false),
+            RedeployAlert::Recovered => CreateEmbed::new()
+                .title("再展開システム復旧")
+                .description("再展開システムへのリクエストが再び成功しました。")
+                .color(Colour::from_rgb(40, 167, 65)),
+        };
+
+        self.webhook
+            .execute(
+                &self.discord_client,
+                false,
+                ExecuteWebhook::new().embed(embed),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn _notify_completion(
+        &self,
+        target: &RedeployTarget,
+        job: &RedeployJob,
+        completion: &RedeployCompletion,
+        requester: &RedeployRequester,
+    ) -> Result<()> {
+        let embed = match completion {
+            RedeployCompletion::Completed { completed_at } => CreateEmbed::new()
+                .title("再展開完了")
+                .color(Colour::from_rgb(40, 167, 65))
+                .field("チームID", &target.team_id, true)
+                .field("問題コード", &target.problem_id, true)
+                .field("再展開Job ID", &job.id, true)
+                .field(
+                    "完了時刻",
+                    completed_at
+                        .with_timezone(&chrono_tz::Asia::Tokyo)
+                        .format("%Y/%m/%d %H:%M:%S")
+                        .to_string(),
+                    true,
+                ),
+            RedeployCompletion::Failed { completed_at } => CreateEmbed::new()
+                .title("再展開失敗")
+                .color(Colour::from_rgb(236, 76, 82))
+                .field("チームID", &target.team_id, true)
+                .field("問題コード", &target.problem_id, true)
+                .field("再展開Job ID", &job.id, true)
+                .field(
+                    "完了時刻",
+                    completed_at
+                        .with_timezone(&chrono_tz::Asia::Tokyo)
+                        .format("%Y/%m/%d %H:%M:%S")
+                        .to_string(),
+                    true,
+                ),
+            RedeployCompletion::TimedOut => CreateEmbed::new()
+                .title("再展開タイムアウト")
+                .color(Colour::from_rgb(236, 76, 82))
+                .field("チームID", &target.team_id, true)
+                .field("問題コード", &target.problem_id, true)
+                .field("再展開Job ID", &job.id, true),
+        };
+
+        let requester = if target.triggered_by_staff {
+            format!("<@{}>（staffによるチームの代理）", requester.user_id)
+        } else {
+            format!("<@{}>", requester.user_id)
+        };
+        let embed = embed.field("実行者", requester, true);
+
+        let result = self
+            .webhook
+            .execute(
+                &self.discord_client,
+                false,
+                ExecuteWebhook::new().embed(embed),
+            )
+            .await?;
+
+        if let Some(message) = result {
+            tracing::debug!(message_id = ?message.id, "finished to notify redeploy completion")
+        }
+
+        Ok(())
+    }
+
+    async fn _notify(&self, event: &RedeployRequestEvent<'_>) -> Result<()> {
+        let target = event.target;
+        let problem = match &event.problem_name {
+            Some(problem_name) => format!("{} ({})", problem_name, target.problem_id),
+            None => target.problem_id.clone(),
+        };
+
+        let embed = match event.result {
+            Ok(job) => CreateEmbed::new()
+                .title("再展開リクエスト")
+                .color(Colour::from_rgb(40, 167, 65))
+                .field("チームID", &target.team_id, true)
+                .field("問題", problem, true)
+                .field("再展開Job ID", &job.id, true),
+            Err(err) => CreateEmbed::new()
+                .title("再展開リクエスト失敗")
+                .color(Colour::from_rgb(236, 76, 82))
+                .field("チームID", &target.team_id, true)
+                .field("問題", problem, true)
+                .field("エラー", err.to_string(), false),
+        };
+
+        let requester = if target.triggered_by_staff {
+            format!("<@{}>（staffによるチームの代理）", event.requester.user_id)
+        } else {
+            format!("<@{}>", event.requester.user_id)
+        };
+
+        let embed = embed
+            .field("実行者", requester, true)
+            .field(
+                "リクエスト時刻",
+                event
+                    .requested_at
+                    .with_timezone(&chrono_tz::Asia::Tokyo)
+                    .format("%Y/%m/%d %H:%M:%S")
+                    .to_string(),
+                true,
+            )
+            .field("応答時間", format!("{}ms", event.latency.as_millis()), true);
+
+        let embed = match &target.reason {
+            Some(reason) => embed.field("理由", reason, false),
+            None => embed,
+        };
+
+        let embed = match &event.message_link {
+            Some(message_link) => embed.field("メッセージ", message_link, false),
+            None => embed,
+        };
+
+        let notification = ExecuteWebhook::new().embed(embed);
+
+        let result = self
+            .webhook
+            .execute(&self.discord_client, false, notification)
+            .await?;
+
+        if let Some(message) = result {
+            tracing::debug!(message_id = ?message.id, "finished to notify redeploy event")
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,582 @@
+// This module implements redeploy notifiers for destinations other than Discord.
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use hmac::Hmac;
+use hmac::Mac;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use reqwest::ClientBuilder;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::services::redeploy::render_json_template;
+use crate::services::redeploy::RedeployAlert;
+use crate::services::redeploy::RedeployCompletion;
+use crate::services::redeploy::RedeployJob;
+use crate::services::redeploy::RedeployNotifier;
+use crate::services::redeploy::RedeployRequestEvent;
+use crate::services::redeploy::RedeployRequester;
+use crate::services::redeploy::RedeployTarget;
+
+// 通知先によらない、再展開イベントの内容
+#[derive(Debug, Clone, Serialize)]
+pub struct RedeployNotification {
+    pub event: RedeployEventKind,
+    pub team_id: Option<String>,
+    pub problem_code: Option<String>,
+    pub job_id: Option<String>,
+    pub triggered_by_staff: bool,
+    pub reason: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+
+    // 再展開のリクエストと完了時に設定される
+    pub requester_id: Option<u64>,
+    pub requester_name: Option<String>,
+
+    // 再展開のリクエスト時のみ設定される
+    pub requested_at: Option<DateTime<Utc>>,
+    pub latency_ms: Option<u64>,
+    pub problem_name: Option<String>,
+    pub message_link: Option<String>,
+
+    // 人が読むためのメッセージ
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedeployEventKind {
+    Requested,
+    Failed,
+    Completed,
+    TimedOut,
+    Unavailable,
+    Recovered,
+}
+
+impl RedeployEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedeployEventKind::Requested => "requested",
+            RedeployEventKind::Failed => "failed",
+            RedeployEventKind::Completed => "completed",
+            RedeployEventKind::TimedOut => "timed_out",
+            RedeployEventKind::Unavailable => "unavailable",
+            RedeployEventKind::Recovered => "recovered",
+        }
+    }
+
+    fn is_error(&self) -> bool {
+        matches!(
+            self,
+            RedeployEventKind::Failed
+                | RedeployEventKind::TimedOut
+                | RedeployEventKind::Unavailable
+        )
+    }
+}
+
+impl RedeployNotification {
+    pub fn from_event(request: &RedeployRequestEvent<'_>) -> Self {
+        let target = request.target;
+        let result = request.result;
+        let (event, message) = match result {
+            Ok(_) => (RedeployEventKind::Requested, "再展開を開始しました"),
+            Err(_) => (
+                RedeployEventKind::Failed,
+                "再展開のリクエストに失敗しました",
+            ),
+        };
+        RedeployNotification {
+            event,
+            team_id: Some(target.team_id.clone()),
+            problem_code: Some(target.problem_id.clone()),
+            job_id: result.as_ref().ok().map(|job| job.id.clone()),
+            triggered_by_staff: target.triggered_by_staff,
+            reason: target.reason.clone(),
+            completed_at: None,
+            error: result.as_ref().err().map(|err| err.to_string()),
+            requester_id: Some(request.requester.user_id),
+            requester_name: Some(request.requester.user_name.clone()),
+            requested_at: Some(request.requested_at),
+            latency_ms: Some(request.latency.as_millis() as u64),
+            problem_name: request.problem_name.clone(),
+            message_link: request.message_link.clone(),
+            message: String::from(message),
+        }
+    }
+
+    pub fn from_completion(
+        target: &RedeployTarget,
+        job: &RedeployJob,
+        completion: &RedeployCompletion,
+        requester: &RedeployRequester,
+    ) -> Self {
+        let (event, completed_at, message) = match completion {
+            RedeployCompletion::Completed { completed_at } => (
+                RedeployEventKind::Completed,
+                Some(*completed_at),
+                "再展開が完了しました",
+            ),
+            RedeployCompletion::Failed { completed_at } => (
+                RedeployEventKind::Failed,
+                Some(*completed_at),
+                "再展開に失敗しました",
+            ),
+            RedeployCompletion::TimedOut => (
+                RedeployEventKind::TimedOut,
+                None,
+                "再展開が制限時間内に完了しませんでした",
+            ),
+        };
+        RedeployNotification {
+            event,
+            team_id: Some(target.team_id.clone()),
+            problem_code: Some(target.problem_id.clone()),
+            job_id: Some(job.id.clone()),
+            triggered_by_staff: target.triggered_by_staff,
+            reason: target.reason.clone(),
+            completed_at,
+            error: None,
+            requester_id: Some(requester.user_id),
+            requester_name: Some(requester.user_name.clone()),
+            requested_at: None,
+            latency_ms: None,
+            problem_name: None,
+            message_link: None,
+            message: String::from(message),
+        }
+    }
+
+    pub fn from_alert(alert: &RedeployAlert) -> Self {
+        let (event, error, message) = match alert {
+            RedeployAlert::Unavailable {
+                consecutive_failures,
+                last_error,
+                ..
+            } => (
+                RedeployEventKind::Unavailable,
+                Some(last_error.clone()),
+                format!(
+                    "再展開システムへのリクエストが{}回連続で失敗したため、一時的にリクエストを止めています",
+                    consecutive_failures
+                ),
+            ),
+            RedeployAlert::Recovered => (
+                RedeployEventKind::Recovered,
+                None,
+                String::from("再展開システムへのリクエストが再び成功しました"),
+            ),
+        };
+        RedeployNotification {
+            event,
+            team_id: None,
+            problem_code: None,
+            job_id: None,
+            triggered_by_staff: false,
+            reason: None,
+            completed_at: None,
+            error,
+            requester_id: None,
+            requester_name: None,
+            requested_at: None,
+            latency_ms: None,
+            problem_name: None,
+            message_link: None,
+            message,
+        }
+    }
+
+    // 表示用の項目の一覧
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(team_id) = &self.team_id {
+            fields.push(("チームID", team_id.clone()));
+        }
+        if let Some(problem_code) = &self.problem_code {
+            fields.push(("問題コード", problem_code.clone()));
+        }
+        if let Some(problem_name) = &self.problem_name {
+            fields.push(("問題名", problem_name.clone()));
+        }
+        if let Some(job_id) = &self.job_id {
+            fields.push(("再展開Job ID", job_id.clone()));
+        }
+        if let Some(completed_at) = &self.completed_at {
+            fields.push((
+                "完了時刻",
+                completed_at
+                    .with_timezone(&chrono_tz::Asia::Tokyo)
+                    .format("%Y/%m/%d %H:%M:%S")
+                    .to_string(),
+            ));
+        }
+        match (&self.requester_name, self.triggered_by_staff) {
+            (Some(requester_name), true) => fields.push((
+                "実行者",
+                format!("{}（staffによるチームの代理）", requester_name),
+            )),
+            (Some(requester_name), false) => fields.push(("実行者", requester_name.clone())),
+            (None, true) => fields.push(("実行者", String::from("staff（チームの代理）"))),
+            (None, false) => {},
+        }
+        if let Some(requested_at) = &self.requested_at {
+            fields.push((
+                "リクエスト時刻",
+                requested_at
+                    .with_timezone(&chrono_tz::Asia::Tokyo)
+                    .format("%Y/%m/%d %H:%M:%S")
+                    .to_string(),
+            ));
+        }
+        if let Some(latency_ms) = self.latency_ms {
+            fields.push(("応答時間", format!("{}ms", latency_ms)));
+        }
+        if let Some(reason) = &self.reason {
+            fields.push(("理由", reason.clone()));
+        }
+        if let Some(message_link) = &self.message_link {
+            fields.push(("メッセージ", message_link.clone()));
+        }
+        if let Some(error) = &self.error {
+            fields.push(("エラー", error.clone()));
+        }
+        fields
+    }
+}
+
+// 再展開イベントを1つの形式で送る通知先。
+// RedeployNotifierの各メソッドを、RedeployNotificationを送る処理にまとめる。
+#[async_trait]
+trait NotificationSink {
+    async fn send(&self, notification: &RedeployNotification) -> Result<()>;
+}
+
+#[async_trait]
+impl<T> RedeployNotifier for T
+where
+    T: NotificationSink + Send + Sync,
+{
+    async fn notify(&self, event: &RedeployRequestEvent<'_>) {
+        let notification = RedeployNotification::from_event(event);
+        if let Err(err) = self.send(&notification).await {
+            tracing::error!("failed to notify: {:?}", err)
+        }
+    }
+
+    async fn notify_completion(
+        &self,
+        target: &RedeployTarget,
+        job: &RedeployJob,
+        completion: &RedeployCompletion,
+        requester: &RedeployRequester,
+    ) {
+        let notification =
+            RedeployNotification::from_completion(target, job, completion, requester);
+        if let Err(err) = self.send(&notification).await {
+            tracing::error!("failed to notify completion: {:?}", err)
+        }
+    }
+
+    async fn notify_alert(&self, alert: &RedeployAlert) {
+        let notification = RedeployNotification::from_alert(alert);
+        if let Err(err) = self.send(&notification).await {
+            tracing::error!("failed to notify alert: {:?}", err)
+        }
+    }
+}
+
+fn build_client() -> Result<Client> {
+    Ok(ClientBuilder::new()
+        .user_agent("ICTSC Discord Bot")
+        .build()?)
+}
+
+// SlackのIncoming Webhookに通知する
+pub struct SlackRedeployNotifier {
+    client: Client,
+    webhook_url: String,
+}
+
+impl SlackRedeployNotifier {
+    pub fn new(webhook_url: &str) -> Result<Self> {
+        Ok(Self {
+            client: build_client()?,
+            webhook_url: webhook_url.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationSink for SlackRedeployNotifier {
+    #[tracing::instrument(skip_all, fields(event = ?notification.event))]
+    async fn send(&self, notification: &RedeployNotification) -> Result<()> {
+        let color = if notification.event.is_error() {
+            "#ec4c52"
+        } else {
+            "#28a741"
+        };
+
+        let fields: Vec<_> = notification
+            .fields()
+            .into_iter()
+            .map(|(title, value)| {
+                serde_json::json!({
+                    "title": title,
+                    "value": value,
+                    "short": title != "エラー" && title != "理由",
+                })
+            })
+            .collect();
+
+        let payload = serde_json::json!({
+            "text": notification.message,
+            "attachments": [{
+                "color": color,
+                "fallback": notification.message,
+                "fields": fields,
+            }],
+        });
+
+        self.client
+            .post(&self.webhook_url)
+            .json(&payload)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+// 任意のHTTPエンドポイントにJSONで通知する
+pub struct WebhookRedeployNotifier {
+    client: Client,
+    config: WebhookRedeployNotifierConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookRedeployNotifierConfig {
+    pub url: String,
+
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    // ボディのテンプレート。{event}, {team_id}, {problem_code}, {job_id}, {triggered_by_staff},
+    // {completed_at}, {error}, {reason}, {requester_id}, {requester_name}, {requested_at}, {latency_ms},
+    // {problem_name}, {message_link}, {message} が置換される。値はJSON文字列としてエスケープされるため、
+    // "{team_id}" のように引用符で囲んで使う。省略した場合はRedeployNotificationをそのまま送る。
+    pub template: Option<String>,
+
+    // 指定した場合、ボディのHMAC-SHA256署名をヘッダに付与する。
+    pub secret: Option<String>,
+
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+}
+
+fn default_signature_header() -> String {
+    String::from("X-Signature-256")
+}
+
+impl WebhookRedeployNotifier {
+    pub fn new(config: WebhookRedeployNotifierConfig) -> Result<Self> {
+        Ok(Self {
+            client: build_client()?,
+            config,
+        })
+    }
+
+    fn render(&self, notification: &RedeployNotification) -> Result<String> {
+        let template = match &self.config.template {
+            Some(template) => template,
+            None => return Ok(serde_json::to_string(notification)?),
+        };
+
+        let vars = [
+            ("event", notification.event.as_str().to_string()),
+            ("team_id", notification.team_id.clone().unwrap_or_default()),
+            (
+                "problem_code",
+                notification.problem_code.clone().unwrap_or_default(),
+            ),
+            ("job_id", notification.job_id.clone().unwrap_or_default()),
+            (
+                "triggered_by_staff",
+                notification.triggered_by_staff.to_string(),
+            ),
+            (
+                "completed_at",
+                notification
+                    .completed_at
+                    .map(|completed_at| completed_at.to_rfc3339())
+                    .unwrap_or_default(),
+            ),
+            ("error", notification.error.clone().unwrap_or_default()),
+            ("reason", notification.reason.clone().unwrap_or_default()),
+            (
+                "requester_id",
+                notification
+                    .requester_id
+                    .map(|requester_id| requester_id.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "requester_name",
+                notification.requester_name.clone().unwrap_or_default(),
+            ),
+            (
+                "requested_at",
+                notification
+                    .requested_at
+                    .map(|requested_at| requested_at.to_rfc3339())
+                    .unwrap_or_default(),
+            ),
+            (
+                "latency_ms",
+                notification
+                    .latency_ms
+                    .map(|latency_ms| latency_ms.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "problem_name",
+                notification.problem_name.clone().unwrap_or_default(),
+            ),
+            (
+                "message_link",
+                notification.message_link.clone().unwrap_or_default(),
+            ),
+            ("message", notification.message.clone()),
+        ];
+
+        let vars: Vec<_> = vars
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        Ok(render_json_template(template, &vars))
+    }
+}
+
+// ボディのHMAC-SHA256署名を "sha256=<hex>" の形式で返す
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(body);
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", signature)
+}
+
+#[async_trait]
+impl NotificationSink for WebhookRedeployNotifier {
+    #[tracing::instrument(skip_all, fields(event = ?notification.event))]
+    async fn send(&self, notification: &RedeployNotification) -> Result<()> {
+        let body = self.render(notification)?;
+
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .header(CONTENT_TYPE, "application/json");
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+        if let Some(secret) = &self.config.secret {
+            request = request.header(&self.config.signature_header, sign(secret, body.as_bytes()));
+        }
+
+        request.body(body).send().await?.error_for_status()?;
+
+        Ok(())
+    }
+}
+
+// 構造化ログとして出力する
+pub struct LogRedeployNotifier;
+
+#[async_trait]
+impl NotificationSink for LogRedeployNotifier {
+    async fn send(&self, notification: &RedeployNotification) -> Result<()> {
+        tracing::info!(
+            target: "redeploy_event",
+            event = notification.event.as_str(),
+            team_id = notification.team_id,
+            problem_code = notification.problem_code,
+            job_id = notification.job_id,
+            triggered_by_staff = notification.triggered_by_staff,
+            reason = notification.reason,
+            completed_at = ?notification.completed_at,
+            error = notification.error,
+            requester_id = notification.requester_id,
+            requester_name = notification.requester_name,
+            latency_ms = notification.latency_ms,
+            problem_name = notification.problem_name,
+            message_link = notification.message_link,
+            "{}",
+            notification.message
+        );
+        Ok(())
+    }
+}
+
+// 再展開イベントを、追記のみのJSON Lines形式の監査ログとして記録する
+pub struct AuditLogRedeployNotifier {
+    path: PathBuf,
+
+    // 複数のイベントの書き込みが混ざらないようにする
+    lock: Mutex<()>,
+}
+
+#[derive(Serialize)]
+struct AuditLogEntry<'a> {
+    logged_at: DateTime<Utc>,
+
+    #[serde(flatten)]
+    notification: &'a RedeployNotification,
+}
+
+impl AuditLogRedeployNotifier {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for AuditLogRedeployNotifier {
+    #[tracing::instrument(skip_all, fields(event = ?notification.event))]
+    async fn send(&self, notification: &RedeployNotification) -> Result<()> {
+        let mut line = serde_json::to_string(&AuditLogEntry {
+            logged_at: Utc::now(),
+            notification,
+        })?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+}
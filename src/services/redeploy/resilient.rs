@@ -0,0 +1,215 @@
+// This module wraps a redeploy service with retries and a circuit breaker.
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rand::Rng;
+use tokio::time::Instant;
+
+use crate::services::redeploy::RedeployAlert;
+use crate::services::redeploy::RedeployError;
+use crate::services::redeploy::RedeployJob;
+use crate::services::redeploy::RedeployNotifier;
+use crate::services::redeploy::RedeployResult;
+use crate::services::redeploy::RedeployService;
+use crate::services::redeploy::RedeployStatusList;
+use crate::services::redeploy::RedeployTarget;
+
+pub struct ResilientRedeployConfig {
+    // get_statusを試行する回数の上限（初回を含む）
+    pub max_attempts: u32,
+
+    // 再試行までの待ち時間の初期値と最大値。試行するたびに倍にしていく。
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+
+    // この回数だけ連続して失敗すると、リクエストを一時的に止める。
+    pub failure_threshold: u32,
+
+    // リクエストを止めておく時間
+    pub open_duration: Duration,
+}
+
+#[derive(Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+
+    // リクエストを止めている場合、その期限
+    open_until: Option<Instant>,
+}
+
+// 一時的な障害に備えて、再試行とサーキットブレーカーを備えたRedeployService
+pub struct ResilientRedeployService {
+    inner: Box<dyn RedeployService + Send + Sync>,
+    config: ResilientRedeployConfig,
+    notifiers: Arc<Vec<Box<dyn RedeployNotifier + Send + Sync>>>,
+    circuit: Mutex<CircuitState>,
+
+    // 期限を過ぎた後に、試しに通したリクエストが実行中か
+    probing: AtomicBool,
+}
+
+// 試しに通したリクエストが終わったら、次のリクエストを試せるようにする。
+// リクエストがキャンセルされた場合にも解除されるよう、dropで解除する。
+struct ProbeGuard<'a> {
+    probing: Option<&'a AtomicBool>,
+}
+
+impl Drop for ProbeGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(probing) = self.probing {
+            probing.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+impl ResilientRedeployService {
+    pub fn new(
+        inner: Box<dyn RedeployService + Send + Sync>,
+        config: ResilientRedeployConfig,
+        notifiers: Arc<Vec<Box<dyn RedeployNotifier + Send + Sync>>>,
+    ) -> Self {
+        Self {
+            inner,
+            config,
+            notifiers,
+            circuit: Mutex::new(CircuitState::default()),
+            probing: AtomicBool::new(false),
+        }
+    }
+
+    // リクエストを止めている間は、再展開システムに問い合わせずにエラーを返す。
+    // 期限を過ぎた後は、試しに1つだけリクエストを通し、その結果が出るまで他のリクエストはエラーにする。
+    fn check_circuit(&self) -> RedeployResult<ProbeGuard<'_>> {
+        let circuit = self.circuit.lock().unwrap_or_else(|err| err.into_inner());
+        match circuit.open_until {
+            None => Ok(ProbeGuard { probing: None }),
+            Some(open_until) if Instant::now() < open_until => Err(RedeployError::Unavailable),
+            Some(_) => {
+                match self
+                    .probing
+                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                {
+                    Ok(_) => Ok(ProbeGuard {
+                        probing: Some(&self.probing),
+                    }),
+                    Err(_) => Err(RedeployError::Unavailable),
+                }
+            },
+        }
+    }
+
+    fn record_result<T>(&self, result: &RedeployResult<T>) {
+        let alert = {
+            let mut circuit = self.circuit.lock().unwrap_or_else(|err| err.into_inner());
+            match result {
+                Err(err) if is_transient(err) => {
+                    circuit.consecutive_failures += 1;
+                    if circuit.consecutive_failures < self.config.failure_threshold {
+                        return;
+                    }
+
+                    let was_open = circuit.open_until.is_some();
+                    circuit.open_until = Some(Instant::now() + self.config.open_duration);
+                    if was_open {
+                        return;
+                    }
+
+                    tracing::error!(
+                        consecutive_failures = circuit.consecutive_failures,
+                        "redeploy system seems to be down, open circuit"
+                    );
+                    RedeployAlert::Unavailable {
+                        consecutive_failures: circuit.consecutive_failures,
+                        retry_at: Utc::now()
+                            + chrono::Duration::from_std(self.config.open_duration)
+                                .unwrap_or_else(|_| chrono::Duration::zero()),
+                        last_error: err.to_string(),
+                    }
+                },
+                // 再展開システムから応答があれば、障害から復旧したとみなす。
+                _ => {
+                    let was_open = circuit.open_until.is_some();
+                    *circuit = CircuitState::default();
+                    if !was_open {
+                        return;
+                    }
+
+                    tracing::info!("redeploy system recovered, close circuit");
+                    RedeployAlert::Recovered
+                },
+            }
+        };
+
+        let notifiers = self.notifiers.clone();
+        tokio::spawn(async move {
+            for notifier in notifiers.iter() {
+                notifier.notify_alert(&alert).await;
+            }
+        });
+    }
+
+    // 試行回数に応じた待ち時間に、ランダムな揺らぎを加える。
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.config.max_backoff);
+        let half = backoff / 2;
+        half + half.mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+// 再展開システムの障害とみなすエラーか？
+// リクエストの内容に起因するエラーは、再試行しても結果が変わらないため含めない。
+fn is_transient(err: &RedeployError) -> bool {
+    matches!(
+        err,
+        RedeployError::Reqwest(_) | RedeployError::Json(_) | RedeployError::Unexpected(_)
+    )
+}
+
+#[async_trait]
+impl RedeployService for ResilientRedeployService {
+    // Jobが重複して作成されるのを防ぐため、再展開のリクエストは再試行しない。
+    #[tracing::instrument(skip_all, fields(target = ?target))]
+    async fn redeploy(&self, target: &RedeployTarget) -> RedeployResult<RedeployJob> {
+        let _probe = self.check_circuit()?;
+
+        let result = self.inner.redeploy(target).await;
+        self.record_result(&result);
+        result
+    }
+
+    #[tracing::instrument(skip_all, fields(team_id = ?team_id))]
+    async fn get_status(&self, team_id: &str) -> RedeployResult<RedeployStatusList> {
+        let _probe = self.check_circuit()?;
+
+        let mut attempt = 1;
+        let result = loop {
+            let result = self.inner.get_status(team_id).await;
+            match &result {
+                Err(err) if is_transient(err) && attempt < self.config.max_attempts => {
+                    let backoff = self.backoff(attempt);
+                    tracing::warn!(
+                        ?err,
+                        attempt,
+                        ?backoff,
+                        "failed to get redeploy status, retry"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                },
+                _ => break result,
+            }
+        };
+
+        self.record_result(&result);
+        result
+    }
+}
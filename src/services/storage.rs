@@ -0,0 +1,1154 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use rusqlite::types::FromSql;
+use rusqlite::types::FromSqlError;
+use rusqlite::types::FromSqlResult;
+use rusqlite::types::ToSqlOutput;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use rusqlite::ToSql;
+use serde::Deserialize;
+use serde::Serialize;
+
+// 再展開リクエストの記録
+#[derive(Debug, Clone, Serialize)]
+pub struct RedeployRecord {
+    pub requested_at: DateTime<Utc>,
+    pub team_id: String,
+    pub problem_code: String,
+    pub user_id: u64,
+    pub user_name: String,
+
+    // staffがチームの代わりに再展開を実行したかを表すフラグ
+    pub triggered_by_staff: bool,
+
+    // 再展開システムが受け付けたJobのID。リクエストに失敗した場合はNone
+    pub job_id: Option<String>,
+
+    // リクエストに失敗した場合のエラー
+    pub error: Option<String>,
+
+    // 再展開が完了した時刻
+    pub completed_at: Option<DateTime<Utc>>,
+
+    // 制限時間内に再展開が完了しなかったかを表すフラグ
+    pub timed_out: bool,
+}
+
+// /askで作成された質問スレッドの記録
+#[derive(Debug, Clone, Serialize)]
+pub struct QuestionRecord {
+    pub created_at: DateTime<Utc>,
+    pub team_id: Option<String>,
+    pub user_id: u64,
+    pub user_name: String,
+    pub title: String,
+    pub channel_id: u64,
+    pub thread_id: u64,
+}
+
+// 質問スレッドの対応状況
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionStatus {
+    // staffの対応待ち
+    Open,
+    // staffが対応中
+    Claimed,
+    // staffが回答済み
+    Answered,
+    // /archiveでスレッドが終了された
+    Closed,
+}
+
+impl QuestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionStatus::Open => "open",
+            QuestionStatus::Claimed => "claimed",
+            QuestionStatus::Answered => "answered",
+            QuestionStatus::Closed => "closed",
+        }
+    }
+
+    // Discord上に表示する名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            QuestionStatus::Open => "未対応",
+            QuestionStatus::Claimed => "対応中",
+            QuestionStatus::Answered => "回答済み",
+            QuestionStatus::Closed => "終了",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(QuestionStatus::Open),
+            "claimed" => Some(QuestionStatus::Claimed),
+            "answered" => Some(QuestionStatus::Answered),
+            "closed" => Some(QuestionStatus::Closed),
+            _ => None,
+        }
+    }
+}
+
+impl ToSql for QuestionStatus {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for QuestionStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let value = value.as_str()?;
+        QuestionStatus::parse(value).ok_or_else(|| FromSqlError::Other(value.into()))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuestionTicketError {
+    #[error("question thread is already closed")]
+    AlreadyClosed,
+}
+
+// 質問スレッドのチケット。botの再起動後もボタンを処理できるよう、ストレージに保存する。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuestionTicket {
+    pub thread_id: u64,
+    pub status: QuestionStatus,
+
+    // 対応しているstaff。誰も対応していない場合はNone
+    pub assignee_id: Option<u64>,
+    pub assignee_name: Option<String>,
+
+    // 対応状況を表示しているbotのメッセージのID
+    pub message_id: Option<u64>,
+    pub updated_at: DateTime<Utc>,
+
+    // staffがスレッドに最後に投稿した時刻。まだ投稿していない場合はNone
+    pub last_staff_reply_at: Option<DateTime<Utc>>,
+
+    // 回答がないことをstaffに再通知した回数。チームが再び回答を待ち始めると0に戻る。
+    pub escalation_count: u32,
+
+    // チームがstaffの回答を待ち始めた時刻。
+    // staffが投稿するとNoneになり、その後にチームが投稿した時刻から再び待ち始める。
+    pub waiting_since: Option<DateTime<Utc>>,
+}
+
+impl QuestionTicket {
+    pub fn open(thread_id: u64, message_id: Option<u64>, now: DateTime<Utc>) -> Self {
+        QuestionTicket {
+            thread_id,
+            status: QuestionStatus::Open,
+            assignee_id: None,
+            assignee_name: None,
+            message_id,
+            updated_at: now,
+            last_staff_reply_at: None,
+            escalation_count: 0,
+            waiting_since: Some(now),
+        }
+    }
+
+    // staffが質問の担当になる。他のstaffが担当している場合は引き継ぐ。
+    pub fn claim(
+        &mut self,
+        user_id: u64,
+        user_name: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), QuestionTicketError> {
+        self.ensure_not_closed()?;
+        self.status = QuestionStatus::Claimed;
+        self.assignee_id = Some(user_id);
+        self.assignee_name = Some(user_name.to_string());
+        self.updated_at = now;
+        Ok(())
+    }
+
+    // 質問を回答済みにする。担当者がいない場合は、回答したstaffを担当者とする。
+    pub fn resolve(
+        &mut self,
+        user_id: u64,
+        user_name: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), QuestionTicketError> {
+        self.ensure_not_closed()?;
+        self.status = QuestionStatus::Answered;
+        if self.assignee_id.is_none() {
+            self.assignee_id = Some(user_id);
+            self.assignee_name = Some(user_name.to_string());
+        }
+        self.updated_at = now;
+        Ok(())
+    }
+
+    pub fn close(&mut self, now: DateTime<Utc>) {
+        self.status = QuestionStatus::Closed;
+        self.updated_at = now;
+    }
+
+    fn ensure_not_closed(&self) -> Result<(), QuestionTicketError> {
+        if self.status == QuestionStatus::Closed {
+            return Err(QuestionTicketError::AlreadyClosed);
+        }
+        Ok(())
+    }
+}
+
+// /joinによるロールの付与の記録
+#[derive(Debug, Clone, Serialize)]
+pub struct JoinRecord {
+    pub joined_at: DateTime<Utc>,
+    pub user_id: u64,
+    pub user_name: String,
+
+    // 参加したチームのID。staffとして参加した場合はNone
+    pub team_id: Option<String>,
+    pub role_name: String,
+}
+
+// staffによる再展開の回数制限のリセットの記録
+#[derive(Debug, Clone, Serialize)]
+pub struct RedeployLimitResetRecord {
+    pub reset_at: DateTime<Utc>,
+    pub team_id: String,
+
+    // リセットした問題の問題コード。全ての問題をリセットした場合はNone
+    pub problem_code: Option<String>,
+    pub user_id: u64,
+    pub user_name: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("record not found: {0}")]
+    NotFound(i64),
+
+    #[error("storage task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+// コンテスト中の操作履歴を永続化するためのストレージ
+#[async_trait]
+pub trait Storage {
+    // 再展開リクエストを記録し、記録のIDを返す
+    async fn record_redeploy(&self, record: &RedeployRecord) -> StorageResult<i64>;
+
+    // 再展開の完了（またはタイムアウト）を記録する
+    async fn record_redeploy_completion(
+        &self,
+        id: i64,
+        completed_at: Option<DateTime<Utc>>,
+        timed_out: bool,
+    ) -> StorageResult<()>;
+
+    // チームが再展開を受け付けられた回数を返す。
+    // staffによる代理実行と、since以前のリクエストは数えない。
+    async fn count_redeploys(
+        &self,
+        team_id: &str,
+        problem_code: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> StorageResult<u32>;
+
+    async fn record_redeploy_limit_reset(
+        &self,
+        record: &RedeployLimitResetRecord,
+    ) -> StorageResult<()>;
+
+    // 指定された問題の回数制限が最後にリセットされた時刻を返す
+    async fn last_redeploy_limit_reset(
+        &self,
+        team_id: &str,
+        problem_code: &str,
+    ) -> StorageResult<Option<DateTime<Utc>>>;
+
+    // 再展開の確認ボタンが使われたことを記録する。
+    // 初めて使われた場合はtrueを、既に使われていた場合はfalseを返す。
+    // 有効期限を過ぎた記録は、ボタン自体が使えなくなるため、nowの時点で削除する。
+    async fn consume_redeploy_confirmation(
+        &self,
+        key: &str,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> StorageResult<bool>;
+
+    async fn record_question(&self, record: &QuestionRecord) -> StorageResult<()>;
+    async fn record_join(&self, record: &JoinRecord) -> StorageResult<()>;
+
+    // 質問スレッドのチケットを保存する。同じスレッドのチケットがあれば上書きする。
+    // ただし、再通知の状態（last_staff_reply_at, escalation_count, waiting_since）は上書きせず、
+    // 以下の専用の更新で変更する。
+    async fn save_question_ticket(&self, ticket: &QuestionTicket) -> StorageResult<()>;
+
+    // チームが質問スレッドに投稿した時刻を記録する。チケットがない場合は何もしない。
+    // staffの最後の投稿の後の最初の投稿であれば、その時刻から回答を待ち始め、再通知の回数を0に戻す。
+    async fn record_question_team_message(
+        &self,
+        thread_id: u64,
+        sent_at: DateTime<Utc>,
+    ) -> StorageResult<()>;
+
+    // staffが質問スレッドに投稿した時刻を記録し、回答待ちを解除する。チケットがない場合は何もしない。
+    async fn record_question_staff_reply(
+        &self,
+        thread_id: u64,
+        replied_at: DateTime<Utc>,
+    ) -> StorageResult<()>;
+
+    // 質問をstaffに再通知した回数を記録する。既により大きい回数が記録されている場合は何もしない。
+    async fn record_question_escalation(
+        &self,
+        thread_id: u64,
+        escalation_count: u32,
+    ) -> StorageResult<()>;
+    async fn get_question_ticket(&self, thread_id: u64) -> StorageResult<Option<QuestionTicket>>;
+    async fn list_question_tickets(&self) -> StorageResult<Vec<QuestionTicket>>;
+
+    // 再展開Jobを開始したが、完了もタイムアウトも記録されていない再展開リクエストを、記録のIDとともに返す
+    async fn list_pending_redeploys(&self) -> StorageResult<Vec<(i64, RedeployRecord)>>;
+
+    async fn list_redeploys(&self) -> StorageResult<Vec<RedeployRecord>>;
+    async fn list_questions(&self) -> StorageResult<Vec<QuestionRecord>>;
+    async fn list_joins(&self) -> StorageResult<Vec<JoinRecord>>;
+}
+
+// SQLiteに履歴を保存するストレージ
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    pub fn open<P: AsRef<Path>>(path: P) -> StorageResult<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> StorageResult<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> StorageResult<Self> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS redeploys (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                requested_at TEXT NOT NULL,
+                team_id TEXT NOT NULL,
+                problem_code TEXT NOT NULL,
+                user_id INTEGER NOT NULL,
+                user_name TEXT NOT NULL,
+                triggered_by_staff INTEGER NOT NULL,
+                job_id TEXT,
+                error TEXT,
+                completed_at TEXT,
+                timed_out INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS redeploy_limit_resets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                reset_at TEXT NOT NULL,
+                team_id TEXT NOT NULL,
+                problem_code TEXT,
+                user_id INTEGER NOT NULL,
+                user_name TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS redeploy_confirmations (
+                key TEXT PRIMARY KEY,
+                expires_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS questions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                team_id TEXT,
+                user_id INTEGER NOT NULL,
+                user_name TEXT NOT NULL,
+                title TEXT NOT NULL,
+                channel_id INTEGER NOT NULL,
+                thread_id INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS joins (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                joined_at TEXT NOT NULL,
+                user_id INTEGER NOT NULL,
+                user_name TEXT NOT NULL,
+                team_id TEXT,
+                role_name TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS question_tickets (
+                thread_id INTEGER PRIMARY KEY,
+                status TEXT NOT NULL,
+                assignee_id INTEGER,
+                assignee_name TEXT,
+                message_id INTEGER,
+                updated_at TEXT NOT NULL,
+                last_staff_reply_at TEXT,
+                escalation_count INTEGER NOT NULL DEFAULT 0,
+                waiting_since TEXT
+            );",
+        )?;
+
+        // 後から追加した列は、CREATE TABLE IF NOT EXISTSでは既存のデータベースに反映されないため、個別に追加する。
+        add_column_if_missing(
+            &connection,
+            "question_tickets",
+            "last_staff_reply_at",
+            "TEXT",
+        )?;
+        add_column_if_missing(
+            &connection,
+            "question_tickets",
+            "escalation_count",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        if add_column_if_missing(&connection, "question_tickets", "waiting_since", "TEXT")? {
+            // staffがまだ投稿していない質問は、質問の作成時から回答を待っているものとする。
+            connection.execute_batch(
+                "UPDATE question_tickets SET waiting_since = (
+                    SELECT created_at FROM questions
+                    WHERE questions.thread_id = question_tickets.thread_id
+                ) WHERE last_staff_reply_at IS NULL",
+            )?;
+        }
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    // rusqliteは同期APIのため、ブロッキング処理用のスレッドで実行する。
+    async fn with_connection<F, T>(&self, f: F) -> StorageResult<T>
+    where
+        F: FnOnce(&Connection) -> StorageResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let connection = connection.lock().unwrap_or_else(|err| err.into_inner());
+            f(&connection)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn record_redeploy(&self, record: &RedeployRecord) -> StorageResult<i64> {
+        let record = record.clone();
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO redeploys (
+                    requested_at, team_id, problem_code, user_id, user_name,
+                    triggered_by_staff, job_id, error, completed_at, timed_out
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![
+                    record.requested_at,
+                    record.team_id,
+                    record.problem_code,
+                    record.user_id as i64,
+                    record.user_name,
+                    record.triggered_by_staff,
+                    record.job_id,
+                    record.error,
+                    record.completed_at,
+                    record.timed_out,
+                ],
+            )?;
+            Ok(connection.last_insert_rowid())
+        })
+        .await
+    }
+
+    async fn record_redeploy_completion(
+        &self,
+        id: i64,
+        completed_at: Option<DateTime<Utc>>,
+        timed_out: bool,
+    ) -> StorageResult<()> {
+        self.with_connection(move |connection| {
+            let updated = connection.execute(
+                "UPDATE redeploys SET completed_at = ?1, timed_out = ?2 WHERE id = ?3",
+                rusqlite::params![completed_at, timed_out, id],
+            )?;
+            if updated == 0 {
+                return Err(StorageError::NotFound(id));
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn count_redeploys(
+        &self,
+        team_id: &str,
+        problem_code: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> StorageResult<u32> {
+        let team_id = team_id.to_string();
+        let problem_code = problem_code.to_string();
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT requested_at FROM redeploys
+                WHERE team_id = ?1 AND problem_code = ?2
+                    AND triggered_by_staff = 0 AND job_id IS NOT NULL",
+            )?;
+            let requested_ats = statement
+                .query_map(rusqlite::params![team_id, problem_code], |row| {
+                    row.get::<_, DateTime<Utc>>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            let count = requested_ats
+                .into_iter()
+                .filter(|requested_at| since.is_none_or(|since| *requested_at > since))
+                .count();
+            Ok(count as u32)
+        })
+        .await
+    }
+
+    async fn record_redeploy_limit_reset(
+        &self,
+        record: &RedeployLimitResetRecord,
+    ) -> StorageResult<()> {
+        let record = record.clone();
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO redeploy_limit_resets (
+                    reset_at, team_id, problem_code, user_id, user_name
+                ) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    record.reset_at,
+                    record.team_id,
+                    record.problem_code,
+                    record.user_id as i64,
+                    record.user_name,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn last_redeploy_limit_reset(
+        &self,
+        team_id: &str,
+        problem_code: &str,
+    ) -> StorageResult<Option<DateTime<Utc>>> {
+        let team_id = team_id.to_string();
+        let problem_code = problem_code.to_string();
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT reset_at FROM redeploy_limit_resets
+                WHERE team_id = ?1 AND (problem_code = ?2 OR problem_code IS NULL)",
+            )?;
+            let reset_ats = statement
+                .query_map(rusqlite::params![team_id, problem_code], |row| {
+                    row.get::<_, DateTime<Utc>>(0)
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(reset_ats.into_iter().max())
+        })
+        .await
+    }
+
+    async fn consume_redeploy_confirmation(
+        &self,
+        key: &str,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> StorageResult<bool> {
+        let key = key.to_string();
+        self.with_connection(move |connection| {
+            connection.execute(
+                "DELETE FROM redeploy_confirmations WHERE expires_at < ?1",
+                rusqlite::params![now.timestamp()],
+            )?;
+            let inserted = connection.execute(
+                "INSERT OR IGNORE INTO redeploy_confirmations (key, expires_at) VALUES (?1, ?2)",
+                rusqlite::params![key, expires_at.timestamp()],
+            )?;
+            Ok(inserted == 1)
+        })
+        .await
+    }
+
+    async fn record_question(&self, record: &QuestionRecord) -> StorageResult<()> {
+        let record = record.clone();
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO questions (
+                    created_at, team_id, user_id, user_name, title, channel_id, thread_id
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    record.created_at,
+                    record.team_id,
+                    record.user_id as i64,
+                    record.user_name,
+                    record.title,
+                    record.channel_id as i64,
+                    record.thread_id as i64,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_join(&self, record: &JoinRecord) -> StorageResult<()> {
+        let record = record.clone();
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO joins (joined_at, user_id, user_name, team_id, role_name)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    record.joined_at,
+                    record.user_id as i64,
+                    record.user_name,
+                    record.team_id,
+                    record.role_name,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn save_question_ticket(&self, ticket: &QuestionTicket) -> StorageResult<()> {
+        let ticket = ticket.clone();
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO question_tickets (
+                    thread_id, status, assignee_id, assignee_name, message_id, updated_at,
+                    last_staff_reply_at, escalation_count, waiting_since
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT (thread_id) DO UPDATE SET
+                    status = excluded.status,
+                    assignee_id = excluded.assignee_id,
+                    assignee_name = excluded.assignee_name,
+                    message_id = excluded.message_id,
+                    updated_at = excluded.updated_at",
+                rusqlite::params![
+                    ticket.thread_id as i64,
+                    ticket.status,
+                    ticket.assignee_id.map(|id| id as i64),
+                    ticket.assignee_name,
+                    ticket.message_id.map(|id| id as i64),
+                    ticket.updated_at,
+                    ticket.last_staff_reply_at,
+                    ticket.escalation_count,
+                    ticket.waiting_since,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_question_team_message(
+        &self,
+        thread_id: u64,
+        sent_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        self.with_connection(move |connection| {
+            connection.execute(
+                "UPDATE question_tickets SET waiting_since = ?2, escalation_count = 0
+                WHERE thread_id = ?1 AND waiting_since IS NULL",
+                rusqlite::params![thread_id as i64, sent_at],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_question_staff_reply(
+        &self,
+        thread_id: u64,
+        replied_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        self.with_connection(move |connection| {
+            connection.execute(
+                "UPDATE question_tickets SET last_staff_reply_at = ?2, waiting_since = NULL
+                WHERE thread_id = ?1",
+                rusqlite::params![thread_id as i64, replied_at],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn record_question_escalation(
+        &self,
+        thread_id: u64,
+        escalation_count: u32,
+    ) -> StorageResult<()> {
+        self.with_connection(move |connection| {
+            connection.execute(
+                "UPDATE question_tickets SET escalation_count = MAX(escalation_count, ?2)
+                WHERE thread_id = ?1",
+                rusqlite::params![thread_id as i64, escalation_count],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_question_ticket(&self, thread_id: u64) -> StorageResult<Option<QuestionTicket>> {
+        self.with_connection(move |connection| {
+            let mut statement = connection.prepare(
+                "SELECT thread_id, status, assignee_id, assignee_name, message_id, updated_at,
+                    last_staff_reply_at, escalation_count, waiting_since
+                FROM question_tickets WHERE thread_id = ?1",
+            )?;
+            let mut tickets = statement
+                .query_map(
+                    rusqlite::params![thread_id as i64],
+                    question_ticket_from_row,
+                )?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(tickets.pop())
+        })
+        .await
+    }
+
+    async fn list_question_tickets(&self) -> StorageResult<Vec<QuestionTicket>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT thread_id, status, assignee_id, assignee_name, message_id, updated_at,
+                    last_staff_reply_at, escalation_count, waiting_since
+                FROM question_tickets ORDER BY thread_id",
+            )?;
+            let tickets = statement
+                .query_map([], question_ticket_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(tickets)
+        })
+        .await
+    }
+
+    async fn list_pending_redeploys(&self) -> StorageResult<Vec<(i64, RedeployRecord)>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT id, requested_at, team_id, problem_code, user_id, user_name,
+                    triggered_by_staff, job_id, error, completed_at, timed_out
+                FROM redeploys
+                WHERE job_id IS NOT NULL AND completed_at IS NULL AND timed_out = 0
+                ORDER BY id",
+            )?;
+            let records = statement
+                .query_map([], |row| {
+                    let record = RedeployRecord {
+                        requested_at: row.get(1)?,
+                        team_id: row.get(2)?,
+                        problem_code: row.get(3)?,
+                        user_id: row.get::<_, i64>(4)? as u64,
+                        user_name: row.get(5)?,
+                        triggered_by_staff: row.get(6)?,
+                        job_id: row.get(7)?,
+                        error: row.get(8)?,
+                        completed_at: row.get(9)?,
+                        timed_out: row.get(10)?,
+                    };
+                    Ok((row.get(0)?, record))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn list_redeploys(&self) -> StorageResult<Vec<RedeployRecord>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT requested_at, team_id, problem_code, user_id, user_name,
+                    triggered_by_staff, job_id, error, completed_at, timed_out
+                FROM redeploys ORDER BY id",
+            )?;
+            let records = statement
+                .query_map([], |row| {
+                    Ok(RedeployRecord {
+                        requested_at: row.get(0)?,
+                        team_id: row.get(1)?,
+                        problem_code: row.get(2)?,
+                        user_id: row.get::<_, i64>(3)? as u64,
+                        user_name: row.get(4)?,
+                        triggered_by_staff: row.get(5)?,
+                        job_id: row.get(6)?,
+                        error: row.get(7)?,
+                        completed_at: row.get(8)?,
+                        timed_out: row.get(9)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn list_questions(&self) -> StorageResult<Vec<QuestionRecord>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT created_at, team_id, user_id, user_name, title, channel_id, thread_id
+                FROM questions ORDER BY id",
+            )?;
+            let records = statement
+                .query_map([], |row| {
+                    Ok(QuestionRecord {
+                        created_at: row.get(0)?,
+                        team_id: row.get(1)?,
+                        user_id: row.get::<_, i64>(2)? as u64,
+                        user_name: row.get(3)?,
+                        title: row.get(4)?,
+                        channel_id: row.get::<_, i64>(5)? as u64,
+                        thread_id: row.get::<_, i64>(6)? as u64,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn list_joins(&self) -> StorageResult<Vec<JoinRecord>> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare(
+                "SELECT joined_at, user_id, user_name, team_id, role_name
+                FROM joins ORDER BY id",
+            )?;
+            let records = statement
+                .query_map([], |row| {
+                    Ok(JoinRecord {
+                        joined_at: row.get(0)?,
+                        user_id: row.get::<_, i64>(1)? as u64,
+                        user_name: row.get(2)?,
+                        team_id: row.get(3)?,
+                        role_name: row.get(4)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(records)
+        })
+        .await
+    }
+}
+
+// テーブルに列がなければ追加し、追加したかを返す。
+fn add_column_if_missing(
+    connection: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> StorageResult<bool> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = statement
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+    if exists {
+        return Ok(false);
+    }
+
+    connection.execute_batch(&format!(
+        "ALTER TABLE {} ADD COLUMN {} {}",
+        table, column, definition
+    ))?;
+    Ok(true)
+}
+
+fn question_ticket_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<QuestionTicket> {
+    Ok(QuestionTicket {
+        thread_id: row.get::<_, i64>(0)? as u64,
+        status: row.get(1)?,
+        assignee_id: row.get::<_, Option<i64>>(2)?.map(|id| id as u64),
+        assignee_name: row.get(3)?,
+        message_id: row.get::<_, Option<i64>>(4)?.map(|id| id as u64),
+        updated_at: row.get(5)?,
+        last_staff_reply_at: row.get(6)?,
+        escalation_count: row.get(7)?,
+        waiting_since: row.get(8)?,
+    })
+}
+
+// メモリ上に履歴を保持するストレージ。botを再起動すると履歴は失われる。
+#[derive(Default)]
+pub struct InMemoryStorage {
+    redeploys: Mutex<Vec<RedeployRecord>>,
+    redeploy_limit_resets: Mutex<Vec<RedeployLimitResetRecord>>,
+    redeploy_confirmations: Mutex<HashMap<String, DateTime<Utc>>>,
+    questions: Mutex<Vec<QuestionRecord>>,
+    joins: Mutex<Vec<JoinRecord>>,
+    question_tickets: Mutex<Vec<QuestionTicket>>,
+}
+
+#[async_trait]
+impl Storage for InMemoryStorage {
+    async fn record_redeploy(&self, record: &RedeployRecord) -> StorageResult<i64> {
+        let mut redeploys = self.redeploys.lock().unwrap_or_else(|err| err.into_inner());
+        redeploys.push(record.clone());
+        Ok(redeploys.len() as i64)
+    }
+
+    async fn record_redeploy_completion(
+        &self,
+        id: i64,
+        completed_at: Option<DateTime<Utc>>,
+        timed_out: bool,
+    ) -> StorageResult<()> {
+        let mut redeploys = self.redeploys.lock().unwrap_or_else(|err| err.into_inner());
+        // IDは1から始まる連番なので、インデックスに変換する。
+        let record = usize::try_from(id - 1)
+            .ok()
+            .and_then(|index| redeploys.get_mut(index))
+            .ok_or(StorageError::NotFound(id))?;
+        record.completed_at = completed_at;
+        record.timed_out = timed_out;
+        Ok(())
+    }
+
+    async fn count_redeploys(
+        &self,
+        team_id: &str,
+        problem_code: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> StorageResult<u32> {
+        let redeploys = self.redeploys.lock().unwrap_or_else(|err| err.into_inner());
+        let count = redeploys
+            .iter()
+            .filter(|record| {
+                record.team_id == team_id
+                    && record.problem_code == problem_code
+                    && !record.triggered_by_staff
+                    && record.job_id.is_some()
+                    && since.is_none_or(|since| record.requested_at > since)
+            })
+            .count();
+        Ok(count as u32)
+    }
+
+    async fn record_redeploy_limit_reset(
+        &self,
+        record: &RedeployLimitResetRecord,
+    ) -> StorageResult<()> {
+        let mut resets = self
+            .redeploy_limit_resets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        resets.push(record.clone());
+        Ok(())
+    }
+
+    async fn last_redeploy_limit_reset(
+        &self,
+        team_id: &str,
+        problem_code: &str,
+    ) -> StorageResult<Option<DateTime<Utc>>> {
+        let resets = self
+            .redeploy_limit_resets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        Ok(resets
+            .iter()
+            .filter(|record| {
+                record.team_id == team_id
+                    && record
+                        .problem_code
+                        .as_ref()
+                        .is_none_or(|code| code == problem_code)
+            })
+            .map(|record| record.reset_at)
+            .max())
+    }
+
+    async fn consume_redeploy_confirmation(
+        &self,
+        key: &str,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> StorageResult<bool> {
+        let mut confirmations = self
+            .redeploy_confirmations
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        confirmations.retain(|_, expires_at| *expires_at >= now);
+        if confirmations.contains_key(key) {
+            return Ok(false);
+        }
+        confirmations.insert(key.to_string(), expires_at);
+        Ok(true)
+    }
+
+    async fn record_question(&self, record: &QuestionRecord) -> StorageResult<()> {
+        let mut questions = self.questions.lock().unwrap_or_else(|err| err.into_inner());
+        questions.push(record.clone());
+        Ok(())
+    }
+
+    async fn record_join(&self, record: &JoinRecord) -> StorageResult<()> {
+        let mut joins = self.joins.lock().unwrap_or_else(|err| err.into_inner());
+        joins.push(record.clone());
+        Ok(())
+    }
+
+    async fn save_question_ticket(&self, ticket: &QuestionTicket) -> StorageResult<()> {
+        let mut tickets = self
+            .question_tickets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        match tickets
+            .iter_mut()
+            .find(|saved| saved.thread_id == ticket.thread_id)
+        {
+            Some(saved) => {
+                *saved = QuestionTicket {
+                    last_staff_reply_at: saved.last_staff_reply_at,
+                    escalation_count: saved.escalation_count,
+                    waiting_since: saved.waiting_since,
+                    ..ticket.clone()
+                }
+            },
+            None => tickets.push(ticket.clone()),
+        }
+        Ok(())
+    }
+
+    async fn record_question_team_message(
+        &self,
+        thread_id: u64,
+        sent_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        let mut tickets = self
+            .question_tickets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if let Some(ticket) = tickets
+            .iter_mut()
+            .find(|ticket| ticket.thread_id == thread_id && ticket.waiting_since.is_none())
+        {
+            ticket.waiting_since = Some(sent_at);
+            ticket.escalation_count = 0;
+        }
+        Ok(())
+    }
+
+    async fn record_question_staff_reply(
+        &self,
+        thread_id: u64,
+        replied_at: DateTime<Utc>,
+    ) -> StorageResult<()> {
+        let mut tickets = self
+            .question_tickets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if let Some(ticket) = tickets
+            .iter_mut()
+            .find(|ticket| ticket.thread_id == thread_id)
+        {
+            ticket.last_staff_reply_at = Some(replied_at);
+            ticket.waiting_since = None;
+        }
+        Ok(())
+    }
+
+    async fn record_question_escalation(
+        &self,
+        thread_id: u64,
+        escalation_count: u32,
+    ) -> StorageResult<()> {
+        let mut tickets = self
+            .question_tickets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if let Some(ticket) = tickets
+            .iter_mut()
+            .find(|ticket| ticket.thread_id == thread_id)
+        {
+            ticket.escalation_count = ticket.escalation_count.max(escalation_count);
+        }
+        Ok(())
+    }
+
+    async fn get_question_ticket(&self, thread_id: u64) -> StorageResult<Option<QuestionTicket>> {
+        let tickets = self
+            .question_tickets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        Ok(tickets
+            .iter()
+            .find(|ticket| ticket.thread_id == thread_id)
+            .cloned())
+    }
+
+    async fn list_question_tickets(&self) -> StorageResult<Vec<QuestionTicket>> {
+        let mut tickets = self
+            .question_tickets
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone();
+        tickets.sort_by_key(|ticket| ticket.thread_id);
+        Ok(tickets)
+    }
+
+    async fn list_pending_redeploys(&self) -> StorageResult<Vec<(i64, RedeployRecord)>> {
+        let redeploys = self.redeploys.lock().unwrap_or_else(|err| err.into_inner());
+        Ok(redeploys
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| {
+                record.job_id.is_some() && record.completed_at.is_none() && !record.timed_out
+            })
+            // IDは1から始まる連番
+            .map(|(index, record)| (index as i64 + 1, record.clone()))
+            .collect())
+    }
+
+    async fn list_redeploys(&self) -> StorageResult<Vec<RedeployRecord>> {
+        Ok(self
+            .redeploys
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone())
+    }
+
+    async fn list_questions(&self) -> StorageResult<Vec<QuestionRecord>> {
+        Ok(self
+            .questions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone())
+    }
+
+    async fn list_joins(&self) -> StorageResult<Vec<JoinRecord>> {
+        Ok(self
+            .joins
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone())
+    }
+}
+
+// 記録をCSVとして書き出す
+pub fn write_csv<W: Write, T: Serialize>(writer: W, records: &[T]) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for record in records {
+        writer.serialize(record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// 記録をJSONの配列として書き出す
+pub fn write_json<W: Write, T: Serialize>(mut writer: W, records: &[T]) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, records)?;
+    writeln!(writer)?;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+// This module renders question thread transcripts and writes them to files.
+use std::fmt::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use serde::Serialize;
+
+// 質問スレッドの全てのメッセージの記録
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub thread_id: u64,
+    pub thread_name: String,
+
+    // スレッドが作成されたチャンネルのチームID。チームチャンネル以外の場合はNone
+    pub team_id: Option<String>,
+    pub exported_at: DateTime<Utc>,
+
+    // 古い順に並べたメッセージ
+    pub messages: Vec<TranscriptMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptMessage {
+    pub id: u64,
+    pub author_id: u64,
+    pub author_name: String,
+    pub created_at: DateTime<Utc>,
+    pub content: String,
+    pub attachments: Vec<TranscriptAttachment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptAttachment {
+    pub filename: String,
+    pub url: String,
+}
+
+impl Transcript {
+    // 書き出すファイル名（拡張子を除く）。例: team1-123456789012345678
+    pub fn file_stem(&self) -> String {
+        format!(
+            "{}-{}",
+            self.team_id.as_deref().unwrap_or("unknown"),
+            self.thread_id
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        // Stringへの書き込みは失敗しないため、結果は無視する。
+        let _ = writeln!(markdown, "# {}\n", self.thread_name);
+        let _ = writeln!(
+            markdown,
+            "- チーム: {}",
+            self.team_id.as_deref().unwrap_or("-")
+        );
+        let _ = writeln!(markdown, "- スレッドID: {}", self.thread_id);
+        let _ = writeln!(
+            markdown,
+            "- 書き出し日時: {}",
+            format_time(&self.exported_at)
+        );
+
+        for message in &self.messages {
+            let _ = writeln!(
+                markdown,
+                "\n## {} ({}) {}\n",
+                message.author_name,
+                message.author_id,
+                format_time(&message.created_at)
+            );
+            for line in message.content.lines() {
+                let _ = writeln!(markdown, "> {}", line);
+            }
+            for attachment in &message.attachments {
+                let _ = writeln!(
+                    markdown,
+                    "\n- 添付ファイル: [{}]({})",
+                    attachment.filename, attachment.url
+                );
+            }
+        }
+
+        markdown
+    }
+
+    // ディレクトリにMarkdownとJSONの2つのファイルを書き出し、そのパスを返す。
+    // 同じスレッドを再度書き出した場合は上書きする。
+    pub async fn write_to(&self, directory: &Path) -> anyhow::Result<(PathBuf, PathBuf)> {
+        tokio::fs::create_dir_all(directory).await?;
+
+        let markdown_path = directory.join(format!("{}.md", self.file_stem()));
+        tokio::fs::write(&markdown_path, self.to_markdown()).await?;
+
+        let json_path = directory.join(format!("{}.json", self.file_stem()));
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        tokio::fs::write(&json_path, json).await?;
+
+        Ok((markdown_path, json_path))
+    }
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bot::config::Configuration;
+use bot::models::ChannelPolicy;
+use bot::services::redeploy::FakeRedeployService;
+use bot::services::storage::InMemoryStorage;
+use bot::Bot;
+use bot::BotSettings;
+use serenity::model::prelude::*;
+
+fn bot() -> Bot {
+    bot_with_staff("  password: staff password\n")
+}
+
+fn bot_with_staff(staff: &str) -> Bot {
+    let yaml = format!(
+        r#"
+staff:
+{}
+discord:
+  token: xxxx
+  application_id: 1
+  guild_id: 1
+teams:
+  - id: team1
+    role_name: team1 role
+    team_code: code1
+  - id: team2
+    role_name: team2 role
+    team_code: code2
+"#,
+        staff
+    );
+    let config: Configuration = serde_yaml::from_str(&yaml).unwrap();
+
+    Bot::new(
+        BotSettings::from(config),
+        Box::new(FakeRedeployService),
+        Arc::new(Vec::new()),
+        Arc::new(InMemoryStorage::default()),
+    )
+}
+
+fn role_map(names: &[(u64, &str)]) -> HashMap<String, Role> {
+    names
+        .iter()
+        .map(|(id, name)| {
+            let mut role = Role::default();
+            role.id = RoleId::new(*id);
+            role.name = name.to_string();
+            (role.name.clone(), role)
+        })
+        .collect()
+}
+
+fn allowed_roles(overwrites: &[PermissionOverwrite]) -> Vec<(RoleId, Permissions)> {
+    overwrites
+        .iter()
+        .map(|overwrite| {
+            assert_eq!(overwrite.deny, Permissions::empty());
+            match overwrite.kind {
+                PermissionOverwriteType::Role(id) => (id, overwrite.allow),
+                _ => panic!("unexpected overwrite: {:?}", overwrite),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn opens_help_channel_to_everyone() {
+    let bot = bot();
+    let roles = role_map(&[(100, "@everyone"), (101, "team1 role"), (102, "team2 role")]);
+
+    let overwrites = bot
+        .get_permission_overwrites_for_policy(ChannelPolicy::Help, &roles)
+        .unwrap();
+
+    assert_eq!(
+        allowed_roles(&overwrites),
+        [(
+            RoleId::new(100),
+            bot.get_permissions_for_readonly_channel_member()
+        )]
+    );
+    assert!(!overwrites[0].allow.send_messages());
+}
+
+#[test]
+fn lets_teams_read_readonly_channel() {
+    let bot = bot();
+    let roles = role_map(&[(100, "@everyone"), (101, "team1 role"), (102, "team2 role")]);
+
+    let overwrites = bot
+        .get_permission_overwrites_for_policy(ChannelPolicy::Readonly, &roles)
+        .unwrap();
+
+    let readonly = bot.get_permissions_for_readonly_channel_member();
+    assert_eq!(
+        allowed_roles(&overwrites),
+        [(RoleId::new(101), readonly), (RoleId::new(102), readonly)]
+    );
+}
+
+#[test]
+fn lets_teams_write_writable_channel() {
+    let bot = bot();
+    let roles = role_map(&[(100, "@everyone"), (101, "team1 role"), (102, "team2 role")]);
+
+    let overwrites = bot
+        .get_permission_overwrites_for_policy(ChannelPolicy::Writable, &roles)
+        .unwrap();
+
+    let writable = bot.get_permissions_for_channel_member();
+    assert!(writable.send_messages());
+    assert_eq!(
+        allowed_roles(&overwrites),
+        [(RoleId::new(101), writable), (RoleId::new(102), writable)]
+    );
+}
+
+#[test]
+fn fails_when_team_role_is_missing() {
+    let bot = bot();
+    let roles = role_map(&[(100, "@everyone"), (101, "team1 role")]);
+
+    assert!(bot
+        .get_permission_overwrites_for_policy(ChannelPolicy::Writable, &roles)
+        .is_err());
+    assert!(bot
+        .get_permission_overwrites_for_policy(ChannelPolicy::Help, &roles)
+        .is_ok());
+}
+
+fn staff_previous_names(bot: &Bot) -> (Vec<String>, Vec<String>) {
+    let role = bot
+        .define_roles()
+        .unwrap()
+        .into_iter()
+        .find(|role| role.name == "ICTSC2025 Staff")
+        .unwrap();
+    let category = bot
+        .define_categories()
+        .unwrap()
+        .into_iter()
+        .find(|category| category.kind == ChannelType::Category && category.name == "staff")
+        .unwrap();
+    (role.previous_names, category.previous_names)
+}
+
+#[test]
+fn renames_staff_category_from_its_own_previous_names() {
+    let bot = bot_with_staff(
+        "  password: staff password
+  category_name: staff
+  previous_names: [ICTSC2024 Staff]
+  previous_category_names: [staff-2024]
+",
+    );
+
+    let (role_names, category_names) = staff_previous_names(&bot);
+    assert_eq!(role_names, ["ICTSC2024 Staff"]);
+    assert_eq!(category_names, ["staff-2024"]);
+}
+
+#[test]
+fn does_not_rename_custom_staff_category_from_role_names() {
+    let bot = bot_with_staff(
+        "  password: staff password
+  category_name: staff
+  previous_names: [ICTSC2024 Staff]
+",
+    );
+
+    let (role_names, category_names) = staff_previous_names(&bot);
+    assert_eq!(role_names, ["ICTSC2024 Staff"]);
+    assert!(category_names.is_empty());
+}
+
+#[test]
+fn renames_default_staff_category_like_role() {
+    let bot = bot_with_staff(
+        "  password: staff password
+  previous_names: [ICTSC2024 Staff]
+",
+    );
+
+    let category = bot
+        .define_categories()
+        .unwrap()
+        .into_iter()
+        .find(|category| category.name == "ICTSC2025 Staff")
+        .unwrap();
+    assert_eq!(category.previous_names, ["ICTSC2024 Staff"]);
+}
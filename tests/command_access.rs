@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use bot::config::Configuration;
+use bot::models::default_command_access;
+use bot::models::CommandAccess;
+use bot::services::redeploy::FakeRedeployService;
+use bot::services::storage::InMemoryStorage;
+use bot::Bot;
+use bot::BotSettings;
+
+fn bot(discord: &str) -> Bot {
+    let config: Configuration = serde_yaml::from_str(&format!(
+        r#"
+staff:
+  password: staff password
+discord:
+  token: xxxx
+  application_id: 1
+  guild_id: 1
+{}
+"#,
+        discord
+    ))
+    .unwrap();
+
+    Bot::new(
+        BotSettings::from(config),
+        Box::new(FakeRedeployService),
+        Arc::new(Vec::new()),
+        Arc::new(InMemoryStorage::default()),
+    )
+}
+
+#[test]
+fn restricts_team_commands_by_default() {
+    assert_eq!(default_command_access("archive"), CommandAccess::TeamMember);
+    assert_eq!(default_command_access("ask"), CommandAccess::TeamMember);
+    assert_eq!(
+        default_command_access("redeploy"),
+        CommandAccess::TeamMember
+    );
+    assert_eq!(default_command_access("join"), CommandAccess::Everyone);
+    assert_eq!(default_command_access("ping"), CommandAccess::Everyone);
+}
+
+#[test]
+fn overrides_command_access_by_configuration() {
+    let bot = bot(r#"
+  command_access:
+    redeploy: staff
+    ping: team_member
+"#);
+
+    assert_eq!(bot.get_command_access("redeploy"), CommandAccess::Staff);
+    assert_eq!(bot.get_command_access("ping"), CommandAccess::TeamMember);
+    assert_eq!(bot.get_command_access("ask"), CommandAccess::TeamMember);
+    assert_eq!(bot.get_command_access("join"), CommandAccess::Everyone);
+}
+
+#[test]
+fn checks_caller_roles_against_command_access() {
+    // (is_staff, is_team_member)
+    let callers = [(false, false), (false, true), (true, false), (true, true)];
+
+    for (is_staff, is_team_member) in callers {
+        assert!(CommandAccess::Everyone.allows(is_staff, is_team_member));
+        assert_eq!(
+            CommandAccess::TeamMember.allows(is_staff, is_team_member),
+            is_staff || is_team_member
+        );
+        assert_eq!(
+            CommandAccess::Staff.allows(is_staff, is_team_member),
+            is_staff
+        );
+    }
+}
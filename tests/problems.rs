@@ -0,0 +1,160 @@
+use bot::config::Configuration;
+use bot::config::RedeployLimitsConfiguration;
+use bot::models::normalize_problem_code;
+use bot::models::search_problems;
+use bot::models::Problem;
+use validator::Validate;
+
+fn problems() -> Vec<Problem> {
+    [
+        ("ABC", "ネットワークが繋がらない"),
+        ("XAB", "DNSの名前解決ができない"),
+        ("DEF", "Webサーバが応答しない"),
+    ]
+    .into_iter()
+    .map(|(code, name)| Problem {
+        code: String::from(code),
+        name: String::from(name),
+        ..Default::default()
+    })
+    .collect()
+}
+
+fn codes(problems: &[Problem], query: &str) -> Vec<String> {
+    search_problems(problems, query)
+        .into_iter()
+        .map(|problem| problem.code.clone())
+        .collect()
+}
+
+#[test]
+fn normalizes_full_width_problem_code() {
+    assert_eq!(normalize_problem_code("ＡＢＣ"), "ABC");
+    assert_eq!(normalize_problem_code(" abc "), "ABC");
+    assert_eq!(normalize_problem_code("ａ　ｂｃ"), "ABC");
+}
+
+#[test]
+fn searches_problems_by_code() {
+    let problems = problems();
+
+    assert_eq!(codes(&problems, "ab"), vec!["ABC", "XAB"]);
+    assert_eq!(codes(&problems, "ｄｅｆ"), vec!["DEF"]);
+    assert_eq!(codes(&problems, "").len(), 3);
+}
+
+#[test]
+fn searches_problems_by_name() {
+    let problems = problems();
+
+    assert_eq!(codes(&problems, "ネットワーク"), vec!["ABC"]);
+    assert_eq!(codes(&problems, "ｄｎｓ"), vec!["XAB"]);
+    // 文字が飛び飛びに含まれる場合も一致とみなす。
+    assert_eq!(codes(&problems, "web応答"), vec!["DEF"]);
+    assert!(codes(&problems, "存在しない").is_empty());
+}
+
+#[test]
+fn resolves_max_redeploys_per_problem() {
+    let limits: RedeployLimitsConfiguration = serde_yaml::from_str(
+        r#"
+max_redeploys: 5
+problems:
+  ABC:
+    max_redeploys: 1
+"#,
+    )
+    .unwrap();
+
+    let problem = |code: &str, max_redeploys: Option<u32>| Problem {
+        code: String::from(code),
+        max_redeploys,
+        ..Default::default()
+    };
+
+    // 問題ごとの上限（redeploy.limits.problems または problems[].max_redeploys）> redeploy.limits.max_redeploys
+    assert_eq!(limits.max_redeploys(&problem("ABC", None)), Some(1));
+    assert_eq!(limits.max_redeploys(&problem("DEF", Some(3))), Some(3));
+    assert_eq!(limits.max_redeploys(&problem("DEF", None)), Some(5));
+}
+
+fn configuration(problems: &str, limits: &str) -> Configuration {
+    serde_yaml::from_str(&format!(
+        r#"
+staff:
+  password: staff password
+discord:
+  token: xxxx
+  application_id: 1
+  guild_id: 1
+redeploy:
+  fake:
+  notifiers: []
+  limits:
+{}
+problems:
+{}
+"#,
+        limits, problems
+    ))
+    .unwrap()
+}
+
+#[test]
+fn accepts_max_redeploys_set_in_one_place_per_problem() {
+    let config = configuration(
+        r#"
+  - code: ABC
+    name: problem abc
+    max_redeploys: 3
+  - code: DEF
+    name: problem def
+"#,
+        r#"
+    max_redeploys: 5
+    problems:
+      ABC:
+        cooldown_secs: 600
+      DEF:
+        max_redeploys: 1
+"#,
+    );
+
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn rejects_max_redeploys_set_in_both_places() {
+    let config = configuration(
+        r#"
+  - code: ABC
+    name: problem abc
+    max_redeploys: 3
+"#,
+        r#"
+    problems:
+      ABC:
+        max_redeploys: 1
+"#,
+    );
+
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("ABC"));
+}
+
+#[test]
+fn parses_problem_owners() {
+    let problems: Vec<Problem> = serde_yaml::from_str(
+        r#"
+- code: ABC
+  name: ネットワークが繋がらない
+  owners: [123456789012345678]
+- code: DEF
+  name: Webサーバが応答しない
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(problems[0].owners, vec![123456789012345678]);
+    assert!(problems[1].owners.is_empty());
+}
@@ -0,0 +1,106 @@
+use bot::services::questions::list_open_questions;
+use bot::services::questions::next_escalation;
+use bot::services::questions::OpenQuestion;
+use bot::services::storage::QuestionRecord;
+use bot::services::storage::QuestionTicket;
+use chrono::Duration;
+use chrono::TimeZone;
+use chrono::Utc;
+
+fn question(thread_id: u64, minute: u32) -> QuestionRecord {
+    QuestionRecord {
+        created_at: Utc.with_ymd_and_hms(2025, 3, 1, 10, minute, 0).unwrap(),
+        team_id: Some(String::from("team1")),
+        user_id: 1,
+        user_name: String::from("alice"),
+        title: format!("質問{}", thread_id),
+        channel_id: 2,
+        thread_id,
+    }
+}
+
+#[test]
+fn lists_open_questions_in_creation_order() {
+    let now = Utc::now();
+    let mut closed = QuestionTicket::open(3, None, now);
+    closed.close(now);
+    let mut claimed = QuestionTicket::open(2, None, now);
+    claimed.claim(5, "staff", now).unwrap();
+
+    let questions = list_open_questions(
+        vec![
+            question(1, 30),
+            question(2, 10),
+            question(3, 0),
+            question(4, 20),
+        ],
+        // スレッド4はチケットが保存されていないため、一覧に含めない。
+        vec![QuestionTicket::open(1, None, now), claimed, closed],
+    );
+
+    let thread_ids: Vec<_> = questions
+        .iter()
+        .map(|question| question.record.thread_id)
+        .collect();
+    assert_eq!(thread_ids, vec![2, 1]);
+    assert_eq!(questions[0].ticket.assignee_id, Some(5));
+}
+
+#[test]
+fn escalates_unanswered_questions_once_per_threshold() {
+    let record = question(1, 0);
+    let mut question = OpenQuestion {
+        ticket: QuestionTicket::open(1, None, record.created_at),
+        record,
+    };
+    let thresholds = [600, 1200];
+    let after = |secs| question.record.created_at + Duration::seconds(secs);
+
+    assert_eq!(next_escalation(&question, &thresholds, after(599)), None);
+    assert_eq!(next_escalation(&question, &thresholds, after(600)), Some(1));
+    // 閾値を2つ同時に超えた場合は、まとめて1回だけ再通知する。
+    assert_eq!(
+        next_escalation(&question, &thresholds, after(1500)),
+        Some(2)
+    );
+
+    question.ticket.escalation_count = 1;
+    let now = after(900);
+    assert_eq!(next_escalation(&question, &thresholds, now), None);
+    assert_eq!(
+        next_escalation(&question, &thresholds, after(1200)),
+        Some(2)
+    );
+
+    // staffが投稿し、チームがまだ投稿していない質問は再通知しない。
+    question.ticket.last_staff_reply_at = Some(now);
+    question.ticket.waiting_since = None;
+    assert_eq!(next_escalation(&question, &thresholds, after(1200)), None);
+}
+
+#[test]
+fn measures_escalation_from_team_message_after_staff_reply() {
+    let record = question(1, 0);
+    let mut question = OpenQuestion {
+        ticket: QuestionTicket::open(1, None, record.created_at),
+        record,
+    };
+    let thresholds = [600, 1200];
+    let after = |secs| question.record.created_at + Duration::seconds(secs);
+
+    // 1回再通知した後にstaffが回答し、その後チームが再び質問した（再通知の回数は0に戻る）。
+    question.ticket.escalation_count = 0;
+    question.ticket.last_staff_reply_at = Some(after(700));
+    question.ticket.waiting_since = Some(after(1000));
+
+    // 質問の作成からではなく、チームが再び質問した時刻から数える。
+    assert_eq!(next_escalation(&question, &thresholds, after(1500)), None);
+    assert_eq!(
+        next_escalation(&question, &thresholds, after(1600)),
+        Some(1)
+    );
+    assert_eq!(
+        next_escalation(&question, &thresholds, after(2200)),
+        Some(2)
+    );
+}
@@ -0,0 +1,103 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bot::services::redeploy::cache::CachedRedeployService;
+use bot::services::redeploy::RedeployError;
+use bot::services::redeploy::RedeployJob;
+use bot::services::redeploy::RedeployService;
+use bot::services::redeploy::RedeployStatus;
+use bot::services::redeploy::RedeployTarget;
+
+// get_statusの呼び出し回数を数えるRedeployService
+struct CountingRedeployService {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl RedeployService for CountingRedeployService {
+    async fn redeploy(&self, target: &RedeployTarget) -> Result<RedeployJob, RedeployError> {
+        Ok(RedeployJob {
+            id: String::from("job"),
+            team_id: target.team_id.clone(),
+            problem_code: target.problem_id.clone(),
+        })
+    }
+
+    async fn get_status(&self, team_id: &str) -> Result<Vec<RedeployStatus>, RedeployError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Ok(vec![RedeployStatus::unknown(team_id, "ABC")])
+    }
+}
+
+fn service(ttl: Duration) -> (Arc<CachedRedeployService>, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let inner = CountingRedeployService {
+        calls: calls.clone(),
+    };
+    (
+        Arc::new(CachedRedeployService::new(Box::new(inner), ttl)),
+        calls,
+    )
+}
+
+#[tokio::test]
+async fn caches_status_per_team() {
+    let (service, calls) = service(Duration::from_secs(60));
+
+    service.get_status("team1").await.unwrap();
+    service.get_status("team1").await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let statuses = service.get_status("team2").await.unwrap();
+    assert_eq!(statuses[0].team_id, "team2");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn expires_after_ttl() {
+    let (service, calls) = service(Duration::from_millis(50));
+
+    service.get_status("team1").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    service.get_status("team1").await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn coalesces_concurrent_calls() {
+    let (service, calls) = service(Duration::from_secs(60));
+
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let service = service.clone();
+            tokio::spawn(async move { service.get_status("team1").await.unwrap() })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn invalidates_on_successful_redeploy() {
+    let (service, calls) = service(Duration::from_secs(60));
+
+    service.get_status("team1").await.unwrap();
+    service
+        .redeploy(&RedeployTarget {
+            team_id: String::from("team1"),
+            problem_id: String::from("ABC"),
+            triggered_by_staff: false,
+            reason: None,
+        })
+        .await
+        .unwrap();
+    service.get_status("team1").await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,197 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bot::models::Problem;
+use bot::services::redeploy::command::CommandRedeployConfig;
+use bot::services::redeploy::command::CommandRedeployService;
+use bot::services::redeploy::RedeployError;
+use bot::services::redeploy::RedeployService;
+use bot::services::redeploy::RedeployStatus;
+use bot::services::redeploy::RedeployTarget;
+
+fn log_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("ictsc-discord-bot-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn service(script: &str, log_dir: PathBuf, timeout_secs: u64) -> CommandRedeployService {
+    let config = CommandRedeployConfig {
+        program: String::from("sh"),
+        args: vec![
+            String::from("-c"),
+            script.to_string(),
+            String::from("redeploy"),
+            String::from("{problem_code}"),
+        ],
+        env: [(String::from("TEAM"), String::from("{team_id}"))]
+            .into_iter()
+            .collect(),
+        working_dir: None,
+        log_dir,
+        max_concurrency: 1,
+        timeout_secs,
+    };
+    let problems = vec![Problem {
+        code: String::from("ABC"),
+        name: String::from("デフォルトルートが消えちゃった！"),
+        ..Default::default()
+    }];
+    CommandRedeployService::new(config, problems).unwrap()
+}
+
+fn target() -> RedeployTarget {
+    RedeployTarget {
+        team_id: String::from("team7"),
+        problem_id: String::from("ABC"),
+        triggered_by_staff: false,
+        reason: None,
+    }
+}
+
+async fn wait_for_completion(service: &CommandRedeployService) -> RedeployStatus {
+    for _ in 0..100 {
+        let status = service.get_status("team7").await.unwrap().remove(0);
+        if !status.is_redeploying {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("redeploy command did not finish");
+}
+
+#[tokio::test]
+async fn runs_command_and_captures_output() {
+    let log_dir = log_dir("output");
+    let service = service(
+        r#"sleep 0.5; echo "$TEAM $REDEPLOY_PROBLEM_CODE $REDEPLOY_BACKEND_ID $1""#,
+        log_dir.clone(),
+        10,
+    );
+
+    let status = service.get_status("team7").await.unwrap().remove(0);
+    assert_eq!(status.last_redeploy_started_at, None);
+
+    let job = service.redeploy(&target()).await.unwrap();
+    assert_eq!(job.team_id, "team7");
+
+    // 開始時刻は、Jobが実行を開始した時点で記録される。
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let status = service.get_status("team7").await.unwrap().remove(0);
+    assert!(status.is_redeploying);
+    assert!(status.last_redeploy_started_at.is_some());
+    assert!(matches!(
+        service.redeploy(&target()).await,
+        Err(RedeployError::AnotherJobInQueue(_))
+    ));
+
+    let status = wait_for_completion(&service).await;
+    assert!(status.last_redeploy_completed_at.is_some());
+    assert!(!status.last_redeploy_failed);
+
+    let log = std::fs::read_to_string(log_dir.join(format!("{}.log", job.id))).unwrap();
+    assert!(log.starts_with("team7 ABC abc ABC\n"));
+    assert!(log.contains("exited"));
+}
+
+#[tokio::test]
+async fn keeps_logs_of_previous_runs() {
+    // botを再起動しても、以前のJobのログを上書きしない。
+    let log_dir = log_dir("restart");
+    let mut jobs = Vec::new();
+    for output in ["first", "second"] {
+        let service = service(&format!("echo {}", output), log_dir.clone(), 10);
+        jobs.push(service.redeploy(&target()).await.unwrap());
+        wait_for_completion(&service).await;
+    }
+
+    assert_ne!(jobs[0].id, jobs[1].id);
+    let first = std::fs::read_to_string(log_dir.join(format!("{}.log", jobs[0].id))).unwrap();
+    assert!(first.starts_with("first\n"));
+    let second = std::fs::read_to_string(log_dir.join(format!("{}.log", jobs[1].id))).unwrap();
+    assert!(second.starts_with("second\n"));
+}
+
+#[tokio::test]
+async fn kills_command_after_timeout() {
+    let log_dir = log_dir("timeout");
+    let service = service("sleep 30", log_dir.clone(), 1);
+
+    let job = service.redeploy(&target()).await.unwrap();
+    let status = wait_for_completion(&service).await;
+    assert!(status.last_redeploy_completed_at.is_some());
+    assert!(status.last_redeploy_failed);
+
+    let log = std::fs::read_to_string(log_dir.join(format!("{}.log", job.id))).unwrap();
+    assert!(log.contains("timed out"));
+}
+
+#[tokio::test]
+async fn records_failure_of_command() {
+    let service = service("exit 3", log_dir("failure"), 10);
+
+    service.redeploy(&target()).await.unwrap();
+    let status = wait_for_completion(&service).await;
+    assert!(status.last_redeploy_completed_at.is_some());
+    assert!(status.last_redeploy_failed);
+}
+
+#[tokio::test]
+async fn kills_child_processes_after_timeout() {
+    let log_dir = log_dir("process-group");
+    let pid_path = log_dir.join("child.pid");
+    let service = service(
+        &format!("sleep 30 & echo $! > {}; wait", pid_path.display()),
+        log_dir.clone(),
+        1,
+    );
+
+    service.redeploy(&target()).await.unwrap();
+    wait_for_completion(&service).await;
+
+    // 孫プロセスのsleepも終了している（ゾンビとして残る場合も含む）。
+    let pid = std::fs::read_to_string(&pid_path).unwrap();
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+    if let Ok(stat) = stat {
+        assert!(
+            stat.contains(") Z "),
+            "child process is still running: {}",
+            stat
+        );
+    }
+}
+
+#[tokio::test]
+async fn starts_queued_job_after_running_job() {
+    // max_concurrency: 1 のため、2つ目のJobは1つ目の終了を待つ。
+    let service = service("sleep 1", log_dir("queue"), 10);
+
+    service.redeploy(&target()).await.unwrap();
+    service
+        .redeploy(&RedeployTarget {
+            team_id: String::from("team8"),
+            ..target()
+        })
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let running = service.get_status("team7").await.unwrap().remove(0);
+    assert!(running.is_redeploying);
+    assert!(running.last_redeploy_started_at.is_some());
+
+    let queued = service.get_status("team8").await.unwrap().remove(0);
+    assert!(queued.is_redeploying);
+    assert_eq!(queued.last_redeploy_started_at, None);
+
+    wait_for_completion(&service).await;
+    for _ in 0..50 {
+        let queued = service.get_status("team8").await.unwrap().remove(0);
+        if queued.last_redeploy_started_at.is_some() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("queued redeploy command did not start");
+}
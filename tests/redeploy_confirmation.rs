@@ -0,0 +1,188 @@
+use bot::config::Configuration;
+use bot::services::redeploy::confirmation::is_confirmation_custom_id;
+use bot::services::redeploy::confirmation::validate_ids;
+use bot::services::redeploy::confirmation::RedeployConfirmation;
+use bot::services::redeploy::confirmation::RedeployConfirmationAction;
+use bot::services::redeploy::confirmation::RedeployConfirmationError;
+use bot::services::redeploy::confirmation::MAX_IDS_LENGTH;
+use chrono::Duration;
+use chrono::TimeZone;
+use chrono::Utc;
+use validator::Validate;
+
+fn confirmation() -> RedeployConfirmation {
+    RedeployConfirmation::new(
+        "team10",
+        "ABCDEFGH",
+        true,
+        1_234_567_890_123_456_789,
+        Utc::now() + Duration::minutes(10),
+    )
+}
+
+#[test]
+fn roundtrips_confirmation() {
+    let confirmation = confirmation().with_action(RedeployConfirmationAction::Cancel);
+    let custom_id = confirmation.encode("secret");
+
+    // Discordの制約上、custom_idは100文字までしか設定できない。
+    assert!(custom_id.len() <= 100, "{}", custom_id);
+    assert!(is_confirmation_custom_id(&custom_id));
+
+    let decoded = RedeployConfirmation::decode(&custom_id, "secret", Utc::now()).unwrap();
+    assert_eq!(
+        decoded,
+        RedeployConfirmation {
+            // 有効期限は秒単位で埋め込まれる。
+            expires_at: decoded.expires_at,
+            ..confirmation.clone()
+        }
+    );
+    assert_eq!(
+        decoded.expires_at.timestamp(),
+        confirmation.expires_at.timestamp()
+    );
+}
+
+#[test]
+fn rejects_tampered_confirmation() {
+    let custom_id = confirmation().encode("secret");
+
+    let tampered = custom_id.replacen("team10", "team11", 1);
+    assert!(matches!(
+        RedeployConfirmation::decode(&tampered, "secret", Utc::now()),
+        Err(RedeployConfirmationError::InvalidSignature)
+    ));
+
+    assert!(matches!(
+        RedeployConfirmation::decode(&custom_id, "another secret", Utc::now()),
+        Err(RedeployConfirmationError::InvalidSignature)
+    ));
+
+    assert!(matches!(
+        RedeployConfirmation::decode("redeploy_confirm", "secret", Utc::now()),
+        Err(RedeployConfirmationError::Malformed)
+    ));
+}
+
+#[test]
+fn rejects_expired_confirmation() {
+    let custom_id = confirmation().encode("secret");
+
+    assert!(matches!(
+        RedeployConfirmation::decode(&custom_id, "secret", Utc::now() + Duration::minutes(11)),
+        Err(RedeployConfirmationError::Expired)
+    ));
+}
+
+#[test]
+fn fits_longest_allowed_ids_into_custom_id() {
+    let team_id = "t".repeat(MAX_IDS_LENGTH / 2);
+    let problem_code = "P".repeat(MAX_IDS_LENGTH - team_id.len());
+    assert!(validate_ids(&team_id, &problem_code).is_ok());
+
+    // ユーザIDと有効期限が最大の値になる場合
+    let confirmation = RedeployConfirmation::new(
+        &team_id,
+        &problem_code,
+        true,
+        u64::MAX,
+        Utc.with_ymd_and_hms(2106, 1, 1, 0, 0, 0).unwrap(),
+    )
+    .with_action(RedeployConfirmationAction::Cancel);
+    let custom_id = confirmation.encode("secret");
+    assert!(custom_id.len() <= 100, "{}", custom_id);
+
+    let decoded = RedeployConfirmation::decode(&custom_id, "secret", Utc::now()).unwrap();
+    assert_eq!(decoded.team_id, team_id);
+    assert_eq!(decoded.problem_code, problem_code);
+    assert_eq!(decoded.requester_id, u64::MAX);
+}
+
+#[test]
+fn allows_reasonably_long_ids() {
+    // 一般的な長さのチームIDと問題コードは、custom_idに収まる必要がある。
+    assert!(MAX_IDS_LENGTH >= 40, "{}", MAX_IDS_LENGTH);
+    assert!(validate_ids("team-100", "network-troubleshooting-01").is_ok());
+}
+
+#[test]
+fn shares_key_between_actions() {
+    // OKとキャンセルのどちらかが押されたら、もう一方も使えなくなるよう、同じキーを持つ。
+    let confirmation = confirmation();
+    let cancel = confirmation.with_action(RedeployConfirmationAction::Cancel);
+    assert_eq!(confirmation.key(), cancel.key());
+
+    // 同じ内容でも、別の確認とは区別される。
+    let another = RedeployConfirmation {
+        nonce: confirmation.nonce.wrapping_add(1),
+        ..confirmation.clone()
+    };
+    assert_ne!(confirmation.key(), another.key());
+}
+
+#[test]
+fn rejects_ids_not_fitting_into_custom_id() {
+    let team_id = "t".repeat(MAX_IDS_LENGTH / 2);
+    let problem_code = "P".repeat(MAX_IDS_LENGTH - team_id.len() + 1);
+    assert!(matches!(
+        validate_ids(&team_id, &problem_code),
+        Err(RedeployConfirmationError::TooLong)
+    ));
+
+    assert!(matches!(
+        validate_ids("team:1", "ABC"),
+        Err(RedeployConfirmationError::InvalidCharacter)
+    ));
+    assert!(matches!(
+        validate_ids("team1", "A:BC"),
+        Err(RedeployConfirmationError::InvalidCharacter)
+    ));
+}
+
+fn configuration(team_id: &str, problem_code: &str) -> Configuration {
+    serde_yaml::from_str(&format!(
+        r#"
+staff:
+  password: staff password
+discord:
+  token: xxxx
+  application_id: 1
+  guild_id: 1
+teams:
+- id: "{}"
+  role_name: team
+  team_code: code
+problems:
+- code: "{}"
+  name: problem
+"#,
+        team_id, problem_code
+    ))
+    .unwrap()
+}
+
+#[test]
+fn validates_ids_in_configuration() {
+    assert!(configuration("team10", "ABC").validate().is_ok());
+
+    let err = configuration("team10", "A:BC").validate().unwrap_err();
+    assert!(err.to_string().contains("A:BC"), "{}", err);
+
+    let err = configuration("team10", &"P".repeat(MAX_IDS_LENGTH))
+        .validate()
+        .unwrap_err();
+    assert!(err.to_string().contains("team10"), "{}", err);
+}
+
+#[test]
+fn roundtrips_reason_modal() {
+    // 理由を入力するモーダルも、ボタンと同じ形式のcustom_idで受け取る。
+    let confirmation = confirmation().with_action(RedeployConfirmationAction::Reason);
+    let custom_id = confirmation.encode("secret");
+    assert!(is_confirmation_custom_id(&custom_id));
+
+    let decoded = RedeployConfirmation::decode(&custom_id, "secret", Utc::now()).unwrap();
+    assert_eq!(decoded.action, RedeployConfirmationAction::Reason);
+    assert_eq!(decoded.key(), confirmation.key());
+}
@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bot::config::Configuration;
+use bot::config::RedeployNotifierConfiguration;
+use bot::config::RedeployNotifiersConfiguration;
+use bot::services::redeploy::filter::FilteredRedeployNotifier;
+use bot::services::redeploy::filter::RedeployNotifierFilter;
+use bot::services::redeploy::RedeployAlert;
+use bot::services::redeploy::RedeployCompletion;
+use bot::services::redeploy::RedeployError;
+use bot::services::redeploy::RedeployJob;
+use bot::services::redeploy::RedeployNotifier;
+use bot::services::redeploy::RedeployRequestEvent;
+use bot::services::redeploy::RedeployRequester;
+use bot::services::redeploy::RedeployTarget;
+use validator::Validate;
+
+// 受け取ったイベントを記録するRedeployNotifier
+#[derive(Default)]
+struct RecordingNotifier {
+    events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+#[async_trait]
+impl RedeployNotifier for RecordingNotifier {
+    async fn notify(&self, event: &RedeployRequestEvent<'_>) {
+        let event = if event.result.is_ok() {
+            "requested"
+        } else {
+            "failed"
+        };
+        self.events.lock().unwrap().push(event);
+    }
+
+    async fn notify_completion(
+        &self,
+        _: &RedeployTarget,
+        _: &RedeployJob,
+        _: &RedeployCompletion,
+        _: &RedeployRequester,
+    ) {
+        self.events.lock().unwrap().push("completion");
+    }
+
+    async fn notify_alert(&self, _: &RedeployAlert) {
+        self.events.lock().unwrap().push("alert");
+    }
+}
+
+fn filtered(filter: &str) -> (FilteredRedeployNotifier, Arc<Mutex<Vec<&'static str>>>) {
+    let notifier = RecordingNotifier::default();
+    let events = notifier.events.clone();
+    let filter: RedeployNotifierFilter = serde_yaml::from_str(filter).unwrap();
+    (
+        FilteredRedeployNotifier::new(Box::new(notifier), filter),
+        events,
+    )
+}
+
+fn target(team_id: &str, problem_id: &str) -> RedeployTarget {
+    RedeployTarget {
+        team_id: String::from(team_id),
+        problem_id: String::from(problem_id),
+        triggered_by_staff: false,
+        reason: None,
+    }
+}
+
+fn job(target: &RedeployTarget) -> RedeployJob {
+    RedeployJob {
+        id: String::from("job"),
+        team_id: target.team_id.clone(),
+        problem_code: target.problem_id.clone(),
+    }
+}
+
+fn requester() -> RedeployRequester {
+    RedeployRequester {
+        user_id: 1,
+        user_name: String::from("user"),
+    }
+}
+
+fn request<'a>(
+    target: &'a RedeployTarget,
+    result: &'a Result<RedeployJob, RedeployError>,
+) -> RedeployRequestEvent<'a> {
+    RedeployRequestEvent {
+        target,
+        result,
+        requester: requester(),
+        requested_at: chrono::Utc::now(),
+        latency: Duration::from_millis(10),
+        problem_name: None,
+        message_link: None,
+    }
+}
+
+#[tokio::test]
+async fn filters_by_outcome() {
+    let (notifier, events) = filtered("outcomes: [failed, timed_out, unavailable]");
+    let target = target("team1", "ABC");
+
+    notifier.notify(&request(&target, &Ok(job(&target)))).await;
+    notifier
+        .notify(&request(
+            &target,
+            &Err(RedeployError::Unexpected("down".into())),
+        ))
+        .await;
+    notifier
+        .notify_completion(
+            &target,
+            &job(&target),
+            &RedeployCompletion::TimedOut,
+            &requester(),
+        )
+        .await;
+    notifier
+        .notify_completion(
+            &target,
+            &job(&target),
+            &RedeployCompletion::Completed {
+                completed_at: chrono::Utc::now(),
+            },
+            &requester(),
+        )
+        .await;
+    notifier
+        .notify_completion(
+            &target,
+            &job(&target),
+            &RedeployCompletion::Failed {
+                completed_at: chrono::Utc::now(),
+            },
+            &requester(),
+        )
+        .await;
+    notifier.notify_alert(&RedeployAlert::Recovered).await;
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec!["failed", "completion", "completion"]
+    );
+}
+
+#[tokio::test]
+async fn filters_by_problem_code_and_team_id() {
+    let (notifier, events) = filtered(
+        r#"
+problem_codes: [ABC, DEF]
+team_ids: ["team1"]
+"#,
+    );
+
+    for target in [
+        target("team1", "ABC"),
+        target("team1", "XYZ"),
+        target("team2", "DEF"),
+    ] {
+        notifier.notify(&request(&target, &Ok(job(&target)))).await;
+    }
+    // 障害・復旧はどの問題にも紐づかないため通知しない。
+    notifier.notify_alert(&RedeployAlert::Recovered).await;
+
+    assert_eq!(*events.lock().unwrap(), vec!["requested"]);
+}
+
+#[test]
+fn parses_notifier_configuration_with_filter() {
+    let configs: Vec<RedeployNotifierConfiguration> = serde_yaml::from_str(
+        r#"
+- slack:
+    webhook_url: https://hooks.slack.com/services/XXX
+  filter:
+    outcomes: [failed]
+- log:
+"#,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        configs[0].notifier,
+        RedeployNotifiersConfiguration::Slack(_)
+    ));
+    assert!(configs[0].filter.outcomes.is_some());
+    assert!(matches!(
+        configs[1].notifier,
+        RedeployNotifiersConfiguration::Log
+    ));
+    assert!(configs[1].filter.outcomes.is_none());
+}
+
+#[test]
+fn rejects_filter_for_audit_log() {
+    // 監査ログには全ての再展開を記録するため、条件は指定できない。
+    let config: Configuration = serde_yaml::from_str(
+        r#"
+staff:
+  password: staff password
+discord:
+  token: xxxx
+  application_id: 1
+  guild_id: 1
+redeploy:
+  fake:
+  notifiers:
+  - audit_log:
+      path: /tmp/audit.jsonl
+    filter:
+      outcomes: [failed]
+"#,
+    )
+    .unwrap();
+
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("audit_log"), "{}", err);
+}
@@ -0,0 +1,426 @@
+use bot::models::Problem;
+use bot::services::redeploy::http::HttpRedeployConfig;
+use bot::services::redeploy::http::HttpRedeployService;
+use bot::services::redeploy::RedeployError;
+use bot::services::redeploy::RedeployService;
+use bot::services::redeploy::RedeployTarget;
+use chrono::TimeZone;
+use chrono::Utc;
+use wiremock::matchers::body_json;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+fn problems() -> Vec<Problem> {
+    vec![
+        Problem {
+            code: String::from("ABC"),
+            name: String::from("デフォルトルートが消えちゃった！"),
+            ..Default::default()
+        },
+        Problem {
+            code: String::from("DEF"),
+            name: String::from("DNSが引けない"),
+            ..Default::default()
+        },
+    ]
+}
+
+fn target() -> RedeployTarget {
+    RedeployTarget {
+        team_id: String::from("team7"),
+        problem_id: String::from("ABC"),
+        triggered_by_staff: false,
+        reason: None,
+    }
+}
+
+fn service(server: &MockServer, status: &str) -> HttpRedeployService {
+    let config = format!(
+        r#"
+auth:
+  bearer:
+    token: secret
+redeploy:
+  method: post
+  url: {uri}/teams/{{team_id}}/redeploys
+  body: '{{"problem": "{{problem_code}}"}}'
+  response:
+    job_id: /job/id
+status:
+  url: {uri}{status}
+"#,
+        uri = server.uri(),
+        status = status
+    );
+    let config: HttpRedeployConfig = serde_yaml::from_str(&config).unwrap();
+    HttpRedeployService::new(config, problems()).unwrap()
+}
+
+#[tokio::test]
+async fn redeploy_maps_job_from_response() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/teams/team7/redeploys"))
+        .and(header("authorization", "Bearer secret"))
+        .and(body_json(serde_json::json!({ "problem": "ABC" })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(serde_json::json!({ "job": { "id": 42 } })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let job = service(&server, "/unused")
+        .redeploy(&target())
+        .await
+        .unwrap();
+    assert_eq!(job.id, "42");
+    assert_eq!(job.team_id, "team7");
+    assert_eq!(job.problem_code, "ABC");
+}
+
+#[tokio::test]
+async fn redeploy_maps_error_statuses() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(409).set_body_string("already running"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+
+    let service = service(&server, "/unused");
+    assert!(matches!(
+        service.redeploy(&target()).await,
+        Err(RedeployError::AnotherJobInQueue(body)) if body == "already running"
+    ));
+    assert!(matches!(
+        service.redeploy(&target()).await,
+        Err(RedeployError::InvalidParameters)
+    ));
+}
+
+#[tokio::test]
+async fn get_status_per_problem() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/problems/ABC"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "started_at": "2025-03-01T10:00:00Z",
+            "completed_at": null,
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/problems/DEF"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "started_at": "2025-03-01T10:00:00+09:00",
+            "completed_at": "2025-03-01T10:05:00+09:00",
+        })))
+        .mount(&server)
+        .await;
+
+    let statuses = service(&server, "/teams/{team_id}/problems/{problem_code}")
+        .get_status("team7")
+        .await
+        .unwrap();
+
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses[0].problem_code, "ABC");
+    assert!(statuses[0].is_redeploying);
+    assert_eq!(
+        statuses[0].last_redeploy_started_at,
+        Some(Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap())
+    );
+    assert_eq!(statuses[1].problem_code, "DEF");
+    assert!(!statuses[1].is_redeploying);
+    assert_eq!(
+        statuses[1].last_redeploy_completed_at,
+        Some(Utc.with_ymd_and_hms(2025, 3, 1, 1, 5, 0).unwrap())
+    );
+}
+
+#[tokio::test]
+async fn get_status_from_item_list() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/status"))
+        .and(header("x-api-key", "secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "data": [
+                { "code": "abc", "busy": false, "last": { "start": null, "end": null } },
+                { "code": "def", "busy": true, "last": { "start": "2025-03-01T10:00:00Z", "end": null } },
+            ],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = format!(
+        r#"
+auth:
+  header:
+    name: X-API-Key
+    value: secret
+redeploy:
+  method: POST
+  url: {uri}/redeploy
+status:
+  url: {uri}/teams/{{team_id}}/status
+  response:
+    items: /data
+    problem_code: /code
+    is_redeploying: /busy
+    started_at: /last/start
+    completed_at: /last/end
+"#,
+        uri = server.uri()
+    );
+    let config: HttpRedeployConfig = serde_yaml::from_str(&config).unwrap();
+    let statuses = HttpRedeployService::new(config, problems())
+        .unwrap()
+        .get_status("team7")
+        .await
+        .unwrap();
+
+    assert_eq!(statuses.len(), 2);
+    // 問題コードは設定上の表記に揃えられる。
+    assert_eq!(statuses[0].problem_code, "ABC");
+    assert!(!statuses[0].is_redeploying);
+    assert_eq!(statuses[0].last_redeploy_started_at, None);
+    assert_eq!(statuses[1].problem_code, "DEF");
+    assert!(statuses[1].is_redeploying);
+}
+
+#[tokio::test]
+async fn redeploy_escapes_values_in_json_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_json(serde_json::json!({ "problem": "A\"B\\C" })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(serde_json::json!({ "job": { "id": 1 } })),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let target = RedeployTarget {
+        problem_id: String::from("A\"B\\C"),
+        ..target()
+    };
+    service(&server, "/unused").redeploy(&target).await.unwrap();
+}
+
+#[tokio::test]
+async fn get_status_per_problem_marks_failed_problems_unknown() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/problems/ABC"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "started_at": "2025-03-01T10:00:00Z",
+            "completed_at": "2025-03-01T10:05:00Z",
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/problems/DEF"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+
+    let statuses = service(&server, "/teams/{team_id}/problems/{problem_code}")
+        .get_status("team7")
+        .await
+        .unwrap();
+
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses[0].problem_code, "ABC");
+    assert!(!statuses[0].is_unknown);
+    assert_eq!(statuses[1].problem_code, "DEF");
+    assert!(statuses[1].is_unknown);
+}
+
+#[tokio::test]
+async fn get_status_per_problem_fails_when_all_problems_fail() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    assert!(service(&server, "/teams/{team_id}/problems/{problem_code}")
+        .get_status("team7")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn substitutes_backend_id_of_problem() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/teams/team7/redeploys"))
+        .and(body_json(
+            serde_json::json!({ "problem": "ABC", "backend": "abc-v2" }),
+        ))
+        .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({ "id": 1 })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/backend/team7/abc-v2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = format!(
+        r#"
+redeploy:
+  method: post
+  url: {uri}/teams/{{team_id}}/redeploys
+  body: '{{"problem": "{{problem_code}}", "backend": "{{backend_id}}"}}'
+status:
+  url: {uri}/backend/{{team_id}}/{{backend_id}}
+"#,
+        uri = server.uri()
+    );
+    let problems = vec![Problem {
+        code: String::from("ABC"),
+        name: String::from("デフォルトルートが消えちゃった！"),
+        backend_id: Some(String::from("abc-v2")),
+        ..Default::default()
+    }];
+    let service =
+        HttpRedeployService::new(serde_yaml::from_str(&config).unwrap(), problems).unwrap();
+
+    service.redeploy(&target()).await.unwrap();
+
+    let statuses = service.get_status("team7").await.unwrap();
+    assert_eq!(statuses[0].problem_code, "ABC");
+    assert!(!statuses[0].is_unknown);
+}
+
+#[tokio::test]
+async fn get_status_per_problem_skips_not_redeployable_problems() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/problems/ABC"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "started_at": null,
+            "completed_at": null,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // VMを持たない問題には、リクエストしない。
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/problems/DEF"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let config: HttpRedeployConfig = serde_yaml::from_str(&format!(
+        r#"
+redeploy:
+  url: {uri}/unused
+status:
+  url: {uri}/teams/{{team_id}}/problems/{{problem_code}}
+"#,
+        uri = server.uri()
+    ))
+    .unwrap();
+    let mut problems = problems();
+    problems[1].redeployable = Some(false);
+    let statuses = HttpRedeployService::new(config, problems)
+        .unwrap()
+        .get_status("team7")
+        .await
+        .unwrap();
+
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].problem_code, "ABC");
+}
+
+#[tokio::test]
+async fn get_status_from_item_list_marks_malformed_items_unknown() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/teams/team7/status"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "data": [
+                { "code": "abc", "start": "2025-03-01T10:00:00Z", "end": "2025-03-01T10:05:00Z" },
+                { "code": "def", "start": "not a datetime", "end": null },
+                { "start": null, "end": null },
+            ],
+        })))
+        .mount(&server)
+        .await;
+
+    let config: HttpRedeployConfig = serde_yaml::from_str(&format!(
+        r#"
+redeploy:
+  url: {uri}/unused
+status:
+  url: {uri}/teams/{{team_id}}/status
+  response:
+    items: /data
+    problem_code: /code
+    started_at: /start
+    completed_at: /end
+"#,
+        uri = server.uri()
+    ))
+    .unwrap();
+    let statuses = HttpRedeployService::new(config, problems())
+        .unwrap()
+        .get_status("team7")
+        .await
+        .unwrap();
+
+    // 問題コードの分からない要素は無視する。
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses[0].problem_code, "ABC");
+    assert!(!statuses[0].is_unknown);
+    assert_eq!(statuses[1].problem_code, "DEF");
+    assert!(statuses[1].is_unknown);
+}
+
+#[tokio::test]
+async fn get_status_times_out() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "data": [] }))
+                .set_delay(std::time::Duration::from_secs(3)),
+        )
+        .mount(&server)
+        .await;
+
+    let config: HttpRedeployConfig = serde_yaml::from_str(&format!(
+        r#"
+timeout_secs: 1
+redeploy:
+  url: {uri}/unused
+status:
+  url: {uri}/teams/{{team_id}}/status
+  response:
+    items: /data
+"#,
+        uri = server.uri()
+    ))
+    .unwrap();
+    assert!(HttpRedeployService::new(config, problems())
+        .unwrap()
+        .get_status("team7")
+        .await
+        .is_err());
+}
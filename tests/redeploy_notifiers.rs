@@ -0,0 +1,244 @@
+use std::time::Duration;
+
+use bot::services::redeploy::notifiers::sign;
+use bot::services::redeploy::notifiers::AuditLogRedeployNotifier;
+use bot::services::redeploy::notifiers::SlackRedeployNotifier;
+use bot::services::redeploy::notifiers::WebhookRedeployNotifier;
+use bot::services::redeploy::notifiers::WebhookRedeployNotifierConfig;
+use bot::services::redeploy::RedeployAlert;
+use bot::services::redeploy::RedeployCompletion;
+use bot::services::redeploy::RedeployError;
+use bot::services::redeploy::RedeployJob;
+use bot::services::redeploy::RedeployNotifier;
+use bot::services::redeploy::RedeployRequestEvent;
+use bot::services::redeploy::RedeployRequester;
+use bot::services::redeploy::RedeployTarget;
+use wiremock::matchers::body_json;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+fn target() -> RedeployTarget {
+    RedeployTarget {
+        team_id: String::from("team7"),
+        problem_id: String::from("ABC"),
+        triggered_by_staff: true,
+        reason: None,
+    }
+}
+
+fn job() -> Result<RedeployJob, RedeployError> {
+    Ok(RedeployJob {
+        id: String::from("job-1"),
+        team_id: String::from("team7"),
+        problem_code: String::from("ABC"),
+    })
+}
+
+fn requester() -> RedeployRequester {
+    RedeployRequester {
+        user_id: 42,
+        user_name: String::from("staff-user"),
+    }
+}
+
+fn request<'a>(
+    target: &'a RedeployTarget,
+    result: &'a Result<RedeployJob, RedeployError>,
+) -> RedeployRequestEvent<'a> {
+    RedeployRequestEvent {
+        target,
+        result,
+        requester: requester(),
+        requested_at: chrono::Utc::now(),
+        latency: Duration::from_millis(1234),
+        problem_name: Some(String::from("ネットワークが繋がらない")),
+        message_link: Some(String::from("https://discord.com/channels/1/2/3")),
+    }
+}
+
+#[test]
+fn sign_with_hmac_sha256() {
+    // RFC 4231 Test Case 2
+    assert_eq!(
+        sign("Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[tokio::test]
+async fn slack_notifier_posts_attachment() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(serde_json::json!({
+            "text": "再展開を開始しました",
+            "attachments": [{
+                "color": "#28a741",
+                "fields": [
+                    { "title": "チームID", "value": "team7" },
+                    { "title": "問題コード", "value": "ABC" },
+                    { "title": "問題名", "value": "ネットワークが繋がらない" },
+                    { "title": "再展開Job ID", "value": "job-1" },
+                    { "title": "実行者", "value": "staff-user（staffによるチームの代理）" },
+                ],
+            }],
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    SlackRedeployNotifier::new(&server.uri())
+        .unwrap()
+        .notify(&request(&target(), &job()))
+        .await;
+}
+
+#[tokio::test]
+async fn webhook_notifier_renders_template_and_signs_body() {
+    let server = MockServer::start().await;
+    let body =
+        r#"{"kind":"requested","team":"team7","by_staff":true,"user":"staff-user","latency":1234}"#;
+    Mock::given(method("POST"))
+        .and(header(
+            "x-signature-256",
+            sign("secret", body.as_bytes()).as_str(),
+        ))
+        .and(header("x-source", "ictsc"))
+        .and(body_json(serde_json::json!({
+            "kind": "requested",
+            "team": "team7",
+            "by_staff": true,
+            "user": "staff-user",
+            "latency": 1234,
+        })))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config: WebhookRedeployNotifierConfig = serde_yaml::from_str(&format!(
+        r#"
+url: {}
+headers:
+  X-Source: ictsc
+template: '{{"kind":"{{event}}","team":"{{team_id}}","by_staff":{{triggered_by_staff}},"user":"{{requester_name}}","latency":{{latency_ms}}}}'
+secret: secret
+"#,
+        server.uri()
+    ))
+    .unwrap();
+
+    WebhookRedeployNotifier::new(config)
+        .unwrap()
+        .notify(&request(&target(), &job()))
+        .await;
+}
+
+#[tokio::test]
+async fn webhook_notifier_does_not_render_placeholders_in_values() {
+    // 理由などユーザが入力した値に含まれる {name} は、置換せずにそのまま送る。
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_json(serde_json::json!({
+            "reason": "{team_id} {error} \"quoted\"",
+            "team": "team7",
+        })))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config: WebhookRedeployNotifierConfig = serde_yaml::from_str(&format!(
+        r#"
+url: {}
+template: '{{"reason":"{{reason}}","team":"{{team_id}}"}}'
+"#,
+        server.uri()
+    ))
+    .unwrap();
+
+    let target = RedeployTarget {
+        reason: Some(String::from("{team_id} {error} \"quoted\"")),
+        ..target()
+    };
+    WebhookRedeployNotifier::new(config)
+        .unwrap()
+        .notify(&request(&target, &job()))
+        .await;
+}
+
+#[tokio::test]
+async fn webhook_notifier_sends_notification_by_default() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(serde_json::json!({
+            "event": "unavailable",
+            "team_id": null,
+            "error": "connection \"refused\"",
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config: WebhookRedeployNotifierConfig =
+        serde_yaml::from_str(&format!("url: {}", server.uri())).unwrap();
+
+    WebhookRedeployNotifier::new(config)
+        .unwrap()
+        .notify_alert(&RedeployAlert::Unavailable {
+            consecutive_failures: 5,
+            retry_at: chrono::Utc::now(),
+            last_error: String::from("connection \"refused\""),
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn audit_log_notifier_appends_json_lines() {
+    let path = std::env::temp_dir().join(format!(
+        "ictsc-discord-bot-audit-{}.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    let target = RedeployTarget {
+        reason: Some(String::from("設定を誤って消してしまったため")),
+        ..target()
+    };
+    let notifier = AuditLogRedeployNotifier::new(&path);
+    notifier.notify(&request(&target, &job())).await;
+    notifier
+        .notify_completion(
+            &target,
+            job().as_ref().unwrap(),
+            &RedeployCompletion::TimedOut,
+            &requester(),
+        )
+        .await;
+
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["event"], "requested");
+    assert_eq!(lines[0]["team_id"], "team7");
+    assert_eq!(lines[0]["problem_code"], "ABC");
+    assert_eq!(lines[0]["requester_name"], "staff-user");
+    assert_eq!(lines[0]["reason"], "設定を誤って消してしまったため");
+    assert!(lines[0]["logged_at"].is_string());
+    assert!(lines[0]["requested_at"].is_string());
+    assert_eq!(lines[1]["event"], "timed_out");
+    assert_eq!(lines[1]["job_id"], "job-1");
+    // 完了の記録にも、再展開をリクエストしたユーザを残す。
+    assert_eq!(lines[1]["requester_id"], 42);
+    assert_eq!(lines[1]["requester_name"], "staff-user");
+}
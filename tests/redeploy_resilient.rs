@@ -0,0 +1,204 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bot::services::redeploy::resilient::ResilientRedeployConfig;
+use bot::services::redeploy::resilient::ResilientRedeployService;
+use bot::services::redeploy::RedeployAlert;
+use bot::services::redeploy::RedeployCompletion;
+use bot::services::redeploy::RedeployError;
+use bot::services::redeploy::RedeployJob;
+use bot::services::redeploy::RedeployNotifier;
+use bot::services::redeploy::RedeployRequestEvent;
+use bot::services::redeploy::RedeployRequester;
+use bot::services::redeploy::RedeployService;
+use bot::services::redeploy::RedeployStatus;
+use bot::services::redeploy::RedeployTarget;
+
+// 指定された回数だけ失敗した後に成功するRedeployService
+struct FlakyRedeployService {
+    calls: Arc<AtomicUsize>,
+    failures: usize,
+
+    // 応答までにかかる時間
+    delay: Duration,
+}
+
+impl FlakyRedeployService {
+    async fn call(&self) -> Result<(), RedeployError> {
+        tokio::time::sleep(self.delay).await;
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Err(RedeployError::Unexpected("connection reset".into()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RedeployService for FlakyRedeployService {
+    async fn redeploy(&self, target: &RedeployTarget) -> Result<RedeployJob, RedeployError> {
+        self.call().await?;
+        Ok(RedeployJob {
+            id: String::from("job"),
+            team_id: target.team_id.clone(),
+            problem_code: target.problem_id.clone(),
+        })
+    }
+
+    async fn get_status(&self, team_id: &str) -> Result<Vec<RedeployStatus>, RedeployError> {
+        self.call().await?;
+        Ok(vec![RedeployStatus::unknown(team_id, "ABC")])
+    }
+}
+
+#[derive(Default)]
+struct RecordingNotifier {
+    alerts: Arc<Mutex<Vec<RedeployAlert>>>,
+}
+
+#[async_trait]
+impl RedeployNotifier for RecordingNotifier {
+    async fn notify(&self, _: &RedeployRequestEvent<'_>) {}
+
+    async fn notify_completion(
+        &self,
+        _: &RedeployTarget,
+        _: &RedeployJob,
+        _: &RedeployCompletion,
+        _: &RedeployRequester,
+    ) {
+    }
+
+    async fn notify_alert(&self, alert: &RedeployAlert) {
+        self.alerts.lock().unwrap().push(alert.clone());
+    }
+}
+
+struct Fixture {
+    service: ResilientRedeployService,
+    calls: Arc<AtomicUsize>,
+    alerts: Arc<Mutex<Vec<RedeployAlert>>>,
+}
+
+fn fixture(failures: usize, open_duration: Duration) -> Fixture {
+    fixture_with_delay(failures, open_duration, Duration::ZERO)
+}
+
+fn fixture_with_delay(failures: usize, open_duration: Duration, delay: Duration) -> Fixture {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let notifier = RecordingNotifier::default();
+    let alerts = notifier.alerts.clone();
+    let service = ResilientRedeployService::new(
+        Box::new(FlakyRedeployService {
+            calls: calls.clone(),
+            failures,
+            delay,
+        }),
+        ResilientRedeployConfig {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            failure_threshold: 2,
+            open_duration,
+        },
+        Arc::new(vec![Box::new(notifier)]),
+    );
+    Fixture {
+        service,
+        calls,
+        alerts,
+    }
+}
+
+fn target() -> RedeployTarget {
+    RedeployTarget {
+        team_id: String::from("team1"),
+        problem_id: String::from("ABC"),
+        triggered_by_staff: false,
+        reason: None,
+    }
+}
+
+#[tokio::test]
+async fn retries_get_status() {
+    let fixture = fixture(2, Duration::from_secs(60));
+
+    fixture.service.get_status("team1").await.unwrap();
+    assert_eq!(fixture.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn never_retries_redeploy() {
+    let fixture = fixture(1, Duration::from_secs(60));
+
+    assert!(fixture.service.redeploy(&target()).await.is_err());
+    assert_eq!(fixture.calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn opens_circuit_and_alerts() {
+    let fixture = fixture(100, Duration::from_millis(200));
+
+    assert!(fixture.service.redeploy(&target()).await.is_err());
+    assert!(fixture.service.redeploy(&target()).await.is_err());
+    assert_eq!(fixture.calls.load(Ordering::SeqCst), 2);
+
+    // 回路が開いている間は、再展開システムに問い合わせない。
+    assert!(matches!(
+        fixture.service.get_status("team1").await,
+        Err(RedeployError::Unavailable)
+    ));
+    assert_eq!(fixture.calls.load(Ordering::SeqCst), 2);
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(matches!(
+        fixture.alerts.lock().unwrap().as_slice(),
+        [RedeployAlert::Unavailable {
+            consecutive_failures: 2,
+            ..
+        }]
+    ));
+}
+
+#[tokio::test]
+async fn closes_circuit_after_recovery() {
+    let fixture = fixture(2, Duration::from_millis(100));
+
+    assert!(fixture.service.redeploy(&target()).await.is_err());
+    assert!(fixture.service.redeploy(&target()).await.is_err());
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    fixture.service.redeploy(&target()).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(matches!(
+        fixture.alerts.lock().unwrap().as_slice(),
+        [RedeployAlert::Unavailable { .. }, RedeployAlert::Recovered]
+    ));
+}
+
+#[tokio::test]
+async fn allows_only_one_probe_while_half_open() {
+    let fixture = fixture_with_delay(2, Duration::from_millis(100), Duration::from_millis(50));
+
+    assert!(fixture.service.redeploy(&target()).await.is_err());
+    assert!(fixture.service.redeploy(&target()).await.is_err());
+
+    // 期限を過ぎた後、同時に届いたリクエストのうち1つだけを再展開システムに通す。
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let target = target();
+    let (first, second) = tokio::join!(
+        fixture.service.redeploy(&target),
+        fixture.service.redeploy(&target)
+    );
+    assert!(first.is_ok());
+    assert!(matches!(second, Err(RedeployError::Unavailable)));
+    assert_eq!(fixture.calls.load(Ordering::SeqCst), 3);
+
+    // 試したリクエストが成功すれば、回路が閉じて全てのリクエストを通す。
+    fixture.service.redeploy(&target).await.unwrap();
+    assert_eq!(fixture.calls.load(Ordering::SeqCst), 4);
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use bot::models::Problem;
+use bot::services::redeploy::RState;
+use bot::services::redeploy::RStateConfig;
+use bot::services::redeploy::RedeployService;
+use bot::services::redeploy::RedeployTarget;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+fn problem(code: &str) -> Problem {
+    Problem {
+        code: code.to_string(),
+        name: code.to_string(),
+        ..Default::default()
+    }
+}
+
+fn rstate(server: &MockServer, problems: Vec<Problem>) -> RState {
+    RState::new(RStateConfig {
+        baseurl: server.uri(),
+        username: String::from("user"),
+        password: String::from("password"),
+        problems,
+        max_concurrency: 2,
+        timeout: Duration::from_millis(500),
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn get_status_returns_partial_results() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/backend/team7/abc"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "available": false,
+            "created_time": "2025-03-01T10:00:00Z",
+            "completed_time": null,
+        })))
+        .mount(&server)
+        .await;
+    // タイムアウトより遅いレスポンス
+    Mock::given(method("GET"))
+        .and(path("/backend/team7/def"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "available": true }))
+                .set_delay(Duration::from_secs(5)),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/backend/team7/ghi"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("not json"))
+        .mount(&server)
+        .await;
+
+    let statuses = rstate(
+        &server,
+        vec![problem("ABC"), problem("DEF"), problem("GHI")],
+    )
+    .get_status("team7")
+    .await
+    .unwrap();
+
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(statuses[0].problem_code, "ABC");
+    assert!(!statuses[0].is_unknown);
+    assert!(statuses[0].is_redeploying);
+    assert_eq!(statuses[1].problem_code, "DEF");
+    assert!(statuses[1].is_unknown);
+    assert_eq!(statuses[2].problem_code, "GHI");
+    assert!(statuses[2].is_unknown);
+}
+
+#[tokio::test]
+async fn get_status_fails_when_every_request_fails() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(502))
+        .mount(&server)
+        .await;
+
+    assert!(rstate(&server, vec![problem("ABC"), problem("DEF")])
+        .get_status("team7")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn uses_backend_id_and_skips_non_redeployable_problems() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/backend/team7/abc-v2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "available": true,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/admin/postJob"))
+        .and(body_string_contains("prob_id=abc-v2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "job-1",
+            "team_id": "team7",
+            "prob_id": "abc-v2",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let rstate = rstate(
+        &server,
+        vec![
+            Problem {
+                backend_id: Some(String::from("abc-v2")),
+                ..problem("ABC")
+            },
+            Problem {
+                redeployable: Some(false),
+                ..problem("DEF")
+            },
+        ],
+    );
+
+    let statuses = rstate.get_status("team7").await.unwrap();
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].problem_code, "ABC");
+
+    rstate
+        .redeploy(&RedeployTarget {
+            team_id: String::from("team7"),
+            problem_id: String::from("ABC"),
+            triggered_by_staff: false,
+            reason: None,
+        })
+        .await
+        .unwrap();
+}
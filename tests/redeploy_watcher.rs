@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use bot::config::RedeployWatcherConfiguration;
+use bot::services::redeploy::find_redeploy_completion;
+use bot::services::redeploy::wait_for_redeploy_completion;
+use bot::services::redeploy::FakeRedeployService;
+use bot::services::redeploy::RedeployCompletion;
+use bot::services::redeploy::RedeployService;
+use bot::services::redeploy::RedeployTarget;
+use bot::services::redeploy::CLOCK_SKEW_TOLERANCE_SECS;
+use chrono::DateTime;
+use chrono::Utc;
+
+fn intervals(initial_interval_secs: u64, max_interval_secs: u64, count: usize) -> Vec<u64> {
+    RedeployWatcherConfiguration {
+        initial_interval_secs,
+        max_interval_secs,
+        timeout_secs: 1800,
+    }
+    .poll_intervals()
+    .take(count)
+    .map(|interval| interval.as_secs())
+    .collect()
+}
+
+#[test]
+fn doubles_poll_interval_up_to_max() {
+    assert_eq!(intervals(10, 60, 6), [10, 20, 40, 60, 60, 60]);
+}
+
+#[test]
+fn caps_initial_poll_interval_at_max() {
+    assert_eq!(intervals(90, 60, 3), [60, 60, 60]);
+}
+
+#[test]
+fn uses_default_poll_intervals() {
+    let intervals: Vec<_> = RedeployWatcherConfiguration::default()
+        .poll_intervals()
+        .take(4)
+        .collect();
+
+    assert_eq!(intervals, [10, 20, 40, 60].map(Duration::from_secs));
+}
+
+fn target(problem_id: &str) -> RedeployTarget {
+    RedeployTarget {
+        team_id: String::from("team-01"),
+        problem_id: problem_id.to_string(),
+        triggered_by_staff: false,
+        reason: None,
+    }
+}
+
+async fn wait(problem_id: &str, requested_at: DateTime<Utc>) -> RedeployCompletion {
+    wait_for_redeploy_completion(
+        &FakeRedeployService,
+        &target(problem_id),
+        requested_at,
+        std::iter::repeat(Duration::from_millis(10)),
+        Duration::from_millis(50),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn detects_completed_redeploy() {
+    let completion = wait("GHI", Utc::now()).await;
+    assert!(matches!(completion, RedeployCompletion::Completed { .. }));
+}
+
+#[tokio::test]
+async fn detects_failed_redeploy() {
+    let completion = wait("JKL", Utc::now()).await;
+    assert!(matches!(completion, RedeployCompletion::Failed { .. }));
+}
+
+#[tokio::test]
+async fn times_out_while_redeploying() {
+    let completion = wait("DEF", Utc::now()).await;
+    assert!(matches!(completion, RedeployCompletion::TimedOut));
+}
+
+#[tokio::test]
+async fn times_out_without_completion_time() {
+    let completion = wait("ABC", Utc::now()).await;
+    assert!(matches!(completion, RedeployCompletion::TimedOut));
+}
+
+#[tokio::test]
+async fn tolerates_clock_skew_of_completion_time() {
+    let requested_at = Utc::now() + chrono::Duration::seconds(CLOCK_SKEW_TOLERANCE_SECS - 10);
+    let completion = wait("GHI", requested_at).await;
+    assert!(matches!(completion, RedeployCompletion::Completed { .. }));
+}
+
+#[tokio::test]
+async fn ignores_completion_of_previous_redeploy() {
+    let requested_at = Utc::now() + chrono::Duration::seconds(CLOCK_SKEW_TOLERANCE_SECS + 10);
+    let completion = wait("GHI", requested_at).await;
+    assert!(matches!(completion, RedeployCompletion::TimedOut));
+}
+
+#[tokio::test]
+async fn finds_completion_from_statuses() {
+    let statuses = FakeRedeployService.get_status("team-01").await.unwrap();
+    let requested_at = Utc::now();
+
+    assert!(find_redeploy_completion(&statuses, "ABC", requested_at).is_none());
+    assert!(find_redeploy_completion(&statuses, "DEF", requested_at).is_none());
+    assert!(find_redeploy_completion(&statuses, "XYZ", requested_at).is_none());
+    assert!(matches!(
+        find_redeploy_completion(&statuses, "GHI", requested_at),
+        Some(RedeployCompletion::Completed { .. })
+    ));
+    assert!(matches!(
+        find_redeploy_completion(&statuses, "JKL", requested_at),
+        Some(RedeployCompletion::Failed { .. })
+    ));
+}
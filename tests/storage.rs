@@ -0,0 +1,455 @@
+use bot::config::Configuration;
+use bot::config::StorageConfiguration;
+use bot::services::storage::InMemoryStorage;
+use bot::services::storage::JoinRecord;
+use bot::services::storage::QuestionRecord;
+use bot::services::storage::QuestionStatus;
+use bot::services::storage::QuestionTicket;
+use bot::services::storage::RedeployLimitResetRecord;
+use bot::services::storage::RedeployRecord;
+use bot::services::storage::SqliteStorage;
+use bot::services::storage::Storage;
+use chrono::TimeZone;
+use chrono::Utc;
+
+fn redeploy_record(team_id: &str, problem_code: &str) -> RedeployRecord {
+    RedeployRecord {
+        requested_at: Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap(),
+        team_id: team_id.to_string(),
+        problem_code: problem_code.to_string(),
+        user_id: 123456789012345678,
+        user_name: String::from("alice"),
+        triggered_by_staff: false,
+        job_id: Some(String::from("job-1")),
+        error: None,
+        completed_at: None,
+        timed_out: false,
+    }
+}
+
+async fn check_storage(storage: &dyn Storage) {
+    let first = storage
+        .record_redeploy(&redeploy_record("team7", "ABC"))
+        .await
+        .unwrap();
+    storage
+        .record_redeploy(&redeploy_record("team7", "DEF"))
+        .await
+        .unwrap();
+
+    let completed_at = Utc.with_ymd_and_hms(2025, 3, 1, 10, 5, 0).unwrap();
+    storage
+        .record_redeploy_completion(first, Some(completed_at), false)
+        .await
+        .unwrap();
+    assert!(storage
+        .record_redeploy_completion(first + 100, None, true)
+        .await
+        .is_err());
+
+    let redeploys = storage.list_redeploys().await.unwrap();
+    assert_eq!(redeploys.len(), 2);
+    assert_eq!(redeploys[0].problem_code, "ABC");
+    assert_eq!(redeploys[0].user_id, 123456789012345678);
+    assert_eq!(redeploys[0].completed_at, Some(completed_at));
+    assert_eq!(redeploys[1].problem_code, "DEF");
+    assert_eq!(redeploys[1].completed_at, None);
+
+    storage
+        .record_question(&QuestionRecord {
+            created_at: Utc::now(),
+            team_id: Some(String::from("team3")),
+            user_id: 1,
+            user_name: String::from("bob"),
+            title: String::from("問題ABCの初期条件について"),
+            channel_id: 2,
+            thread_id: 3,
+        })
+        .await
+        .unwrap();
+    let questions = storage.list_questions().await.unwrap();
+    assert_eq!(questions.len(), 1);
+    assert_eq!(questions[0].title, "問題ABCの初期条件について");
+
+    storage
+        .record_join(&JoinRecord {
+            joined_at: Utc::now(),
+            user_id: 4,
+            user_name: String::from("carol"),
+            team_id: None,
+            role_name: String::from("ICTSC2025 Staff"),
+        })
+        .await
+        .unwrap();
+    let joins = storage.list_joins().await.unwrap();
+    assert_eq!(joins.len(), 1);
+    assert_eq!(joins[0].team_id, None);
+}
+
+#[tokio::test]
+async fn in_memory_storage_records_history() {
+    check_storage(&InMemoryStorage::default()).await;
+}
+
+#[tokio::test]
+async fn sqlite_storage_records_history() {
+    check_storage(&SqliteStorage::open_in_memory().unwrap()).await;
+}
+
+async fn check_redeploy_confirmations(storage: &dyn Storage) {
+    let now = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+    let expires_at = now + chrono::Duration::minutes(5);
+
+    // 同じ確認は一度しか使えない。
+    assert!(storage
+        .consume_redeploy_confirmation("team1:ABC:1:00000001", expires_at, now)
+        .await
+        .unwrap());
+    assert!(!storage
+        .consume_redeploy_confirmation("team1:ABC:1:00000001", expires_at, now)
+        .await
+        .unwrap());
+    assert!(storage
+        .consume_redeploy_confirmation("team1:ABC:1:00000002", expires_at, now)
+        .await
+        .unwrap());
+
+    // 有効期限を過ぎた記録は削除される（ボタン自体は署名の検証で拒否される）。
+    let later = expires_at + chrono::Duration::seconds(1);
+    assert!(storage
+        .consume_redeploy_confirmation("team1:ABC:1:00000001", later, later)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn in_memory_storage_consumes_redeploy_confirmations() {
+    check_redeploy_confirmations(&InMemoryStorage::default()).await;
+}
+
+#[tokio::test]
+async fn sqlite_storage_consumes_redeploy_confirmations() {
+    check_redeploy_confirmations(&SqliteStorage::open_in_memory().unwrap()).await;
+}
+
+async fn check_pending_redeploys(storage: &dyn Storage) {
+    let completed = storage
+        .record_redeploy(&redeploy_record("team1", "ABC"))
+        .await
+        .unwrap();
+    let timed_out = storage
+        .record_redeploy(&redeploy_record("team1", "DEF"))
+        .await
+        .unwrap();
+    let pending = storage
+        .record_redeploy(&redeploy_record("team2", "ABC"))
+        .await
+        .unwrap();
+    storage
+        .record_redeploy(&RedeployRecord {
+            job_id: None,
+            error: Some(String::from("unavailable")),
+            ..redeploy_record("team2", "DEF")
+        })
+        .await
+        .unwrap();
+
+    let completed_at = Utc.with_ymd_and_hms(2025, 3, 1, 10, 5, 0).unwrap();
+    storage
+        .record_redeploy_completion(completed, Some(completed_at), false)
+        .await
+        .unwrap();
+    storage
+        .record_redeploy_completion(timed_out, None, true)
+        .await
+        .unwrap();
+
+    // 完了もタイムアウトもしておらず、Jobが開始された記録だけが返される。
+    let records = storage.list_pending_redeploys().await.unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].0, pending);
+    assert_eq!(records[0].1.team_id, "team2");
+    assert_eq!(records[0].1.problem_code, "ABC");
+
+    // 返されたIDで完了を記録できる。
+    storage
+        .record_redeploy_completion(records[0].0, Some(completed_at), false)
+        .await
+        .unwrap();
+    assert!(storage.list_pending_redeploys().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn in_memory_storage_lists_pending_redeploys() {
+    check_pending_redeploys(&InMemoryStorage::default()).await;
+}
+
+#[tokio::test]
+async fn sqlite_storage_lists_pending_redeploys() {
+    check_pending_redeploys(&SqliteStorage::open_in_memory().unwrap()).await;
+}
+
+async fn check_redeploy_limits(storage: &dyn Storage) {
+    storage
+        .record_redeploy(&redeploy_record("team7", "ABC"))
+        .await
+        .unwrap();
+
+    // staffによる代理実行と、受け付けられなかったリクエストは数えない。
+    let mut by_staff = redeploy_record("team7", "ABC");
+    by_staff.triggered_by_staff = true;
+    storage.record_redeploy(&by_staff).await.unwrap();
+    let mut failed = redeploy_record("team7", "ABC");
+    failed.job_id = None;
+    storage.record_redeploy(&failed).await.unwrap();
+
+    assert_eq!(
+        storage.count_redeploys("team7", "ABC", None).await.unwrap(),
+        1
+    );
+    assert_eq!(
+        storage.count_redeploys("team7", "DEF", None).await.unwrap(),
+        0
+    );
+
+    assert_eq!(
+        storage
+            .last_redeploy_limit_reset("team7", "ABC")
+            .await
+            .unwrap(),
+        None
+    );
+
+    let reset_at = Utc.with_ymd_and_hms(2025, 3, 1, 11, 0, 0).unwrap();
+    storage
+        .record_redeploy_limit_reset(&RedeployLimitResetRecord {
+            reset_at,
+            team_id: String::from("team7"),
+            problem_code: None,
+            user_id: 1,
+            user_name: String::from("staff"),
+        })
+        .await
+        .unwrap();
+
+    let since = storage
+        .last_redeploy_limit_reset("team7", "DEF")
+        .await
+        .unwrap();
+    assert_eq!(since, Some(reset_at));
+    assert_eq!(
+        storage
+            .count_redeploys("team7", "ABC", since)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn in_memory_storage_counts_redeploys() {
+    check_redeploy_limits(&InMemoryStorage::default()).await;
+}
+
+#[tokio::test]
+async fn sqlite_storage_counts_redeploys() {
+    check_redeploy_limits(&SqliteStorage::open_in_memory().unwrap()).await;
+}
+
+#[test]
+fn export_history_as_csv_and_json() {
+    let records = vec![redeploy_record("team7", "ABC")];
+
+    let mut csv = Vec::new();
+    bot::services::storage::write_csv(&mut csv, &records).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("requested_at,team_id,problem_code"));
+    assert!(lines.next().unwrap().contains(",team7,ABC,"));
+
+    let mut json = Vec::new();
+    bot::services::storage::write_json(&mut json, &records).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json[0]["team_id"], "team7");
+}
+
+async fn check_question_tickets(storage: &dyn Storage) {
+    let now = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+    assert_eq!(storage.get_question_ticket(3).await.unwrap(), None);
+
+    let mut ticket = QuestionTicket::open(3, Some(4), now);
+    storage.save_question_ticket(&ticket).await.unwrap();
+    storage
+        .save_question_ticket(&QuestionTicket::open(1, None, now))
+        .await
+        .unwrap();
+
+    storage.record_question_staff_reply(3, now).await.unwrap();
+    storage.record_question_escalation(3, 2).await.unwrap();
+    // 既により大きい回数が記録されている場合は、減らさない。
+    storage.record_question_escalation(3, 1).await.unwrap();
+
+    // 古いチケットを保存しても、再通知の状態は上書きされない。
+    ticket.claim(5, "staff", now).unwrap();
+    storage.save_question_ticket(&ticket).await.unwrap();
+
+    let saved = storage.get_question_ticket(3).await.unwrap().unwrap();
+    assert_eq!(saved.status, QuestionStatus::Claimed);
+    assert_eq!(saved.assignee_id, Some(5));
+    assert_eq!(saved.assignee_name.as_deref(), Some("staff"));
+    assert_eq!(saved.message_id, Some(4));
+    assert_eq!(saved.last_staff_reply_at, Some(now));
+    assert_eq!(saved.escalation_count, 2);
+
+    let tickets = storage.list_question_tickets().await.unwrap();
+    assert_eq!(tickets.len(), 2);
+    assert_eq!(tickets[0].thread_id, 1);
+    assert_eq!(tickets[0].status, QuestionStatus::Open);
+
+    // staffの投稿で回答待ちが解除され、その後のチームの最初の投稿から再び待ち始める。
+    let later = now + chrono::Duration::minutes(10);
+    assert_eq!(saved.waiting_since, None);
+    storage
+        .record_question_team_message(3, later)
+        .await
+        .unwrap();
+    storage
+        .record_question_team_message(3, later + chrono::Duration::minutes(1))
+        .await
+        .unwrap();
+    let saved = storage.get_question_ticket(3).await.unwrap().unwrap();
+    assert_eq!(saved.waiting_since, Some(later));
+    assert_eq!(saved.escalation_count, 0);
+
+    // チケットがないスレッドは無視する。
+    storage.record_question_team_message(9, now).await.unwrap();
+    storage.record_question_staff_reply(9, now).await.unwrap();
+    storage.record_question_escalation(9, 1).await.unwrap();
+    assert_eq!(storage.get_question_ticket(9).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn in_memory_storage_saves_question_tickets() {
+    check_question_tickets(&InMemoryStorage::default()).await;
+}
+
+#[tokio::test]
+async fn sqlite_storage_saves_question_tickets() {
+    check_question_tickets(&SqliteStorage::open_in_memory().unwrap()).await;
+}
+
+#[tokio::test]
+async fn sqlite_storage_adds_columns_to_existing_database() {
+    let path = std::env::temp_dir().join(format!(
+        "ictsc-discord-bot-migration-{}.sqlite",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    // 再通知の列を追加する前のスキーマで作成されたデータベース
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE questions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                team_id TEXT,
+                user_id INTEGER NOT NULL,
+                user_name TEXT NOT NULL,
+                title TEXT NOT NULL,
+                channel_id INTEGER NOT NULL,
+                thread_id INTEGER NOT NULL
+            );
+            INSERT INTO questions (created_at, user_id, user_name, title, channel_id, thread_id)
+                VALUES ('2025-03-01T09:00:00Z', 1, 'alice', 'question', 2, 3);
+            CREATE TABLE question_tickets (
+                thread_id INTEGER PRIMARY KEY,
+                status TEXT NOT NULL,
+                assignee_id INTEGER,
+                assignee_name TEXT,
+                message_id INTEGER,
+                updated_at TEXT NOT NULL
+            );
+            INSERT INTO question_tickets (thread_id, status, updated_at)
+                VALUES (3, 'open', '2025-03-01T10:00:00Z');",
+        )
+        .unwrap();
+    drop(connection);
+
+    let storage = SqliteStorage::open(&path).unwrap();
+    let ticket = storage.get_question_ticket(3).await.unwrap().unwrap();
+    assert_eq!(ticket.status, QuestionStatus::Open);
+    assert_eq!(ticket.last_staff_reply_at, None);
+    assert_eq!(ticket.escalation_count, 0);
+    // staffがまだ投稿していない質問は、質問の作成時から回答を待っている。
+    assert_eq!(
+        ticket.waiting_since,
+        Some(Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap())
+    );
+    drop(storage);
+
+    // 既に列がある場合は、何もしない。
+    SqliteStorage::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn question_ticket_lifecycle() {
+    let now = Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap();
+    let mut ticket = QuestionTicket::open(1, None, now);
+
+    // 担当者がいない状態で回答した場合は、回答したstaffが担当者になる。
+    ticket.resolve(2, "alice", now).unwrap();
+    assert_eq!(ticket.status, QuestionStatus::Answered);
+    assert_eq!(ticket.assignee_id, Some(2));
+
+    // 回答済みの質問も、別のstaffが引き継げる。
+    ticket.claim(3, "bob", now).unwrap();
+    assert_eq!(ticket.status, QuestionStatus::Claimed);
+    assert_eq!(ticket.assignee_name.as_deref(), Some("bob"));
+
+    ticket.resolve(2, "alice", now).unwrap();
+    assert_eq!(ticket.assignee_id, Some(3));
+
+    ticket.close(now);
+    assert!(ticket.claim(2, "alice", now).is_err());
+    assert!(ticket.resolve(2, "alice", now).is_err());
+    assert_eq!(ticket.status, QuestionStatus::Closed);
+}
+
+fn storage_configuration(storage: &str) -> StorageConfiguration {
+    let config: Configuration = serde_yaml::from_str(&format!(
+        r#"
+staff:
+  password: staff password
+discord:
+  token: xxxx
+  application_id: 1
+  guild_id: 1
+{}
+"#,
+        storage
+    ))
+    .unwrap();
+    config.storage
+}
+
+#[test]
+fn defaults_storage_to_sqlite() {
+    // 省略した場合は、再起動しても履歴が失われないようにSQLiteに保存する。
+    match storage_configuration("") {
+        StorageConfiguration::Sqlite(sqlite) => assert_eq!(sqlite.path, "bot.sqlite3"),
+        StorageConfiguration::Memory => panic!("storage should default to sqlite"),
+    }
+    match storage_configuration("storage:\n  sqlite: {}") {
+        StorageConfiguration::Sqlite(sqlite) => assert_eq!(sqlite.path, "bot.sqlite3"),
+        StorageConfiguration::Memory => panic!("storage should be sqlite"),
+    }
+    assert!(matches!(
+        storage_configuration("storage: memory"),
+        StorageConfiguration::Memory
+    ));
+}
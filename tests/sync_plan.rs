@@ -0,0 +1,362 @@
+use bot::bot::channels::plan_channel_actions;
+use bot::bot::channels::ChannelSyncAction;
+use bot::bot::helpers::channels::GuildChannelDefinition;
+use bot::bot::helpers::channels::GuildChannelDefinitionBuilder;
+use bot::bot::helpers::roles::RoleDefinition;
+use bot::bot::helpers::roles::RoleDefinitionBuilder;
+use bot::bot::plan::SyncPlan;
+use bot::bot::roles::plan_role_actions;
+use bot::bot::roles::RoleSyncAction;
+use serenity::model::prelude::*;
+
+fn role(id: u64, name: &str) -> Role {
+    let mut role = Role::default();
+    role.id = RoleId::new(id);
+    role.name = name.to_string();
+    role
+}
+
+fn channel(id: u64, name: &str, kind: ChannelType, parent_id: Option<u64>) -> GuildChannel {
+    let mut channel = GuildChannel::default();
+    channel.id = ChannelId::new(id);
+    channel.name = name.to_string();
+    channel.kind = kind;
+    channel.parent_id = parent_id.map(ChannelId::new);
+    channel
+}
+
+#[test]
+fn shows_children_of_renamed_category_under_new_name() {
+    let mut plan = SyncPlan::default();
+
+    let staff_category = channel(10, "ICTSC2024 Staff", ChannelType::Category, None);
+    let category_map = plan.add_categories(
+        vec![staff_category.clone()],
+        vec![ChannelSyncAction::Update(
+            staff_category,
+            GuildChannelDefinitionBuilder::default()
+                .name(String::from("ICTSC2025 Staff"))
+                .kind(ChannelType::Category)
+                .build()
+                .unwrap(),
+        )],
+    );
+
+    assert_eq!(
+        category_map.get("ICTSC2025 Staff"),
+        Some(&ChannelId::new(10))
+    );
+    assert_eq!(category_map.get("ICTSC2024 Staff"), None);
+
+    plan.add_channels(vec![ChannelSyncAction::Update(
+        channel(11, "staff-text", ChannelType::Text, Some(10)),
+        GuildChannelDefinitionBuilder::default()
+            .name(String::from("staff-text"))
+            .kind(ChannelType::Text)
+            .topic(Some(String::from("staff only")))
+            .category(Some(ChannelId::new(10)))
+            .build()
+            .unwrap(),
+    )]);
+
+    let output = plan.to_string();
+    assert!(output.contains("~ update text channel \"staff-text\" (category: ICTSC2025 Staff)"));
+    assert!(!output.contains("(category: ICTSC2024 Staff)"));
+}
+
+#[test]
+fn resolves_planned_roles_and_categories_with_placeholder_ids() {
+    let mut plan = SyncPlan::default();
+
+    let roles = plan.add_roles(
+        vec![
+            role(100, "@everyone"),
+            role(101, "ICTSC2024 Staff"),
+            role(102, "old team"),
+        ],
+        vec![
+            RoleSyncAction::Update(
+                role(101, "ICTSC2024 Staff"),
+                RoleDefinitionBuilder::default()
+                    .name(String::from("ICTSC2025 Staff"))
+                    .permissions(Permissions::empty())
+                    .build()
+                    .unwrap(),
+            ),
+            RoleSyncAction::Create(
+                RoleDefinitionBuilder::default()
+                    .name(String::from("team1"))
+                    .permissions(Permissions::empty())
+                    .build()
+                    .unwrap(),
+            ),
+            RoleSyncAction::Delete(role(102, "old team")),
+        ],
+    );
+
+    let names: Vec<_> = roles.iter().map(|role| role.name.as_str()).collect();
+    assert_eq!(names, ["@everyone", "ICTSC2025 Staff", "team1"]);
+
+    let category_map = plan.add_categories(
+        Vec::new(),
+        vec![ChannelSyncAction::Create(
+            GuildChannelDefinitionBuilder::default()
+                .name(String::from("team1"))
+                .kind(ChannelType::Category)
+                .build()
+                .unwrap(),
+        )],
+    );
+
+    // 仮のIDは、ロールとカテゴリで重複しないよう割り当てられる。
+    let team_role_id = roles.iter().find(|role| role.name == "team1").unwrap().id;
+    let team_category_id = category_map["team1"];
+    assert_ne!(team_role_id.get(), team_category_id.get());
+}
+
+fn role_definition(name: &str, permissions: Permissions) -> RoleDefinition {
+    RoleDefinitionBuilder::default()
+        .name(name.to_string())
+        .permissions(permissions)
+        .build()
+        .unwrap()
+}
+
+fn text_channel_definition(
+    name: &str,
+    category: Option<u64>,
+    permissions: Vec<PermissionOverwrite>,
+) -> GuildChannelDefinition {
+    GuildChannelDefinitionBuilder::default()
+        .name(name.to_string())
+        .kind(ChannelType::Text)
+        .category(category.map(ChannelId::new))
+        .permissions(permissions)
+        .build()
+        .unwrap()
+}
+
+fn overwrite(role_id: u64, allow: Permissions) -> PermissionOverwrite {
+    PermissionOverwrite {
+        allow,
+        deny: Permissions::empty(),
+        kind: PermissionOverwriteType::Role(RoleId::new(role_id)),
+    }
+}
+
+#[test]
+fn plans_role_creations_updates_and_deletions() {
+    let mut managed = role(104, "integration");
+    managed.managed = true;
+    let mut outdated = role(102, "team1");
+    outdated.permissions = Permissions::ADMINISTRATOR;
+
+    let actions = plan_role_actions(
+        vec![
+            role(100, "@everyone"),
+            role(101, "team0"),
+            outdated,
+            role(103, "removed team"),
+            managed,
+        ],
+        &[
+            role_definition("@everyone", Permissions::empty()),
+            role_definition("team0", Permissions::empty()),
+            role_definition("team1", Permissions::empty()),
+            role_definition("team2", Permissions::empty()),
+        ],
+    );
+
+    let summary: Vec<_> = actions
+        .iter()
+        .map(|action| match action {
+            RoleSyncAction::Create(definition) => format!("create {}", definition.name),
+            RoleSyncAction::Update(role, _) => format!("update {}", role.name),
+            RoleSyncAction::Delete(role) => format!("delete {}", role.name),
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        ["update team1", "create team2", "delete removed team"]
+    );
+}
+
+#[test]
+fn deletes_duplicated_roles_and_recreates_them() {
+    let actions = plan_role_actions(
+        vec![role(101, "team1"), role(102, "team1")],
+        &[role_definition("team1", Permissions::empty())],
+    );
+
+    assert!(matches!(&actions[0], RoleSyncAction::Delete(role) if role.id == RoleId::new(101)));
+    assert!(matches!(&actions[1], RoleSyncAction::Delete(role) if role.id == RoleId::new(102)));
+    assert!(
+        matches!(&actions[2], RoleSyncAction::Create(definition) if definition.name == "team1")
+    );
+    assert_eq!(actions.len(), 3);
+}
+
+#[test]
+fn ignores_order_of_permission_overwrites() {
+    let mut current = channel(11, "random", ChannelType::Text, None);
+    current.permission_overwrites = vec![
+        overwrite(102, Permissions::VIEW_CHANNEL),
+        overwrite(101, Permissions::VIEW_CHANNEL),
+    ];
+
+    let actions = plan_channel_actions(
+        vec![current],
+        &[text_channel_definition(
+            "random",
+            None,
+            vec![
+                overwrite(101, Permissions::VIEW_CHANNEL),
+                overwrite(102, Permissions::VIEW_CHANNEL),
+            ],
+        )],
+    );
+
+    assert!(actions.is_empty());
+}
+
+#[test]
+fn plans_channel_updates_with_differing_overwrites() {
+    let mut current = channel(11, "random", ChannelType::Text, None);
+    current.permission_overwrites = vec![overwrite(101, Permissions::VIEW_CHANNEL)];
+
+    let actions = plan_channel_actions(
+        vec![current, channel(12, "sponsors", ChannelType::Text, None)],
+        &[
+            text_channel_definition(
+                "random",
+                None,
+                vec![overwrite(
+                    101,
+                    Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
+                )],
+            ),
+            text_channel_definition("rules", None, Vec::new()),
+        ],
+    );
+
+    assert!(
+        matches!(&actions[0], ChannelSyncAction::Update(channel, _) if channel.name == "random")
+    );
+    assert!(
+        matches!(&actions[1], ChannelSyncAction::Create(definition) if definition.name == "rules")
+    );
+    assert!(
+        matches!(&actions[2], ChannelSyncAction::Delete(channel) if channel.name == "sponsors")
+    );
+    assert_eq!(actions.len(), 3);
+
+    let mut plan = SyncPlan::default();
+    plan.add_roles(vec![role(101, "team1")], Vec::new());
+    plan.add_channels(actions);
+
+    let output = plan.to_string();
+    assert!(output.contains("~ update text channel \"random\" (category: -)"));
+    assert!(output.contains("- role \"team1\": allow [View Channel], deny []"));
+    assert!(output.contains("+ role \"team1\": allow [Send Messages, View Channel], deny []"));
+    assert!(output.contains("+ create text channel \"rules\" (category: -)"));
+    assert!(output.contains("- delete text channel \"sponsors\" (category: -)"));
+}
+
+fn staff_role_definition() -> RoleDefinition {
+    RoleDefinitionBuilder::default()
+        .name(String::from("ICTSC2025 Staff"))
+        .permissions(Permissions::empty())
+        .previous_names(vec![
+            String::from("ICTSC2024 Staff"),
+            String::from("ICTSC2023 Staff"),
+        ])
+        .build()
+        .unwrap()
+}
+
+fn staff_category_definition() -> GuildChannelDefinition {
+    GuildChannelDefinitionBuilder::default()
+        .name(String::from("ICTSC2025 Staff"))
+        .kind(ChannelType::Category)
+        .previous_names(vec![String::from("ICTSC2024 Staff")])
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn renames_role_with_previous_name_in_place() {
+    let actions = plan_role_actions(
+        vec![role(101, "ICTSC2024 Staff")],
+        &[staff_role_definition()],
+    );
+
+    assert!(matches!(
+        &actions[..],
+        [RoleSyncAction::Update(role, definition)]
+            if role.id == RoleId::new(101) && definition.name == "ICTSC2025 Staff"
+    ));
+}
+
+#[test]
+fn prefers_role_with_current_name_over_previous_name() {
+    let actions = plan_role_actions(
+        vec![role(101, "ICTSC2024 Staff"), role(102, "ICTSC2025 Staff")],
+        &[staff_role_definition()],
+    );
+
+    assert!(matches!(
+        &actions[..],
+        [RoleSyncAction::Delete(role)] if role.id == RoleId::new(101)
+    ));
+}
+
+#[test]
+fn recreates_role_when_several_roles_have_previous_names() {
+    let actions = plan_role_actions(
+        vec![role(101, "ICTSC2024 Staff"), role(102, "ICTSC2023 Staff")],
+        &[staff_role_definition()],
+    );
+
+    assert!(
+        matches!(&actions[0], RoleSyncAction::Create(definition) if definition.name == "ICTSC2025 Staff")
+    );
+    assert!(matches!(&actions[1], RoleSyncAction::Delete(role) if role.id == RoleId::new(101)));
+    assert!(matches!(&actions[2], RoleSyncAction::Delete(role) if role.id == RoleId::new(102)));
+    assert_eq!(actions.len(), 3);
+}
+
+#[test]
+fn renames_category_with_previous_name_in_place() {
+    let actions = plan_channel_actions(
+        vec![channel(10, "ICTSC2024 Staff", ChannelType::Category, None)],
+        &[staff_category_definition()],
+    );
+
+    assert!(matches!(
+        &actions[..],
+        [ChannelSyncAction::Update(channel, definition)]
+            if channel.id == ChannelId::new(10) && definition.name == "ICTSC2025 Staff"
+    ));
+
+    let mut plan = SyncPlan::default();
+    plan.add_categories(Vec::new(), actions);
+    assert!(plan
+        .to_string()
+        .contains("      name: \"ICTSC2024 Staff\" -> \"ICTSC2025 Staff\""));
+}
+
+#[test]
+fn does_not_rename_channel_of_other_kind() {
+    let actions = plan_channel_actions(
+        vec![channel(10, "ICTSC2024 Staff", ChannelType::Text, None)],
+        &[staff_category_definition()],
+    );
+
+    assert!(
+        matches!(&actions[0], ChannelSyncAction::Create(definition) if definition.name == "ICTSC2025 Staff")
+    );
+    assert!(
+        matches!(&actions[1], ChannelSyncAction::Delete(channel) if channel.id == ChannelId::new(10))
+    );
+    assert_eq!(actions.len(), 2);
+}
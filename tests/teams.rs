@@ -0,0 +1,72 @@
+use bot::models::find_team;
+use bot::models::search_teams;
+use bot::models::Team;
+
+fn teams() -> Vec<Team> {
+    [
+        ("team1", "Team Alpha"),
+        ("team2", "Team Beta"),
+        ("team10", "ＴＥＡＭ Gamma"),
+    ]
+    .into_iter()
+    .map(|(id, role_name)| Team {
+        id: id.to_string(),
+        role_name: role_name.to_string(),
+        team_code: format!("{}-code", id),
+    })
+    .collect()
+}
+
+fn ids(teams: &[Team], query: &str) -> Vec<String> {
+    search_teams(teams, query)
+        .into_iter()
+        .map(|team| team.id.clone())
+        .collect()
+}
+
+#[test]
+fn lists_all_teams_for_empty_query() {
+    assert_eq!(ids(&teams(), ""), ["team1", "team2", "team10"]);
+}
+
+#[test]
+fn searches_teams_by_id_ignoring_case_and_width() {
+    let teams = teams();
+    assert_eq!(ids(&teams, "TEAM1"), ["team1", "team10"]);
+    assert_eq!(ids(&teams, "ｔｅａｍ２"), ["team2"]);
+}
+
+#[test]
+fn searches_teams_by_role_name_ignoring_case_and_width() {
+    let teams = teams();
+    assert_eq!(ids(&teams, "alpha"), ["team1"]);
+    assert_eq!(ids(&teams, "BETA"), ["team2"]);
+    // ロール名側が全角の場合も一致する。
+    assert_eq!(ids(&teams, "team gamma"), ["team10"]);
+}
+
+#[test]
+fn returns_no_teams_for_unknown_query() {
+    assert!(ids(&teams(), "delta").is_empty());
+}
+
+#[test]
+fn finds_team_specified_by_staff() {
+    let teams = teams();
+    let find = |team_id| find_team(&teams, team_id).map(|team| team.id.as_str());
+
+    assert_eq!(find("team1"), Some("team1"));
+    assert_eq!(find("team10"), Some("team10"));
+    // 手入力された場合に備え、大文字や全角の入力も受け付ける。
+    assert_eq!(find("TEAM2"), Some("team2"));
+    assert_eq!(find("ｔｅａｍ１"), Some("team1"));
+}
+
+#[test]
+fn does_not_find_team_by_partial_id_or_role_name() {
+    let teams = teams();
+
+    assert!(find_team(&teams, "team").is_none());
+    assert!(find_team(&teams, "Team Alpha").is_none());
+    assert!(find_team(&teams, "team3").is_none());
+}
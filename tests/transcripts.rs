@@ -0,0 +1,71 @@
+use bot::services::transcripts::Transcript;
+use bot::services::transcripts::TranscriptAttachment;
+use bot::services::transcripts::TranscriptMessage;
+use chrono::TimeZone;
+use chrono::Utc;
+
+fn transcript() -> Transcript {
+    let message = |id: u64, author_name: &str, content: &str| TranscriptMessage {
+        id,
+        author_id: id * 10,
+        author_name: String::from(author_name),
+        created_at: Utc.with_ymd_and_hms(2025, 3, 1, 10, id as u32, 0).unwrap(),
+        content: String::from(content),
+        attachments: vec![],
+    };
+
+    let mut question = message(1, "alice", "初期条件で\npingが通りません。");
+    question.attachments.push(TranscriptAttachment {
+        filename: String::from("ping.png"),
+        url: String::from("https://cdn.discordapp.com/attachments/1/2/ping.png"),
+    });
+
+    Transcript {
+        thread_id: 123,
+        thread_name: String::from("[ABC] 初期条件について"),
+        team_id: Some(String::from("team1")),
+        exported_at: Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap(),
+        messages: vec![question, message(2, "staff", "仕様です。")],
+    }
+}
+
+#[test]
+fn renders_transcript_as_markdown() {
+    let markdown = transcript().to_markdown();
+
+    assert!(markdown.starts_with("# [ABC] 初期条件について\n"));
+    assert!(markdown.contains("- チーム: team1\n"));
+    assert!(markdown
+        .contains("## alice (10) 2025-03-01T10:01:00Z\n\n> 初期条件で\n> pingが通りません。\n"));
+    assert!(markdown.contains(
+        "- 添付ファイル: [ping.png](https://cdn.discordapp.com/attachments/1/2/ping.png)"
+    ));
+    // メッセージは古い順に並べる。
+    assert!(markdown.find("## alice").unwrap() < markdown.find("## staff").unwrap());
+}
+
+#[tokio::test]
+async fn writes_transcript_as_markdown_and_json() {
+    let directory = std::env::temp_dir().join(format!(
+        "ictsc-discord-bot-transcripts-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&directory);
+
+    let (markdown_path, json_path) = transcript().write_to(&directory).await.unwrap();
+    assert_eq!(markdown_path, directory.join("team1-123.md"));
+    assert_eq!(json_path, directory.join("team1-123.json"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(json["thread_id"], 123);
+    assert_eq!(json["team_id"], "team1");
+    assert_eq!(json["messages"][0]["author_name"], "alice");
+    assert_eq!(
+        json["messages"][0]["attachments"][0]["url"],
+        "https://cdn.discordapp.com/attachments/1/2/ping.png"
+    );
+    assert_eq!(json["messages"][1]["content"], "仕様です。");
+}